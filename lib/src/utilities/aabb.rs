@@ -0,0 +1,143 @@
+use super::{interval::Interval, point::Point3, ray::Ray};
+
+/// Smallest thickness an axis of a bounding box may have, so that
+/// flat primitives (which are zero-thick along one axis) can still be hit
+const MIN_AXIS_SIZE: f64 = 0.0001;
+
+/// Axis-aligned bounding box, stored as one interval per axis
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }.pad_to_minimums()
+    }
+
+    /// Treat the two points a and b as extrema for the bounding box,
+    /// so we don't require a particular minimum/maximum coordinate order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        let x = Interval::new(a.get_x().min(b.get_x()), a.get_x().max(b.get_x()));
+        let y = Interval::new(a.get_y().min(b.get_y()), a.get_y().max(b.get_y()));
+        let z = Interval::new(a.get_z().min(b.get_z()), a.get_z().max(b.get_z()));
+        Self::new(x, y, z)
+    }
+
+    /// The box enclosing both input boxes
+    pub fn surrounding(box0: &Aabb, box1: &Aabb) -> Self {
+        Self {
+            x: Interval::from_intervals(box0.x, box1.x),
+            y: Interval::from_intervals(box0.y, box1.y),
+            z: Interval::from_intervals(box0.z, box1.z),
+        }
+    }
+
+    pub fn axis_interval(&self, axis: usize) -> Interval {
+        match axis {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0
+    }
+
+    /// False for boxes of infinite objects, such as planes
+    pub fn is_bounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .all(|axis| axis.min.is_finite() && axis.max.is_finite())
+    }
+
+    /// Index of the axis with the largest extent (0 = x, 1 = y, 2 = z)
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() {
+                0
+            } else {
+                2
+            }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    /// Surface area of the box, used by the surface-area heuristic
+    /// when building a bounding volume hierarchy
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * ((dx * dy) + (dy * dz) + (dz * dx))
+    }
+
+    /// Slab test: the ray hits the box if the parameter intervals
+    /// in which it is inside each pair of slabs overlap
+    pub fn hit(&self, ray: &Ray, ray_interval: Interval) -> bool {
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+        let mut t_min = ray_interval.min;
+        let mut t_max = ray_interval.max;
+
+        for (axis, origin_component, direction_component) in [
+            (self.x, origin.get_x(), direction.get_x()),
+            (self.y, origin.get_y(), direction.get_y()),
+            (self.z, origin.get_z(), direction.get_z()),
+        ] {
+            let inv_direction = 1.0 / direction_component;
+            let t0 = (axis.min - origin_component) * inv_direction;
+            let t1 = (axis.max - origin_component) * inv_direction;
+
+            // f64::max/min ignore NaN, which keeps rays lying on a slab boundary well behaved
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Adjust the box so that no side is narrower than some delta, padding if necessary
+    fn pad_to_minimums(mut self) -> Self {
+        if self.x.size() < MIN_AXIS_SIZE {
+            self.x = self.x.expand(MIN_AXIS_SIZE);
+        }
+        if self.y.size() < MIN_AXIS_SIZE {
+            self.y = self.y.expand(MIN_AXIS_SIZE);
+        }
+        if self.z.size() < MIN_AXIS_SIZE {
+            self.z = self.z.expand(MIN_AXIS_SIZE);
+        }
+        self
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
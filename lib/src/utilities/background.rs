@@ -0,0 +1,252 @@
+use std::{f64::consts::PI, io, path::Path, sync::Arc};
+
+use super::{color::Color, hdr, ray::Ray, vector3::Vector3};
+
+/// What a ray sees when it escapes the scene without hitting anything
+#[derive(Clone)]
+pub enum Background {
+    /// The same color in every direction
+    Solid(Color),
+    /// Linear blend from `bottom` (looking straight down) to `top` (looking straight up)
+    Gradient { bottom: Color, top: Color },
+    /// An equirectangular (latitude/longitude) HDR image surrounding the scene
+    Environment(EnvironmentMap),
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction: Vector3 = ray.get_direction().unit_vector();
+                let a: f64 = 0.5 * (unit_direction.get_y() + 1.0);
+                (*bottom * (1.0 - a)) + (*top * a)
+            }
+            Background::Environment(environment_map) => {
+                environment_map.lookup(ray.get_direction().unit_vector())
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    /// The blue-white sky of the "Ray Tracing in One Weekend" book
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+/// An equirectangular environment map.
+/// The top row of the image is straight up (+y), the left and right edges meet at -x,
+/// and `rotation` (in degrees) turns the map about the vertical axis.
+/// Directions can be importance-sampled proportionally to the luminance of the map.
+#[derive(Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Arc<Vec<Color>>,
+    rotation: f64, // In radians
+    intensity: f64,
+    distribution: Arc<Distribution2D>,
+}
+
+impl EnvironmentMap {
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+        rotation: f64,
+        intensity: f64,
+    ) -> Self {
+        // Weight each texel by its luminance and by the solid angle it covers (sin theta),
+        // so bright texels near the poles are not oversampled
+        let weights: Vec<f64> = (0..height)
+            .flat_map(|y_index| {
+                let sin_theta = (PI * (y_index as f64 + 0.5) / height as f64).sin();
+                let pixels = &pixels;
+                (0..width).map(move |x_index| {
+                    luminance(pixels[(y_index * width) + x_index]).max(0.0) * sin_theta
+                })
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            distribution: Arc::new(Distribution2D::new(&weights, width, height)),
+            pixels: Arc::new(pixels),
+            rotation: rotation.to_radians(),
+            intensity,
+        }
+    }
+
+    /// Loads a Radiance `.hdr` file
+    pub fn from_hdr_file(path: &Path, rotation: f64, intensity: f64) -> io::Result<Self> {
+        let image = hdr::read_hdr(path)?;
+        let (width, height) = (image.get_width(), image.get_height());
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "environment map is empty",
+            ));
+        }
+        Ok(Self::new(
+            width,
+            height,
+            image.pixels().to_vec(),
+            rotation,
+            intensity,
+        ))
+    }
+
+    /// Radiance arriving from `direction` (which must have unit length)
+    pub fn lookup(&self, direction: Vector3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let x_index = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y_index = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[(y_index * self.width) + x_index] * self.intensity
+    }
+
+    /// Picks a direction with probability proportional to the brightness of the map.
+    /// Takes two uniform random numbers in [0, 1) and returns the direction
+    /// together with its probability density with respect to solid angle.
+    pub fn sample(&self, random_u: f64, random_v: f64) -> (Vector3, f64) {
+        let ((u, v), map_pdf) = self.distribution.sample(random_u, random_v);
+        let direction = self.uv_to_direction(u, v);
+        let sin_theta = (PI * v).sin();
+        if map_pdf == 0.0 || sin_theta == 0.0 {
+            return (direction, 0.0);
+        }
+        (direction, map_pdf / (2.0 * PI * PI * sin_theta))
+    }
+
+    /// Probability density (with respect to solid angle) that `sample` returns `direction`
+    pub fn pdf(&self, direction: Vector3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn direction_to_uv(&self, direction: Vector3) -> (f64, f64) {
+        let theta = direction.get_y().clamp(-1.0, 1.0).acos();
+        let phi = direction.get_z().atan2(direction.get_x()) + self.rotation;
+        let u = (phi + PI).rem_euclid(2.0 * PI) / (2.0 * PI);
+        (u, theta / PI)
+    }
+
+    fn uv_to_direction(&self, u: f64, v: f64) -> Vector3 {
+        let theta = v * PI;
+        let phi = (u * 2.0 * PI) - PI - self.rotation;
+        Vector3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        )
+    }
+}
+
+fn luminance(color: Color) -> f64 {
+    (0.2126 * color.get_r()) + (0.7152 * color.get_g()) + (0.0722 * color.get_b())
+}
+
+/// Piecewise-constant 1D distribution over [0, 1) built from non-negative weights
+struct Distribution1D {
+    weights: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    fn new(weights: &[f64]) -> Self {
+        let count = weights.len() as f64;
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        cdf.push(0.0);
+        for weight in weights.iter() {
+            cdf.push(cdf[cdf.len() - 1] + (weight / count));
+        }
+
+        let integral = cdf[weights.len()];
+        if integral > 0.0 {
+            cdf.iter_mut().for_each(|value| *value /= integral);
+        } else {
+            // All weights are zero: fall back to a uniform distribution
+            cdf.iter_mut()
+                .enumerate()
+                .for_each(|(index, value)| *value = index as f64 / count);
+        }
+
+        Self {
+            weights: weights.to_vec(),
+            cdf,
+            integral,
+        }
+    }
+
+    /// Returns the sampled position in [0, 1), its pdf and the index of the bucket it falls in
+    fn sample(&self, random: f64) -> (f64, f64, usize) {
+        // Last cdf entry that is <= random
+        let index = self
+            .cdf
+            .partition_point(|value| *value <= random)
+            .saturating_sub(1)
+            .min(self.weights.len() - 1);
+
+        let bucket_width = self.cdf[index + 1] - self.cdf[index];
+        let offset = match bucket_width > 0.0 {
+            true => (random - self.cdf[index]) / bucket_width,
+            false => 0.0,
+        };
+
+        let position = (index as f64 + offset) / self.weights.len() as f64;
+        (position, self.pdf_at(index), index)
+    }
+
+    fn pdf_at(&self, index: usize) -> f64 {
+        match self.integral > 0.0 {
+            true => self.weights[index] / self.integral,
+            false => 1.0,
+        }
+    }
+}
+
+/// Piecewise-constant 2D distribution over [0, 1)^2:
+/// a marginal distribution picks the row, then that row's conditional picks the column
+struct Distribution2D {
+    conditionals: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    fn new(weights: &[f64], width: usize, height: usize) -> Self {
+        let conditionals: Vec<Distribution1D> = weights
+            .chunks_exact(width)
+            .take(height)
+            .map(Distribution1D::new)
+            .collect();
+        let row_integrals: Vec<f64> = conditionals.iter().map(|row| row.integral).collect();
+        Self {
+            marginal: Distribution1D::new(&row_integrals),
+            conditionals,
+        }
+    }
+
+    fn sample(&self, random_u: f64, random_v: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(random_v);
+        let (u, pdf_u, _) = self.conditionals[row].sample(random_u);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row = ((v * self.conditionals.len() as f64) as usize).min(self.conditionals.len() - 1);
+        let conditional = &self.conditionals[row];
+        let column =
+            ((u * conditional.weights.len() as f64) as usize).min(conditional.weights.len() - 1);
+        conditional.pdf_at(column) * self.marginal.pdf_at(row)
+    }
+}
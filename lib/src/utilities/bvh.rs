@@ -0,0 +1,297 @@
+use super::{
+    aabb::Aabb, geometry::Hittable, hit_record::HitRecord, interval::Interval, point::Point3,
+    ray::Ray, sampler::Sampler,
+};
+
+/// Number of buckets the centroids are binned into when evaluating the SAH
+const SAH_BUCKETS: usize = 12;
+/// Cost of visiting a node relative to intersecting one primitive
+const TRAVERSAL_COST: f64 = 0.125;
+/// Above this many primitives a leaf is always split further
+const MAX_LEAF_SIZE: usize = 4;
+/// Subtrees larger than this are built in parallel with rayon
+const PARALLEL_BUILD_THRESHOLD: usize = 4096;
+
+/// Bounding volume hierarchy over a list of objects.
+/// Rays only visit the children whose boxes they hit,
+/// so the cost per ray grows logarithmically with the number of objects.
+pub struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+    bbox: Aabb,
+}
+
+/// An object together with its (cached) bounding box and box centroid
+struct BuildItem {
+    object: Box<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+impl BvhNode {
+    /// Builds the hierarchy using a binned surface-area heuristic (SAH).
+    /// Unbounded objects (planes) cannot be sorted into boxes; they are kept next
+    /// to the hierarchy of the bounded ones and tested by every ray.
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        let (items, unbounded): (Vec<BuildItem>, Vec<BuildItem>) = objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                BuildItem {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .partition(|item| item.bbox.is_bounded());
+
+        let bounded = Self::from_items(items);
+        match unbounded.is_empty() {
+            true => bounded,
+            false => {
+                let unbounded = Self::make_leaf(unbounded);
+                let bbox = Aabb::surrounding(&bounded.bbox, &unbounded.bounding_box());
+                Self {
+                    left: Box::new(bounded),
+                    right: unbounded,
+                    bbox,
+                }
+            }
+        }
+    }
+
+    fn from_items(items: Vec<BuildItem>) -> Self {
+        match Self::build(items) {
+            Ok(node) => node,
+            Err(leaf) => {
+                // Too few objects to be worth splitting: wrap the leaf so we still return a node
+                let bbox = leaf.bounding_box();
+                Self {
+                    left: leaf,
+                    right: Box::new(Vec::<Box<dyn Hittable>>::new()),
+                    bbox,
+                }
+            }
+        }
+    }
+
+    /// Recursively splits the items. Returns Err with a leaf (a plain object list)
+    /// when splitting is not worth it according to the SAH.
+    fn build(mut items: Vec<BuildItem>) -> Result<Self, Box<dyn Hittable>> {
+        let bbox = Self::bounds_of(&items);
+
+        let split = match Self::find_sah_split(&items, &bbox) {
+            Some(split) => split,
+            None => return Err(Self::make_leaf(items)),
+        };
+
+        let right_items: Vec<BuildItem> = match split {
+            Split::Bucket {
+                axis,
+                bucket,
+                min,
+                extent,
+            } => {
+                let (left, right): (Vec<BuildItem>, Vec<BuildItem>) =
+                    items.into_iter().partition(|item| {
+                        Self::bucket_index(item.centroid, axis, min, extent) <= bucket
+                    });
+                items = left;
+                right
+            }
+            Split::Median { axis } => {
+                items.sort_by(|a, b| {
+                    Self::axis_value(a.centroid, axis)
+                        .total_cmp(&Self::axis_value(b.centroid, axis))
+                });
+                let mid = items.len() / 2;
+                items.split_off(mid)
+            }
+        };
+
+        let (left, right) = if items.len() + right_items.len() > PARALLEL_BUILD_THRESHOLD {
+            rayon::join(
+                || Self::build_child(items),
+                || Self::build_child(right_items),
+            )
+        } else {
+            (Self::build_child(items), Self::build_child(right_items))
+        };
+
+        Ok(Self { left, right, bbox })
+    }
+
+    fn build_child(mut items: Vec<BuildItem>) -> Box<dyn Hittable> {
+        if items.len() == 1 {
+            return items.pop().map(|item| item.object).unwrap();
+        }
+        match Self::build(items) {
+            Ok(node) => Box::new(node),
+            Err(leaf) => leaf,
+        }
+    }
+
+    fn make_leaf(items: Vec<BuildItem>) -> Box<dyn Hittable> {
+        Box::new(
+            items
+                .into_iter()
+                .map(|item| item.object)
+                .collect::<Vec<Box<dyn Hittable>>>(),
+        )
+    }
+
+    fn bounds_of(items: &[BuildItem]) -> Aabb {
+        items.iter().fold(Aabb::EMPTY, |bbox, item| {
+            Aabb::surrounding(&bbox, &item.bbox)
+        })
+    }
+
+    fn axis_value(point: Point3, axis: usize) -> f64 {
+        match axis {
+            1 => point.get_y(),
+            2 => point.get_z(),
+            _ => point.get_x(),
+        }
+    }
+
+    fn bucket_index(centroid: Point3, axis: usize, min: f64, extent: f64) -> usize {
+        let offset = (Self::axis_value(centroid, axis) - min) / extent;
+        ((offset * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+    }
+
+    /// Evaluates the SAH cost of splitting after every bucket boundary on every axis
+    /// and returns the cheapest split, or None when a leaf is cheaper.
+    fn find_sah_split(items: &[BuildItem], bbox: &Aabb) -> Option<Split> {
+        let parent_area = bbox.surface_area();
+        let leaf_cost = items.len() as f64;
+        let mut best: Option<(f64, Split)> = None;
+
+        for axis in 0..3 {
+            let centroid_range = items
+                .iter()
+                .map(|item| Self::axis_value(item.centroid, axis))
+                .fold(Interval::EMPTY, |range, value| {
+                    Interval::from_intervals(range, Interval::new(value, value))
+                });
+            let extent = centroid_range.size();
+            if extent <= 0.0 || parent_area <= 0.0 || !parent_area.is_finite() {
+                continue;
+            }
+
+            let mut bucket_counts = [0usize; SAH_BUCKETS];
+            let mut bucket_bounds = [Aabb::EMPTY; SAH_BUCKETS];
+            for item in items.iter() {
+                let index = Self::bucket_index(item.centroid, axis, centroid_range.min, extent);
+                bucket_counts[index] += 1;
+                bucket_bounds[index] = Aabb::surrounding(&bucket_bounds[index], &item.bbox);
+            }
+
+            // Sweep from the right to get the area and count of every suffix of buckets
+            let mut right_area = [0.0; SAH_BUCKETS];
+            let mut right_count = [0usize; SAH_BUCKETS];
+            let mut running_bounds = Aabb::EMPTY;
+            let mut running_count = 0;
+            for index in (1..SAH_BUCKETS).rev() {
+                running_bounds = Aabb::surrounding(&running_bounds, &bucket_bounds[index]);
+                running_count += bucket_counts[index];
+                right_area[index] = running_bounds.surface_area();
+                right_count[index] = running_count;
+            }
+
+            let mut left_bounds = Aabb::EMPTY;
+            let mut left_count = 0;
+            for bucket in 0..(SAH_BUCKETS - 1) {
+                left_bounds = Aabb::surrounding(&left_bounds, &bucket_bounds[bucket]);
+                left_count += bucket_counts[bucket];
+                let right = bucket + 1;
+                if left_count == 0 || right_count[right] == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST
+                    + ((left_bounds.surface_area() * left_count as f64)
+                        + (right_area[right] * right_count[right] as f64))
+                        / parent_area;
+                if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                    best = Some((
+                        cost,
+                        Split::Bucket {
+                            axis,
+                            bucket,
+                            min: centroid_range.min,
+                            extent,
+                        },
+                    ));
+                }
+            }
+        }
+
+        match best {
+            Some((cost, split)) if cost < leaf_cost || items.len() > MAX_LEAF_SIZE => Some(split),
+            Some(_) => None,
+            // All centroids coincide (or the boxes are unbounded): SAH cannot
+            // tell the objects apart, so fall back to an even split
+            None if items.len() > MAX_LEAF_SIZE => Some(Split::Median {
+                axis: bbox.longest_axis(),
+            }),
+            None => None,
+        }
+    }
+
+    /// The closer of the hits of both children, each tested with `hit`; the right child
+    /// only in front of the left child's hit
+    fn closest_hit<'a>(
+        &'a self,
+        ray: Ray,
+        ray_interval: Interval,
+        mut hit: impl FnMut(&'a dyn Hittable, Interval) -> Option<HitRecord<'a>>,
+    ) -> Option<HitRecord<'a>> {
+        if !self.bbox.hit(&ray, ray_interval) {
+            return None;
+        }
+
+        let hit_left = hit(self.left.as_ref(), ray_interval);
+        let t_max = hit_left
+            .as_ref()
+            .map(|hit| hit.parameter)
+            .unwrap_or(ray_interval.max);
+        let hit_right = hit(self.right.as_ref(), Interval::new(ray_interval.min, t_max));
+
+        hit_right.or(hit_left)
+    }
+}
+
+enum Split {
+    Bucket {
+        axis: usize,
+        bucket: usize,
+        min: f64,
+        extent: f64,
+    },
+    Median {
+        axis: usize,
+    },
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        self.closest_hit(ray, ray_interval, |child, interval| {
+            child.hit(ray, interval)
+        })
+    }
+
+    fn sample_hit(
+        &self,
+        ray: Ray,
+        ray_interval: Interval,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.closest_hit(ray, ray_interval, |child, interval| {
+            child.sample_hit(ray, interval, sampler)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -1,16 +1,23 @@
-use rand::Rng;
+use std::ops::{AddAssign, Mul, MulAssign};
+
 use rayon::prelude::*;
 
 use super::{
+    background::Background,
     color::Color,
     geometry::Hittable,
+    hit_record::HitRecord,
+    image::Image,
     interval::Interval,
-    material::Scatter,
     point::Point3,
     ray::Ray,
+    sampler::{Sampler, SamplerKind},
+    spectrum::{SampledSpectrum, SampledWavelengths},
     vector3::{Cross, Vector3},
 };
-use std::{fs::File, io::Write};
+
+/// Rays start this far from the surface they leave, so they do not hit it again
+const SHADOW_EPSILON: f64 = 0.001;
 
 #[derive(Default, Clone)]
 pub struct CameraFrameBasis {
@@ -29,13 +36,24 @@ pub struct Camera {
     pub look_from: Point3,
     pub look_at: Point3,
     pub vertical_camera_up: Vector3,
-    image_height: i32,
+    pub defocus_angle: f64, // Variation angle of rays through each pixel, in degrees
+    pub focus_distance: f64, // Distance from camera look_from point to plane of perfect focus
+    pub background: Background, // Seen by rays that escape the scene
+    pub image_height: i32,  // Derived from image_width and aspect_ratio when 0
+    pub seed: u64,          // Seed of the per-pixel, per-sample random streams
+    pub sampler: SamplerKind, // How the sample values of each pixel are generated
+    pub shutter_open: f64,  // Camera rays are sent at times between shutter_open
+    pub shutter_close: f64, // and shutter_close; moving objects blur over that interval
+    pub spectral: bool,     // Trace sampled wavelengths instead of RGB, for dispersion
+
     camera_center: Point3,
     pixel00_loc: Point3,      // Location of pixel 0, 0
     pixel_delta_u: Vector3,   // Offset to pixel to the right
     pixel_delta_v: Vector3,   // Offset to pixel below
     pixel_samples_scale: f64, //  Color scale factor for a sum of pixel samples
     frame_basis: CameraFrameBasis,
+    defocus_disk_u: Vector3, // Defocus disk horizontal radius
+    defocus_disk_v: Vector3, // Defocus disk vertical radius
 }
 
 impl Camera {
@@ -48,18 +66,23 @@ impl Camera {
             look_from: Point3::new(0.0, 0.0, 0.0),
             look_at: Point3::new(0.0, 0.1, -1.0),
             vertical_camera_up: Vector3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus_distance: 10.0,
             ..Default::default() // this is possible using the derive(Default)
         }
     }
 
-    pub fn render(&mut self, world: Vec<Box<dyn Hittable>>) {
+    /// Renders the world and returns the image. Each pixel holds the
+    /// linear color averaged over all samples of that pixel.
+    /// The sample values come from a `sampler` that depends only on `seed` and the
+    /// pixel and sample indices, so the image does not depend on thread scheduling.
+    /// `lights` holds the emitting objects of the world (copies of them), which are
+    /// sampled directly at every diffuse bounce. It may be empty.
+    /// In `spectral` mode each sample traces a few wavelengths, converted to RGB through
+    /// the CIE color matching functions before they are added to the pixel.
+    pub fn render(&mut self, world: &dyn Hittable, lights: &[Box<dyn Hittable>]) -> Image {
         self.initialize();
 
-        // Render and write to file
-        let file_path = "image_test.ppm";
-        let mut file = File::create(file_path).unwrap();
-        writeln!(file, "P3\n{} {}\n255", self.image_width, self.image_height).unwrap();
-
         let pixel_color_vec: Vec<Color> = (0..self.image_height)
             .into_par_iter()
             .flat_map(|y_index| {
@@ -67,43 +90,57 @@ impl Camera {
                 (0..self.image_width)
                     .into_par_iter()
                     .map(|x_index| {
+                        let mut sampler = self.sampler.create(self.samples_per_pixel, self.seed);
+                        // Samples are summed in order, so the floating point sum is reproducible
                         let pixel_color: Color = (0..self.samples_per_pixel)
-                            .into_par_iter()
-                            .map(|_| {
-                                let ray_sent: Ray = self.get_ray(x_index, y_index);
-                                Self::ray_color(ray_sent, self.max_depth, &world[..])
+                            .map(|sample_index| {
+                                sampler.start_pixel_sample(x_index, y_index, sample_index);
+                                let ray_sent: Ray =
+                                    self.get_ray(x_index, y_index, sampler.as_mut());
+                                self.sample_color(ray_sent, world, lights, sampler.as_mut())
                             })
-                            .sum(); // need to implement sum trait for Color
-                        pixel_color // Return the Color from the map closure
+                            .sum();
+                        pixel_color * self.pixel_samples_scale // Return the Color from the map closure
                     })
                     .collect::<Vec<Color>>() // Collect the inner Vec<Color>
             })
             .collect(); // Collect the outer Vec<Color>
 
-        for pixel in pixel_color_vec.iter() {
-            let write_res = (*pixel * self.pixel_samples_scale).write_color(&mut file);
-            if let Err(e) = write_res {
-                println!("Error in writing result to file: {}", e)
-            }
-        }
+        Image::from_pixels(
+            self.image_width as usize,
+            self.image_height as usize,
+            pixel_color_vec,
+        )
+    }
 
-        println!("Done!");
+    /// The ray that `render` would send for a sample of pixel (`loc_x`, `loc_y`)
+    pub fn sample_ray(&mut self, loc_x: i32, loc_y: i32, sampler: &mut dyn Sampler) -> Ray {
+        self.initialize();
+        self.get_ray(loc_x, loc_y, sampler)
+    }
+
+    /// Width and height of the rendered image in pixels
+    pub fn image_size(&self) -> (i32, i32) {
+        match self.image_height < 1 {
+            true => (
+                self.image_width,
+                ((self.image_width as f64 / self.aspect_ratio) as i32).max(1),
+            ),
+            false => (self.image_width, self.image_height),
+        }
     }
 
     fn initialize(&mut self) {
         // Image
-        self.image_height = (self.image_width as f64 / self.aspect_ratio) as i32;
-        if self.image_height < 1 {
-            self.image_height = 1
-        }
+        self.image_height = self.image_size().1;
 
         self.pixel_samples_scale = 1.0 / self.samples_per_pixel as f64;
         self.camera_center = self.look_from;
 
         // Camera - Viewport dimensions
-        let focal_length: f64 = (self.look_from - self.look_at).as_vec().length();
+        // The viewport sits on the focus plane, independent of where look_at is
         let theta: f64 = self.vertical_field_of_view.to_radians();
-        let viewport_height: f64 = 2.0 * focal_length * (theta / 2.0).tan();
+        let viewport_height: f64 = 2.0 * self.focus_distance * (theta / 2.0).tan();
         let viewport_width: f64 =
             viewport_height * (self.image_width as f64 / self.image_height as f64);
 
@@ -122,58 +159,319 @@ impl Camera {
 
         // Calculate the location of the upper left pixel
         let viewport_origin: Point3 = self.camera_center
-            - (self.frame_basis.w * focal_length)
+            - (self.frame_basis.w * self.focus_distance)
             - (viewport_u / 2.0)
             - (viewport_v / 2.0);
 
         self.pixel00_loc = viewport_origin + ((self.pixel_delta_u + self.pixel_delta_v) * 0.5);
+
+        // Calculate the camera defocus disk basis vectors
+        let defocus_radius: f64 =
+            self.focus_distance * (self.defocus_angle / 2.0).to_radians().tan();
+        self.defocus_disk_u = self.frame_basis.u * defocus_radius;
+        self.defocus_disk_v = self.frame_basis.v * defocus_radius;
+    }
+
+    /// Linear RGB color seen along a camera ray
+    fn sample_color(
+        &self,
+        camera_ray: Ray,
+        world: &dyn Hittable,
+        lights: &[Box<dyn Hittable>],
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        match self.spectral {
+            true => {
+                let wavelengths = SampledWavelengths::sample_uniform(sampler.get_1d());
+                let radiance: SampledSpectrum =
+                    self.ray_color(camera_ray, &wavelengths, world, lights, sampler);
+                radiance.to_xyz(&wavelengths).to_rgb()
+            }
+            false => self.ray_color(camera_ray, &(), world, lights, sampler),
+        }
+    }
+
+    /// Path tracing with next-event estimation: at every bounce off a surface that is not
+    /// purely specular one light (an object of `lights`, or the environment map) is sampled
+    /// and connected to with a shadow ray. Light found by following the BSDF is weighted
+    /// against that light sample with the power heuristic of multiple importance sampling,
+    /// so each path is counted once.
+    fn ray_color<S: PathRadiance>(
+        &self,
+        camera_ray: Ray,
+        wavelengths: &S::Wavelengths,
+        world: &dyn Hittable,
+        lights: &[Box<dyn Hittable>],
+        sampler: &mut dyn Sampler,
+    ) -> S {
+        let mut color = S::default();
+        let mut throughput = S::from_color(Color::new(1.0, 1.0, 1.0), wavelengths);
+        let mut ray = camera_ray.with_wavelength(S::hero_wavelength(wavelengths));
+        // Density of the BSDF sample that produced `ray`; None for camera rays and specular
+        // bounces, whose light the light samples never cover
+        let mut scatter_pdf: Option<f64> = None;
+        // Whether a dispersive bounce has left only the hero wavelength
+        let mut is_single_wavelength = false;
+
+        for bounce in 0..self.max_depth {
+            let Some(hit) =
+                world.sample_hit(ray, Interval::new(SHADOW_EPSILON, f64::INFINITY), sampler)
+            else {
+                // The ray escaped the scene
+                let weight = match (scatter_pdf, &self.background) {
+                    (Some(pdf), Background::Environment(environment_map)) => power_heuristic(
+                        pdf,
+                        environment_map.pdf(ray.get_direction().unit_vector())
+                            / self.light_count(lights) as f64,
+                    ),
+                    _ => 1.0,
+                };
+                color +=
+                    throughput * S::from_color(self.background.color(&ray), wavelengths) * weight;
+                break;
+            };
+
+            if hit.material.is_emissive() {
+                let weight = match scatter_pdf {
+                    Some(pdf) => {
+                        let light_pdf = self.lights_pdf(
+                            lights,
+                            ray,
+                            Interval::new(SHADOW_EPSILON, hit.parameter),
+                        );
+                        power_heuristic(pdf, light_pdf)
+                    }
+                    None => 1.0,
+                };
+                let emitted = hit.material.emitted(hit.u, hit.v, hit.point);
+                color += throughput * S::from_color(emitted, wavelengths) * weight;
+            }
+
+            // Lights are sampled before the BSDF picks a direction, as `eval` covers every
+            // non-specular part of the material whichever part the bounce goes on with
+            let is_last_bounce = bounce + 1 >= self.max_depth;
+            if hit.material.has_non_specular() && !is_last_bounce {
+                color += throughput
+                    * self.sample_light::<S>(ray, &hit, wavelengths, world, lights, sampler);
+            }
+            let Some(scatter) = hit.material.scatter(ray, &hit, sampler) else {
+                break;
+            };
+
+            throughput *= S::from_color(scatter.attenuation, wavelengths);
+            if hit.material.is_dispersive() && !is_single_wavelength {
+                throughput = throughput.terminate_secondary();
+                is_single_wavelength = true;
+            }
+            scatter_pdf = scatter.pdf;
+            ray = scatter
+                .scattered_ray
+                .with_wavelength(S::hero_wavelength(wavelengths));
+        }
+
+        color
     }
 
-    fn ray_color(ray: Ray, depth: i32, world: &[Box<dyn Hittable>]) -> Color {
-        // If we've exceeded the ray bounce limit, no more light is gathered.
-        if depth <= 0 {
-            return Color::default();
+    /// Light arriving at the hit point from one randomly chosen light, times the BSDF,
+    /// divided by the probability of the sample and weighted against BSDF sampling
+    fn sample_light<S: PathRadiance>(
+        &self,
+        ray: Ray,
+        hit: &HitRecord,
+        wavelengths: &S::Wavelengths,
+        world: &dyn Hittable,
+        lights: &[Box<dyn Hittable>],
+        sampler: &mut dyn Sampler,
+    ) -> S {
+        // Always draw the same sample dimensions, whichever light is chosen
+        let light_choice = sampler.get_1d();
+        let light_sample = sampler.get_2d();
+        let light_count = self.light_count(lights);
+        if light_count == 0 {
+            return S::default();
         }
+        let index = ((light_choice * light_count as f64) as usize).min(light_count - 1);
 
-        if let Some(hit) = world.hit(ray, Interval::new(0.001, f64::INFINITY)) {
-            if let Some(scatter) = hit.material.scatter(ray, &hit) {
-                let Scatter {
-                    scattered_ray,
-                    attenuation,
-                } = scatter;
-                return (Self::ray_color(scattered_ray, depth - 1, world)) * attenuation;
-            } else {
-                return Color::new(0.0, 0.0, 0.0);
+        let (direction, light_pdf, radiance) = match lights.get(index) {
+            Some(light) => {
+                let direction = light.random_direction(hit.point, light_sample);
+                let shadow_ray = Ray::with_time(hit.point, direction, ray.get_time());
+                let Some(light_hit) =
+                    light.hit(shadow_ray, Interval::new(SHADOW_EPSILON, f64::INFINITY))
+                else {
+                    return S::default();
+                };
+                // Anything in between casts a shadow
+                let unoccluded =
+                    Interval::new(SHADOW_EPSILON, light_hit.parameter - SHADOW_EPSILON);
+                if world.sample_hit(shadow_ray, unoccluded, sampler).is_some() {
+                    return S::default();
+                }
+                (
+                    direction,
+                    // Lights behind the sampled one are hidden from BSDF sampling too
+                    self.lights_pdf(
+                        lights,
+                        shadow_ray,
+                        Interval::new(SHADOW_EPSILON, light_hit.parameter),
+                    ),
+                    light_hit
+                        .material
+                        .emitted(light_hit.u, light_hit.v, light_hit.point),
+                )
+            }
+            None => {
+                // The last choice is the environment map
+                let Background::Environment(environment_map) = &self.background else {
+                    return S::default();
+                };
+                let (direction, pdf) = environment_map.sample(light_sample.0, light_sample.1);
+                let shadow_ray = Ray::with_time(hit.point, direction, ray.get_time());
+                if world
+                    .sample_hit(
+                        shadow_ray,
+                        Interval::new(SHADOW_EPSILON, f64::INFINITY),
+                        sampler,
+                    )
+                    .is_some()
+                {
+                    return S::default();
+                }
+                (
+                    direction,
+                    pdf / light_count as f64,
+                    environment_map.lookup(direction),
+                )
             }
+        };
+        if light_pdf <= 0.0 {
+            return S::default();
+        }
+
+        let bsdf = hit.material.eval(ray, hit, direction);
+        let scatter_pdf = hit.material.pdf(ray, hit, direction);
+        S::from_color(radiance * bsdf, wavelengths)
+            * (power_heuristic(light_pdf, scatter_pdf) / light_pdf)
+    }
+
+    /// Number of lights to choose from: the objects in `lights`, plus the environment map
+    fn light_count(&self, lights: &[Box<dyn Hittable>]) -> usize {
+        match self.background {
+            Background::Environment(_) => lights.len() + 1,
+            _ => lights.len(),
         }
+    }
 
-        // Color the background blue - Linear blending
-        let unit_direction: Vector3 = ray.get_direction().unit_vector();
-        let a: f64 = 0.5 * (unit_direction.get_y() + 1.0);
-        (Color::new(1.0, 1.0, 1.0) * (1.0 - a)) + (Color::new(0.5, 0.7, 1.0) * a)
+    /// Density with which `sample_light` picks the direction of `ray`, counting only
+    /// lights hit inside `ray_interval`
+    fn lights_pdf(&self, lights: &[Box<dyn Hittable>], ray: Ray, ray_interval: Interval) -> f64 {
+        let light_count = self.light_count(lights);
+        if light_count == 0 {
+            return 0.0;
+        }
+        let objects_pdf: f64 = lights
+            .iter()
+            .map(|light| light.pdf_value(ray, ray_interval))
+            .sum();
+        objects_pdf / light_count as f64
     }
 
-    /// Construct a camera ray originating from the origin
+    /// Construct a camera ray originating from the defocus disk
     /// and directed at randomly sampled
-    /// point around the pixel location i, j.
-    fn get_ray(&self, loc_x: i32, loc_y: i32) -> Ray {
-        let offset: Vector3 = Self::sample_square();
+    /// point around the pixel location i, j,
+    /// at a random time while the shutter is open.
+    fn get_ray(&self, loc_x: i32, loc_y: i32, sampler: &mut dyn Sampler) -> Ray {
+        let offset: Vector3 = Self::sample_square(sampler);
         let pixel_sample = self.pixel00_loc
             + (self.pixel_delta_u * (loc_x as f64 + offset.get_x()))
             + (self.pixel_delta_v * (loc_y as f64 + offset.get_y()));
 
-        let ray_direction = (pixel_sample - self.camera_center).as_vec();
+        let ray_origin = if self.defocus_angle <= 0.0 {
+            self.camera_center
+        } else {
+            self.defocus_disk_sample(sampler)
+        };
+        let ray_direction = (pixel_sample - ray_origin).as_vec();
+        // A closed shutter does not draw a sample, so still images keep their sample streams
+        let ray_time = match self.shutter_close > self.shutter_open {
+            true => {
+                self.shutter_open + (sampler.get_1d() * (self.shutter_close - self.shutter_open))
+            }
+            false => self.shutter_open,
+        };
+
+        Ray::with_time(ray_origin, ray_direction, ray_time)
+    }
 
-        Ray::new(self.camera_center, ray_direction)
+    /// Returns a random point in the camera defocus disk (the thin lens)
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
+        let point: Vector3 = Vector3::random_in_unit_disk(sampler);
+        self.camera_center
+            + (self.defocus_disk_u * point.get_x())
+            + (self.defocus_disk_v * point.get_y())
     }
 
     /// Returns the vector to a random point in the
     /// [-.5,-.5] to [+.5,+.5] unit square.
-    fn sample_square() -> Vector3 {
-        Vector3::new(
-            rand::thread_rng().r#gen::<f64>() - 0.5,
-            rand::thread_rng().r#gen::<f64>() - 0.5,
-            0.0,
-        )
+    fn sample_square(sampler: &mut dyn Sampler) -> Vector3 {
+        let (x, y) = sampler.get_2d();
+        Vector3::new(x - 0.5, y - 0.5, 0.0)
+    }
+}
+
+/// Weight of a sample taken with density `pdf` when the other strategy would have
+/// produced it with density `other_pdf` (Veach's power heuristic with exponent 2)
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (squared, other_squared) = (pdf * pdf, other_pdf * other_pdf);
+    match squared + other_squared > 0.0 {
+        true => squared / (squared + other_squared),
+        false => 0.0,
+    }
+}
+
+/// What a path carries: an RGB color, or a spectrum at the wavelengths of the path
+trait PathRadiance:
+    Copy + Default + AddAssign + MulAssign + Mul<Output = Self> + Mul<f64, Output = Self>
+{
+    type Wavelengths;
+
+    /// An RGB color (of a texture, a light or the background) as carried by the path
+    fn from_color(color: Color, wavelengths: &Self::Wavelengths) -> Self;
+
+    /// The wavelength rays are refracted with, if any
+    fn hero_wavelength(wavelengths: &Self::Wavelengths) -> Option<f64>;
+
+    fn terminate_secondary(self) -> Self;
+}
+
+impl PathRadiance for Color {
+    type Wavelengths = ();
+
+    fn from_color(color: Color, _wavelengths: &()) -> Self {
+        color
+    }
+
+    fn hero_wavelength(_wavelengths: &()) -> Option<f64> {
+        None
+    }
+
+    fn terminate_secondary(self) -> Self {
+        self
+    }
+}
+
+impl PathRadiance for SampledSpectrum {
+    type Wavelengths = SampledWavelengths;
+
+    fn from_color(color: Color, wavelengths: &SampledWavelengths) -> Self {
+        SampledSpectrum::from_rgb(color, wavelengths)
+    }
+
+    fn hero_wavelength(wavelengths: &SampledWavelengths) -> Option<f64> {
+        Some(wavelengths.hero())
+    }
+
+    fn terminate_secondary(self) -> Self {
+        SampledSpectrum::terminate_secondary(self)
     }
 }
@@ -1,10 +1,7 @@
 use std::io::Write;
 use std::iter::Sum;
 use std::ops::Add;
-use std::{
-    fs::File,
-    ops::{AddAssign, Div, Mul, MulAssign},
-};
+use std::ops::{AddAssign, Div, Mul, MulAssign};
 
 /// The color Class
 ///
@@ -31,18 +28,24 @@ impl Color {
         self.blue
     }
 
-    pub fn write_color(&mut self, file: &mut File) -> std::io::Result<()> {
+    pub fn write_color<W: Write>(&self, file: &mut W) -> std::io::Result<()> {
+        let [rbyte, gbyte, bbyte] = self.to_bytes();
+        writeln!(file, "{} {} {}", rbyte, gbyte, bbyte)
+    }
+
+    /// Gamma corrected color in the byte range [0,255]
+    pub fn to_bytes(&self) -> [u8; 3] {
         // Apply a linear to gamma transform for gamma
-        self.red = Self::linear_to_gamma_transformation(self.red);
-        self.green = Self::linear_to_gamma_transformation(self.green);
-        self.blue = Self::linear_to_gamma_transformation(self.blue);
+        let red = Self::linear_to_gamma_transformation(self.red);
+        let green = Self::linear_to_gamma_transformation(self.green);
+        let blue = Self::linear_to_gamma_transformation(self.blue);
 
         // Translate the [0,1] component values to the byte range [0,255].
-        let rbyte = (256.0 * self.red.clamp(0.0, 0.999)) as i32;
-        let gbyte = (256.0 * self.green.clamp(0.0, 0.999)) as i32;
-        let bbyte = (256.0 * self.blue.clamp(0.0, 0.999)) as i32;
-
-        writeln!(file, "{} {} {}", rbyte, gbyte, bbyte)
+        [
+            (256.0 * red.clamp(0.0, 0.999)) as u8,
+            (256.0 * green.clamp(0.0, 0.999)) as u8,
+            (256.0 * blue.clamp(0.0, 0.999)) as u8,
+        ]
     }
 
     /// Transform our data into gamma space so that an image viewer
@@ -1,44 +1,115 @@
+use std::{f64::consts::PI, sync::Arc};
+
 use super::{
-    hit_record::HitRecord, interval::Interval, material::Material, point::Point3, ray::Ray,
-    vector3::Vector3,
+    aabb::Aabb,
+    hit_record::HitRecord,
+    interval::Interval,
+    material::Material,
+    point::Point3,
+    ray::Ray,
+    sampler::Sampler,
+    vector3::{Cross, Vector3},
 };
 
+/// Rays whose direction is this close to parallel with a flat primitive miss it
+const PARALLEL_EPSILON: f64 = 1e-8;
+
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>>;
+
+    /// `hit` for the rays of a traced path, whose random decisions draw from `sampler`:
+    /// participating media pick there the distance at which the ray scatters. Surfaces make
+    /// no random decisions, so by default this is `hit`.
+    fn sample_hit(
+        &self,
+        ray: Ray,
+        ray_interval: Interval,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.hit(ray, ray_interval)
+    }
+
+    /// Axis-aligned box enclosing the whole object, used to build the BVH
+    fn bounding_box(&self) -> Aabb;
+
+    /// Probability density, with respect to solid angle at the origin of `ray`, that
+    /// `random_direction` picks the direction of `ray`. Only hits inside `ray_interval` count.
+    /// Objects that cannot be sampled as lights return zero.
+    fn pdf_value(&self, _ray: Ray, _ray_interval: Interval) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` towards a point of the object, picked with the density
+    /// of `pdf_value` from the two uniform values of `sample`
+    fn random_direction(&self, _origin: Point3, _sample: (f64, f64)) -> Vector3 {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
 }
 
 impl<T> Hittable for T
 where
     T: AsRef<[Box<dyn Hittable>]> + Send + Sync,
 {
-    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord> {
-        let t_min: f64 = ray_interval.min;
-        let t_max: f64 = ray_interval.max;
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        closest_hit(self.as_ref(), ray_interval, |object, interval| {
+            object.hit(ray, interval)
+        })
+    }
 
-        let mut closest_so_far: Option<HitRecord> = None;
+    fn sample_hit(
+        &self,
+        ray: Ray,
+        ray_interval: Interval,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        closest_hit(self.as_ref(), ray_interval, |object, interval| {
+            object.sample_hit(ray, interval, sampler)
+        })
+    }
 
-        for object in self.as_ref().iter() {
-            let t_max_local = closest_so_far
-                .as_ref()
-                .map(|hit| hit.parameter)
-                .unwrap_or(t_max);
-            if let Some(record) = object.hit(ray, Interval::new(t_min, t_max_local)) {
-                closest_so_far = Some(record);
-            }
-        }
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().iter().fold(Aabb::EMPTY, |bbox, object| {
+            Aabb::surrounding(&bbox, &object.bounding_box())
+        })
+    }
+}
 
-        closest_so_far
+/// The hit of `objects` closest along the ray, each object tested with `hit` over the part
+/// of `ray_interval` in front of the closest hit so far
+fn closest_hit<'a>(
+    objects: &'a [Box<dyn Hittable>],
+    ray_interval: Interval,
+    mut hit: impl FnMut(&'a dyn Hittable, Interval) -> Option<HitRecord<'a>>,
+) -> Option<HitRecord<'a>> {
+    let mut closest_so_far: Option<HitRecord> = None;
+
+    for object in objects.iter() {
+        let t_max_local = closest_so_far
+            .as_ref()
+            .map(|hit| hit.parameter)
+            .unwrap_or(ray_interval.max);
+        if let Some(record) = hit(
+            object.as_ref(),
+            Interval::new(ray_interval.min, t_max_local),
+        ) {
+            closest_so_far = Some(record);
+        }
     }
+
+    closest_so_far
 }
 
+/// Its surface coordinates are longitude (u) and latitude (v), so images in the
+/// equirectangular layout wrap around it like a globe
+#[derive(Clone)]
 pub struct Sphere {
     center: Point3,
     radius: f64,
-    material: Box<dyn Material>,
+    material: Arc<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: Box<dyn Material>) -> Self {
+    pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
         Self {
             center,
             radius: radius.max(0.0), // std::fmax(0,radius)
@@ -56,38 +127,638 @@ impl Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord> {
-        let dist_center_origin: Vector3 = (self.center - ray.get_origin()).as_vec();
-        let a: f64 = ray.get_direction().length_squared();
-        let h: f64 = ray.get_direction().dot_prod(dist_center_origin);
-        let c: f64 = (dist_center_origin.length_squared()) - (self.radius * self.radius);
-
-        let discriminant: f64 = (h * h) - (a * c);
-        if discriminant < 0.0 {
-            return None;
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center, self.radius, &*self.material, ray, ray_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius_vec = Vector3::new(self.radius, self.radius, self.radius);
+        Aabb::from_points(self.center - radius_vec, self.center + radius_vec)
+    }
+
+    /// Seen from outside, directions are sampled uniformly inside the cone that the sphere
+    /// subtends; from inside, points are sampled uniformly over the surface
+    fn pdf_value(&self, ray: Ray, ray_interval: Interval) -> f64 {
+        let Some(record) = self.hit(ray, ray_interval) else {
+            return 0.0;
+        };
+        let distance_squared = (self.center - ray.get_origin()).as_vec().length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            let to_point = (record.point - ray.get_origin()).as_vec();
+            let cosine = to_point.unit_vector().dot_prod(record.normal).abs();
+            return to_point.length_squared() / (cosine * 4.0 * PI * radius_squared);
+        }
+        // 1 - cos_theta_max, written so that it stays accurate for small, distant spheres
+        let sin_squared_max = radius_squared / distance_squared;
+        let one_minus_cos_max = sin_squared_max / (1.0 + (1.0 - sin_squared_max).sqrt());
+        1.0 / (2.0 * PI * one_minus_cos_max)
+    }
+
+    fn random_direction(&self, origin: Point3, sample: (f64, f64)) -> Vector3 {
+        let to_center = (self.center - origin).as_vec();
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            let point =
+                self.center + (Vector3::sample_unit_sphere(sample.0, sample.1) * self.radius);
+            return (point - origin).as_vec();
+        }
+
+        let sin_squared_max = radius_squared / distance_squared;
+        let one_minus_cos_max = sin_squared_max / (1.0 + (1.0 - sin_squared_max).sqrt());
+        let cos_theta = 1.0 - (sample.0 * one_minus_cos_max);
+        let sin_theta = (1.0 - (cos_theta * cos_theta)).max(0.0).sqrt();
+        let phi = 2.0 * PI * sample.1;
+
+        let w = to_center.unit_vector();
+        let (u, v) = w.orthonormal_basis();
+        (u * (phi.cos() * sin_theta)) + (v * (phi.sin() * sin_theta)) + (w * cos_theta)
+    }
+}
+
+/// A sphere whose center moves in a straight line from `start` at time 0 to `end` at time 1,
+/// seen where it is at the time of each ray. Moving emitters are not sampled as lights.
+#[derive(Clone)]
+pub struct MovingSphere {
+    path: Ray, // The center at a given time
+    radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb, // Covers the whole motion
+}
+
+impl MovingSphere {
+    pub fn new(start: Point3, end: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let radius = radius.max(0.0);
+        let radius_vec = Vector3::new(radius, radius, radius);
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(start - radius_vec, start + radius_vec),
+            &Aabb::from_points(end - radius_vec, end + radius_vec),
+        );
+        Self {
+            path: Ray::new(start, (end - start).as_vec()),
+            radius,
+            material,
+            bbox,
         }
+    }
+
+    pub fn center_at(&self, time: f64) -> Point3 {
+        self.path.position(time)
+    }
+
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let center = self.center_at(ray.get_time());
+        hit_sphere(center, self.radius, &*self.material, ray, ray_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+fn hit_sphere<'a>(
+    center: Point3,
+    radius: f64,
+    material: &'a dyn Material,
+    ray: Ray,
+    ray_interval: Interval,
+) -> Option<HitRecord<'a>> {
+    let dist_center_origin: Vector3 = (center - ray.get_origin()).as_vec();
+    let a: f64 = ray.get_direction().length_squared();
+    let h: f64 = ray.get_direction().dot_prod(dist_center_origin);
+    let c: f64 = (dist_center_origin.length_squared()) - (radius * radius);
+
+    let discriminant: f64 = (h * h) - (a * c);
+    if discriminant < 0.0 {
+        return None;
+    }
 
-        // Find the nearest root that lies in the acceptable range.
-        let sqrt_discriminant: f64 = discriminant.sqrt();
-        let mut root: f64 = (h - sqrt_discriminant) / a;
+    // Find the nearest root that lies in the acceptable range.
+    let sqrt_discriminant: f64 = discriminant.sqrt();
+    let mut root: f64 = (h - sqrt_discriminant) / a;
+    if !ray_interval.contains(root) {
+        root = (h + sqrt_discriminant) / a;
         if !ray_interval.contains(root) {
-            root = (h + sqrt_discriminant) / a;
-            if !ray_interval.contains(root) {
-                return None;
-            }
+            return None;
+        }
+    }
+
+    let parameter = root;
+    let point = ray.position(root);
+    let outward_normal = (point - center).as_vec() / radius;
+
+    let mut record = HitRecord::set_face_normal(ray, outward_normal, point, material, parameter);
+    (record.u, record.v) = sphere_uv(outward_normal);
+    (record.dpdu, record.dpdv) = sphere_tangents(outward_normal * radius);
+    Some(record)
+}
+
+/// Longitude and latitude of a point on the unit sphere, both in [0, 1].
+/// u starts at -x and runs through +z, +x and -z; v runs from -y at 0 up to +y at 1.
+fn sphere_uv(point: Vector3) -> (f64, f64) {
+    let theta = (-point.get_y()).clamp(-1.0, 1.0).acos();
+    let phi = f64::atan2(-point.get_z(), point.get_x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// The derivatives of the point (relative to the center) along u and v of `sphere_uv`.
+/// At the poles, where longitude is undefined, any pair of tangents is returned.
+fn sphere_tangents(offset: Vector3) -> (Vector3, Vector3) {
+    let (x, y, z) = (offset.get_x(), offset.get_y(), offset.get_z());
+    let distance_to_axis = ((x * x) + (z * z)).sqrt();
+    if distance_to_axis < 1e-12 * offset.length() {
+        let (dpdu, dpdv) = offset.unit_vector().orthonormal_basis();
+        return (dpdu * (2.0 * PI), dpdv * PI);
+    }
+    let dpdu = Vector3::new(z, 0.0, -x) * (2.0 * PI);
+    let dpdv = Vector3::new(
+        -x * y / distance_to_axis,
+        distance_to_axis,
+        -z * y / distance_to_axis,
+    ) * PI;
+    (dpdu, dpdv)
+}
+
+/// A parallelogram with one corner at `corner` and the two edges `u` and `v` leaving it.
+/// Surface coordinates run from 0 to 1 along each edge.
+#[derive(Clone)]
+pub struct Quad {
+    corner: Point3,
+    u: Vector3,
+    v: Vector3,
+    w: Vector3, // n / (n . n) for the unnormalized normal n = u x v, used for the hit coordinates
+    normal: Vector3, // Unit normal, u x v
+    plane_offset: f64, // The plane contains the points p with normal . p = plane_offset
+    area: f64,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vector3, v: Vector3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross_prod(v);
+        let normal = n.unit_vector();
+        Self {
+            corner,
+            u,
+            v,
+            w: n / n.length_squared(),
+            normal,
+            plane_offset: normal.dot_prod(corner.as_vec()),
+            area: n.length(),
+            material,
+        }
+    }
+
+    pub fn get_corner(&self) -> Point3 {
+        self.corner
+    }
+
+    pub fn get_edges(&self) -> (Vector3, Vector3) {
+        (self.u, self.v)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot_prod(ray.get_direction());
+        // No hit if the ray is parallel to the plane
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+
+        let parameter =
+            (self.plane_offset - self.normal.dot_prod(ray.get_origin().as_vec())) / denominator;
+        if !ray_interval.contains(parameter) {
+            return None;
+        }
+
+        // Coordinates of the hit point along the two edges
+        let point = ray.position(parameter);
+        let planar_hit = (point - self.corner).as_vec();
+        let alpha = self.w.dot_prod(planar_hit.cross_prod(self.v));
+        let beta = self.w.dot_prod(self.u.cross_prod(planar_hit));
+        let unit_interval = Interval::new(0.0, 1.0);
+        if !unit_interval.contains(alpha) || !unit_interval.contains(beta) {
+            return None;
+        }
+
+        let material = &*self.material;
+        let mut record = HitRecord::set_face_normal(ray, self.normal, point, material, parameter);
+        record.u = alpha;
+        record.v = beta;
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let diagonal1 = Aabb::from_points(self.corner, self.corner + self.u + self.v);
+        let diagonal2 = Aabb::from_points(self.corner + self.u, self.corner + self.v);
+        Aabb::surrounding(&diagonal1, &diagonal2)
+    }
+
+    /// Points are sampled uniformly over the area of the parallelogram
+    fn pdf_value(&self, ray: Ray, ray_interval: Interval) -> f64 {
+        let Some(record) = self.hit(ray, ray_interval) else {
+            return 0.0;
+        };
+        let to_point = (record.point - ray.get_origin()).as_vec();
+        let cosine = to_point.unit_vector().dot_prod(self.normal).abs();
+        to_point.length_squared() / (cosine * self.area)
+    }
+
+    fn random_direction(&self, origin: Point3, sample: (f64, f64)) -> Vector3 {
+        let point = self.corner + (self.u * sample.0) + (self.v * sample.1);
+        (point - origin).as_vec()
+    }
+}
+
+/// A single triangle with optional per-vertex shading normals and texture coordinates.
+/// The material is shared (`Arc`) since a mesh usually uses one material for many triangles.
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vector3; 3]>,
+    uvs: [(f64, f64); 3],
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, material: Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            material,
+        }
+    }
+
+    /// Use interpolated vertex normals for shading instead of the flat face normal
+    pub fn with_vertex_normals(mut self, normals: [Vector3; 3]) -> Self {
+        self.normals = Some(normals.map(|normal| normal.unit_vector()));
+        self
+    }
+
+    pub fn with_vertex_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    pub fn get_vertices(&self) -> [Point3; 3] {
+        self.vertices
+    }
+
+    /// Unit normal of the triangle plane, following the counter-clockwise winding v0, v1, v2
+    pub fn face_normal(&self) -> Vector3 {
+        let edge1 = (self.vertices[1] - self.vertices[0]).as_vec();
+        let edge2 = (self.vertices[2] - self.vertices[0]).as_vec();
+        edge1.cross_prod(edge2).unit_vector()
+    }
+}
+
+impl Hittable for Triangle {
+    /// Watertight ray/triangle intersection (Woop, Benthin and Wald, JCGT 2013).
+    /// The vertices are transformed into a space where the ray starts at the origin
+    /// and points along +z, so the inside test reduces to the signs of three 2D edge
+    /// functions. Rays through a shared edge get exactly the same edge function on
+    /// both triangles, so they can never slip between them.
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let direction = ray.get_direction();
+
+        // Permute the axes so that the largest direction component becomes z
+        let kz = direction.max_dimension();
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if direction.get_axis(kz) < 0.0 {
+            // Swap to preserve the winding of the triangle
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        // Shear constants that align the ray direction with +z
+        let shear_x = direction.get_axis(kx) / direction.get_axis(kz);
+        let shear_y = direction.get_axis(ky) / direction.get_axis(kz);
+        let shear_z = 1.0 / direction.get_axis(kz);
+
+        let [a, b, c] = self
+            .vertices
+            .map(|vertex| (vertex - ray.get_origin()).as_vec());
+        let a_x = a.get_axis(kx) - (shear_x * a.get_axis(kz));
+        let a_y = a.get_axis(ky) - (shear_y * a.get_axis(kz));
+        let b_x = b.get_axis(kx) - (shear_x * b.get_axis(kz));
+        let b_y = b.get_axis(ky) - (shear_y * b.get_axis(kz));
+        let c_x = c.get_axis(kx) - (shear_x * c.get_axis(kz));
+        let c_y = c.get_axis(ky) - (shear_y * c.get_axis(kz));
+
+        // Scaled barycentric coordinates (edge functions)
+        let edge_u = (c_x * b_y) - (c_y * b_x);
+        let edge_v = (a_x * c_y) - (a_y * c_x);
+        let edge_w = (b_x * a_y) - (b_y * a_x);
+
+        if (edge_u < 0.0 || edge_v < 0.0 || edge_w < 0.0)
+            && (edge_u > 0.0 || edge_v > 0.0 || edge_w > 0.0)
+        {
+            return None;
+        }
+
+        let determinant = edge_u + edge_v + edge_w;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        // Scaled hit distance, divided by the determinant only once we know it's a hit
+        let a_z = shear_z * a.get_axis(kz);
+        let b_z = shear_z * b.get_axis(kz);
+        let c_z = shear_z * c.get_axis(kz);
+        let parameter = ((edge_u * a_z) + (edge_v * b_z) + (edge_w * c_z)) / determinant;
+        if !ray_interval.contains(parameter) {
+            return None;
         }
 
-        let parameter = root;
-        let point = ray.position(root);
-        let outward_normal = (point - self.center).as_vec() / self.radius;
+        let barycentric = [
+            edge_u / determinant,
+            edge_v / determinant,
+            edge_w / determinant,
+        ];
+        let point = ray.position(parameter);
+        let outward_normal = self.face_normal();
         let material = &*self.material;
 
-        Some(HitRecord::set_face_normal(
-            ray,
-            outward_normal,
+        let mut record =
+            HitRecord::set_face_normal(ray, outward_normal, point, material, parameter);
+
+        // Shading normal: interpolate the vertex normals, keeping it on the side the ray came from
+        if let Some(normals) = self.normals {
+            let shading_normal = ((normals[0] * barycentric[0])
+                + (normals[1] * barycentric[1])
+                + (normals[2] * barycentric[2]))
+                .unit_vector();
+            record.normal = match record.is_face_front {
+                true => shading_normal,
+                false => -shading_normal,
+            };
+        }
+
+        record.u = (self.uvs[0].0 * barycentric[0])
+            + (self.uvs[1].0 * barycentric[1])
+            + (self.uvs[2].0 * barycentric[2]);
+        record.v = (self.uvs[0].1 * barycentric[0])
+            + (self.uvs[1].1 * barycentric[1])
+            + (self.uvs[2].1 * barycentric[2]);
+
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Bound all three vertices before padding, so only truly flat axes get padded
+        let [v0, v1, v2] = self.vertices;
+        let axis_bounds = |component: fn(Point3) -> f64| {
+            Interval::new(
+                component(v0).min(component(v1)).min(component(v2)),
+                component(v0).max(component(v1)).max(component(v2)),
+            )
+        };
+        Aabb::new(
+            axis_bounds(Point3::get_x),
+            axis_bounds(Point3::get_y),
+            axis_bounds(Point3::get_z),
+        )
+    }
+}
+
+/// An infinite plane through `point`, facing `normal`.
+/// Surface coordinates are distances from `point` along two perpendicular directions
+/// in the plane, so repeating textures tile it.
+#[derive(Clone)]
+pub struct Plane {
+    point: Point3,
+    normal: Vector3, // Unit length
+    u_axis: Vector3,
+    v_axis: Vector3,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vector3, material: Arc<dyn Material>) -> Self {
+        let normal = normal.unit_vector();
+        let (u_axis, v_axis) = normal.orthonormal_basis();
+        Self {
             point,
+            normal,
+            u_axis,
+            v_axis,
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot_prod(ray.get_direction());
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let parameter = self
+            .normal
+            .dot_prod((self.point - ray.get_origin()).as_vec())
+            / denominator;
+        if !ray_interval.contains(parameter) {
+            return None;
+        }
+
+        let point = ray.position(parameter);
+        let offset = (point - self.point).as_vec();
+        let material = &*self.material;
+        let mut record = HitRecord::set_face_normal(ray, self.normal, point, material, parameter);
+        record.u = offset.dot_prod(self.u_axis);
+        record.v = offset.dot_prod(self.v_axis);
+        Some(record)
+    }
+
+    /// Unbounded, except along the normal of a plane perpendicular to an axis
+    fn bounding_box(&self) -> Aabb {
+        let axis_bounds = |axis: usize| {
+            let normal_is_axis =
+                (0..3).all(|other| other == axis || self.normal.get_axis(other) == 0.0);
+            match normal_is_axis {
+                true => Interval::new(
+                    self.point.as_vec().get_axis(axis),
+                    self.point.as_vec().get_axis(axis),
+                ),
+                false => Interval::UNIVERSE,
+            }
+        };
+        Aabb::new(axis_bounds(0), axis_bounds(1), axis_bounds(2))
+    }
+}
+
+/// A flat disk around `center`, facing `normal`.
+/// Surface coordinates are polar: u is the angle (one full turn from 0 to 1), v the
+/// distance from the center relative to the radius.
+#[derive(Clone)]
+pub struct Disk {
+    center: Point3,
+    normal: Vector3, // Unit length
+    u_axis: Vector3,
+    v_axis: Vector3,
+    radius: f64,
+    material: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vector3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let normal = normal.unit_vector();
+        let (u_axis, v_axis) = normal.orthonormal_basis();
+        Self {
+            center,
+            normal,
+            u_axis,
+            v_axis,
+            radius: radius.max(0.0),
             material,
-            parameter,
-        ))
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot_prod(ray.get_direction());
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let parameter = self
+            .normal
+            .dot_prod((self.center - ray.get_origin()).as_vec())
+            / denominator;
+        if !ray_interval.contains(parameter) {
+            return None;
+        }
+
+        let point = ray.position(parameter);
+        let offset = (point - self.center).as_vec();
+        if offset.length_squared() > self.radius * self.radius {
+            return None;
+        }
+
+        let material = &*self.material;
+        let mut record = HitRecord::set_face_normal(ray, self.normal, point, material, parameter);
+        let angle = offset
+            .dot_prod(self.v_axis)
+            .atan2(offset.dot_prod(self.u_axis));
+        record.u = (angle / (2.0 * PI)).rem_euclid(1.0);
+        record.v = offset.length() / self.radius;
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Along each axis the rim reaches radius * sin(angle between the normal and the axis)
+        let extent = |axis: usize| {
+            let cosine = self.normal.get_axis(axis);
+            self.radius * (1.0 - (cosine * cosine)).max(0.0).sqrt()
+        };
+        let extents = Vector3::new(extent(0), extent(1), extent(2));
+        Aabb::from_points(self.center - extents, self.center + extents)
+    }
+
+    /// Points are sampled uniformly over the area of the disk
+    fn pdf_value(&self, ray: Ray, ray_interval: Interval) -> f64 {
+        let Some(record) = self.hit(ray, ray_interval) else {
+            return 0.0;
+        };
+        let to_point = (record.point - ray.get_origin()).as_vec();
+        let cosine = to_point.unit_vector().dot_prod(self.normal).abs();
+        to_point.length_squared() / (cosine * PI * self.radius * self.radius)
+    }
+
+    fn random_direction(&self, origin: Point3, sample: (f64, f64)) -> Vector3 {
+        let on_disk = Vector3::sample_unit_disk(sample.0, sample.1) * self.radius;
+        let point = self.center + (self.u_axis * on_disk.get_x()) + (self.v_axis * on_disk.get_y());
+        (point - origin).as_vec()
+    }
+}
+
+/// A box with faces parallel to the axes between the corners `min` and `max`, made of
+/// six quads facing outwards. Each face has its own 0 to 1 surface coordinates.
+#[derive(Clone)]
+pub struct Cuboid {
+    sides: [Quad; 6],
+    bbox: Aabb,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material>) -> Self {
+        let min = Point3::new(
+            a.get_x().min(b.get_x()),
+            a.get_y().min(b.get_y()),
+            a.get_z().min(b.get_z()),
+        );
+        let max = Point3::new(
+            a.get_x().max(b.get_x()),
+            a.get_y().max(b.get_y()),
+            a.get_z().max(b.get_z()),
+        );
+        let dx = Vector3::new(max.get_x() - min.get_x(), 0.0, 0.0);
+        let dy = Vector3::new(0.0, max.get_y() - min.get_y(), 0.0);
+        let dz = Vector3::new(0.0, 0.0, max.get_z() - min.get_z());
+
+        let quad =
+            |corner: Point3, u: Vector3, v: Vector3| Quad::new(corner, u, v, material.clone());
+        let sides = [
+            quad(Point3::new(min.get_x(), min.get_y(), max.get_z()), dx, dy), // Front
+            quad(Point3::new(max.get_x(), min.get_y(), max.get_z()), -dz, dy), // Right
+            quad(Point3::new(max.get_x(), min.get_y(), min.get_z()), -dx, dy), // Back
+            quad(min, dz, dy),                                                // Left
+            quad(Point3::new(min.get_x(), max.get_y(), max.get_z()), dx, -dz), // Top
+            quad(min, dx, dz),                                                // Bottom
+        ];
+        Self {
+            sides,
+            bbox: Aabb::from_points(min, max),
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let mut closest_so_far: Option<HitRecord> = None;
+        for side in self.sides.iter() {
+            let t_max = closest_so_far
+                .as_ref()
+                .map(|hit| hit.parameter)
+                .unwrap_or(ray_interval.max);
+            if let Some(record) = side.hit(ray, Interval::new(ray_interval.min, t_max)) {
+                closest_so_far = Some(record);
+            }
+        }
+        closest_so_far
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// A side is picked uniformly, then a point on it. A direction through the box can
+    /// come from the side facing the origin or from the one behind it, so both count.
+    fn pdf_value(&self, ray: Ray, ray_interval: Interval) -> f64 {
+        if self.hit(ray, ray_interval).is_none() {
+            return 0.0;
+        }
+        let beyond = Interval::new(ray_interval.min, f64::INFINITY);
+        let total: f64 = self
+            .sides
+            .iter()
+            .map(|side| side.pdf_value(ray, beyond))
+            .sum();
+        total / self.sides.len() as f64
+    }
+
+    fn random_direction(&self, origin: Point3, sample: (f64, f64)) -> Vector3 {
+        // Reuse the first value: its integer part picks the side, the rest places the point
+        let scaled = sample.0 * self.sides.len() as f64;
+        let index = (scaled as usize).min(self.sides.len() - 1);
+        self.sides[index].random_direction(origin, (scaled - index as f64, sample.1))
     }
 }
@@ -0,0 +1,195 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use super::{color::Color, image::Image};
+
+/// Reads a Radiance RGBE (`.hdr`) image.
+pub fn read_hdr(path: &Path) -> io::Result<Image> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    // Header: a magic line, `KEY=value` lines, and a blank line
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid_data("not a Radiance HDR file"));
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("unexpected end of header"));
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            break;
+        }
+        if let Some(format) = trimmed.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data("only the 32-bit_rle_rgbe format is supported"));
+            }
+        }
+    }
+
+    // Resolution line, e.g. `-Y 512 +X 1024` (top-to-bottom, left-to-right)
+    line.clear();
+    reader.read_line(&mut line)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (height, width) = match fields.as_slice() {
+        ["-Y", height, "+X", width] => (
+            height
+                .parse::<usize>()
+                .map_err(|_| invalid_data("bad height"))?,
+            width
+                .parse::<usize>()
+                .map_err(|_| invalid_data("bad width"))?,
+        ),
+        _ => return Err(invalid_data("unsupported resolution line")),
+    };
+
+    let mut pixels: Vec<Color> = Vec::with_capacity(width * height);
+    let mut scanline: Vec<[u8; 4]> = vec![[0; 4]; width];
+    for _ in 0..height {
+        read_scanline(&mut reader, &mut scanline)?;
+        pixels.extend(scanline.iter().map(|rgbe| rgbe_to_color(*rgbe)));
+    }
+
+    Ok(Image::from_pixels(width, height, pixels))
+}
+
+fn read_scanline<R: Read>(reader: &mut R, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+    let mut first = [0u8; 4];
+    reader.read_exact(&mut first)?;
+
+    // New-style run-length encoding: each of the four channels is stored separately
+    let is_rle = (8..0x8000).contains(&width)
+        && first[0] == 2
+        && first[1] == 2
+        && (((first[2] as usize) << 8) | first[3] as usize) == width;
+    if !is_rle {
+        scanline[0] = first;
+        for pixel in scanline.iter_mut().skip(1) {
+            reader.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            reader.read_exact(&mut count)?;
+            if count[0] > 128 {
+                // A run of one repeated value
+                let run = (count[0] - 128) as usize;
+                if x + run > width {
+                    return Err(invalid_data("run-length overflows the scanline"));
+                }
+                let mut value = [0u8; 1];
+                reader.read_exact(&mut value)?;
+                for pixel in scanline[x..x + run].iter_mut() {
+                    pixel[channel] = value[0];
+                }
+                x += run;
+            } else {
+                // A literal block of values
+                let run = count[0] as usize;
+                if run == 0 || x + run > width {
+                    return Err(invalid_data("bad literal block in scanline"));
+                }
+                let mut values = vec![0u8; run];
+                reader.read_exact(&mut values)?;
+                for (pixel, value) in scanline[x..x + run].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                x += run;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a Radiance RGBE (`.hdr`) image. Scanlines are stored with the
+/// per-channel run-length layout (as literal blocks) when the width allows it.
+pub fn write_hdr<W: Write>(image: &Image, writer: &mut W) -> io::Result<()> {
+    let (width, height) = (image.get_width(), image.get_height());
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let use_rle = (8..0x8000).contains(&width);
+    for scanline in image.pixels().chunks(width.max(1)) {
+        let rgbe: Vec<[u8; 4]> = scanline.iter().map(|pixel| color_to_rgbe(*pixel)).collect();
+        if !use_rle {
+            for pixel in rgbe.iter() {
+                writer.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        writer.write_all(&[2, 2, (width >> 8) as u8, (width & 0xFF) as u8])?;
+        for channel in 0..4 {
+            let values: Vec<u8> = rgbe.iter().map(|pixel| pixel[channel]).collect();
+            for block in values.chunks(128) {
+                writer.write_all(&[block.len() as u8])?;
+                writer.write_all(block)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Stores the largest component as a mantissa in [128, 255] and a shared exponent.
+/// Negative components are clamped to zero.
+fn color_to_rgbe(color: Color) -> [u8; 4] {
+    let (red, green, blue) = (
+        color.get_r().max(0.0),
+        color.get_g().max(0.0),
+        color.get_b().max(0.0),
+    );
+    let max_component = red.max(green).max(blue);
+    if max_component < 1e-32 || !max_component.is_finite() {
+        return [0, 0, 0, 0];
+    }
+
+    // max_component = mantissa * 2^exponent with mantissa in [0.5, 1)
+    let mut exponent = max_component.log2().floor() as i32 + 1;
+    let mut mantissa = max_component / 2.0_f64.powi(exponent);
+    if mantissa >= 1.0 {
+        // Guard against rounding in log2 right at powers of two
+        exponent += 1;
+        mantissa /= 2.0;
+    }
+    if exponent + 128 > 255 {
+        return [255, 255, 255, 255];
+    }
+
+    let scale = mantissa * 256.0 / max_component;
+    [
+        (red * scale) as u8,
+        (green * scale) as u8,
+        (blue * scale) as u8,
+        (exponent + 128).max(0) as u8,
+    ]
+}
+
+/// The three mantissas share the exponent stored in the fourth byte
+fn rgbe_to_color(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::default();
+    }
+    let scale = 2.0_f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * scale,
+        (rgbe[1] as f64 + 0.5) * scale,
+        (rgbe[2] as f64 + 0.5) * scale,
+    )
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
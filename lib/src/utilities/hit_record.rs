@@ -7,6 +7,10 @@ pub struct HitRecord<'a> {
     pub parameter: f64,
     pub is_face_front: bool,
     pub material: &'a dyn Material,
+    pub u: f64, // Surface coordinates of the hit point
+    pub v: f64,
+    pub dpdu: Vector3, // How the point moves with u and with v, along the surface
+    pub dpdv: Vector3, // Zero for primitives without them
 }
 
 impl<'a> HitRecord<'a> {
@@ -16,6 +20,8 @@ impl<'a> HitRecord<'a> {
         parameter: f64,
         is_face_front: bool,
         material: &'a dyn Material,
+        u: f64,
+        v: f64,
     ) -> Self {
         Self {
             point,
@@ -23,10 +29,15 @@ impl<'a> HitRecord<'a> {
             parameter,
             is_face_front,
             material,
+            u,
+            v,
+            dpdu: Vector3::default(),
+            dpdv: Vector3::default(),
         }
     }
     /// Sets the hit record normal vector.
-    /// The parameter `outward_normal` is assumed to have unit length
+    /// The parameter `outward_normal` is assumed to have unit length.
+    /// Surface coordinates and tangents start at zero; primitives that have them set them afterwards.
     pub fn set_face_normal(
         ray: Ray,
         outward_normal: Vector3,
@@ -45,6 +56,10 @@ impl<'a> HitRecord<'a> {
             parameter,
             is_face_front,
             material,
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vector3::default(),
+            dpdv: Vector3::default(),
         }
     }
 }
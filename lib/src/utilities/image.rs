@@ -0,0 +1,292 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use exr::prelude::{
+    f16, AnyChannel, AnyChannels, Encoding, FlatSamples, Image as ExrImage, Layer, LayerAttributes,
+    SmallVec, WritableImage,
+};
+
+use super::{color::Color, hdr};
+
+/// File formats an `Image` can be written in.
+/// PNG and PPM are gamma corrected and clamped to 8 bits,
+/// OpenEXR and Radiance HDR keep the unclamped linear colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    PpmBinary, // P6
+    PpmAscii,  // P3
+    Exr(ExrPrecision),
+    Hdr, // Radiance RGBE
+}
+
+/// Sample type of the channels in an OpenEXR file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExrPrecision {
+    Half,  // 16-bit float
+    Float, // 32-bit float
+}
+
+impl ImageFormat {
+    /// Guesses the format from the file extension. `.ppm` files are written as ASCII P3,
+    /// `.exr` files with half floats.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::PpmAscii),
+            "exr" => Some(ImageFormat::Exr(ExrPrecision::Half)),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+}
+
+/// A framebuffer of linear (not gamma corrected) colors, stored row by row from the top left
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    /// A black image
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::default(); width * height],
+        }
+    }
+
+    /// Wraps existing pixels, which must hold exactly `width * height` colors
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "pixel count must match image size"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[(y * self.width) + x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[(y * self.width) + x] = color;
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Reads a PNG, JPEG or Radiance HDR file, chosen by the extension.
+    /// 8-bit colors are taken out of gamma space with the curve `to_bytes` puts them in.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let image = match extension.as_str() {
+            "png" => Self::read_png(path)?,
+            "jpg" | "jpeg" => Self::read_jpeg(path)?,
+            "hdr" => hdr::read_hdr(path)?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unsupported image format, expected .png, .jpg, .jpeg or .hdr",
+                ))
+            }
+        };
+        match image.width > 0 && image.height > 0 {
+            true => Ok(image),
+            false => Err(io::Error::new(io::ErrorKind::InvalidData, "image is empty")),
+        }
+    }
+
+    fn read_png(path: &Path) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(io::Error::other)?;
+        let mut bytes = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut bytes).map_err(io::Error::other)?;
+        let channels = info.color_type.samples();
+        Ok(Self::from_bytes(
+            info.width as usize,
+            info.height as usize,
+            &bytes[..info.buffer_size()],
+            channels,
+        ))
+    }
+
+    fn read_jpeg(path: &Path) -> io::Result<Self> {
+        let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(File::open(path)?));
+        let bytes = decoder.decode().map_err(io::Error::other)?;
+        let info = decoder
+            .info()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing JPEG header"))?;
+        let channels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => 1,
+            jpeg_decoder::PixelFormat::RGB24 => 3,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "only 8-bit grayscale and RGB JPEG files are supported",
+                ))
+            }
+        };
+        Ok(Self::from_bytes(
+            info.width as usize,
+            info.height as usize,
+            &bytes,
+            channels,
+        ))
+    }
+
+    /// Gray, gray and alpha, RGB or RGBA bytes; alpha is ignored
+    fn from_bytes(width: usize, height: usize, bytes: &[u8], channels: usize) -> Self {
+        let to_linear = |byte: u8| (byte as f64 / 255.0).powi(2);
+        let pixels = bytes
+            .chunks_exact(channels)
+            .map(|pixel| match channels {
+                1 | 2 => {
+                    let gray = to_linear(pixel[0]);
+                    Color::new(gray, gray, gray)
+                }
+                _ => Color::new(
+                    to_linear(pixel[0]),
+                    to_linear(pixel[1]),
+                    to_linear(pixel[2]),
+                ),
+            })
+            .collect();
+        Self::from_pixels(width, height, pixels)
+    }
+
+    /// Writes the image to `path`, creating or truncating the file
+    pub fn save(&self, path: &Path, format: ImageFormat) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, format)?;
+        writer.flush()
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Png => self.write_png(writer),
+            ImageFormat::PpmBinary => self.write_ppm_binary(writer),
+            ImageFormat::PpmAscii => self.write_ppm_ascii(writer),
+            ImageFormat::Exr(precision) => self.write_exr(writer, precision),
+            ImageFormat::Hdr => hdr::write_hdr(self, writer),
+        }
+    }
+
+    /// Plain text PPM (P3), one pixel per line
+    pub fn write_ppm_ascii<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "P3\n{} {}\n255", self.width, self.height)?;
+        for pixel in self.pixels.iter() {
+            pixel.write_color(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Binary PPM (P6)
+    pub fn write_ppm_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.to_rgb_bytes())
+    }
+
+    /// 8-bit RGB PNG
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&self.to_rgb_bytes())?;
+        png_writer.finish()?;
+        Ok(())
+    }
+
+    /// OpenEXR with linear R, G, B channels and an opaque A channel,
+    /// stored as half or full floats and compressed with ZIP
+    pub fn write_exr<W: Write>(&self, writer: &mut W, precision: ExrPrecision) -> io::Result<()> {
+        let channel_values: [(&str, Vec<f32>); 4] = [
+            (
+                "R",
+                self.pixels
+                    .iter()
+                    .map(|pixel| pixel.get_r() as f32)
+                    .collect(),
+            ),
+            (
+                "G",
+                self.pixels
+                    .iter()
+                    .map(|pixel| pixel.get_g() as f32)
+                    .collect(),
+            ),
+            (
+                "B",
+                self.pixels
+                    .iter()
+                    .map(|pixel| pixel.get_b() as f32)
+                    .collect(),
+            ),
+            ("A", vec![1.0; self.pixels.len()]),
+        ];
+        let channels: SmallVec<[AnyChannel<FlatSamples>; 4]> = channel_values
+            .into_iter()
+            .map(|(name, values)| {
+                let samples = match precision {
+                    ExrPrecision::Half => {
+                        FlatSamples::F16(values.into_iter().map(f16::from_f32).collect())
+                    }
+                    ExrPrecision::Float => FlatSamples::F32(values),
+                };
+                AnyChannel::new(name, samples)
+            })
+            .collect();
+
+        let layer = Layer::new(
+            (self.width, self.height),
+            LayerAttributes::default(),
+            Encoding::SMALL_LOSSLESS,
+            AnyChannels::sort(channels),
+        );
+
+        // The encoder needs to seek, so encode into memory first
+        let mut buffer = io::Cursor::new(Vec::new());
+        ExrImage::from_layer(layer)
+            .write()
+            .to_buffered(&mut buffer)
+            .map_err(|e| match e {
+                exr::error::Error::Io(io_error) => io_error,
+                other => io::Error::new(io::ErrorKind::InvalidData, other.to_string()),
+            })?;
+        writer.write_all(buffer.get_ref())
+    }
+
+    /// Gamma corrected 8-bit RGB triples, row by row
+    fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_bytes())
+            .collect()
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use super::{
+    aabb::Aabb, geometry::Hittable, hit_record::HitRecord, interval::Interval, ray::Ray,
+    sampler::Sampler, transform::Transform,
+};
+
+/// An object placed in the world by a transform. The object is shared (`Arc`), so one
+/// mesh can appear many times at different poses without copying its triangles.
+/// Rays are taken into object space, hits are brought back into world space.
+/// Transformed emitters are found by the paths that hit them but are not sampled as lights.
+pub struct Transformed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    transform: Transform, // Object space to world space
+    bbox: Aabb,
+}
+
+/// A transformed object of any type
+pub type Instance = Transformed<dyn Hittable>;
+
+impl<H: Hittable + ?Sized> Transformed<H> {
+    pub fn new(object: Arc<H>, transform: Transform) -> Self {
+        let bbox = transform.apply_bbox(&object.bounding_box());
+        Self {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// Brings a hit of the object back into world space
+    fn to_world<'a>(&self, mut record: HitRecord<'a>) -> HitRecord<'a> {
+        record.point = self.transform.apply_point(record.point);
+        record.normal = self.transform.apply_normal(record.normal).unit_vector();
+        record.dpdu = self.transform.apply_vector(record.dpdu);
+        record.dpdv = self.transform.apply_vector(record.dpdv);
+        record
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Transformed<H> {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        let object_ray = self.transform.inverse().apply_ray(ray);
+        let record = self.object.hit(object_ray, ray_interval)?;
+        Some(self.to_world(record))
+    }
+
+    fn sample_hit(
+        &self,
+        ray: Ray,
+        ray_interval: Interval,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let object_ray = self.transform.inverse().apply_ray(ray);
+        let record = self.object.sample_hit(object_ray, ray_interval, sampler)?;
+        Some(self.to_world(record))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
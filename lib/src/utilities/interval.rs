@@ -1,14 +1,26 @@
+#[derive(Clone, Copy)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
 }
 
 impl Interval {
+    pub const EMPTY: Self = Self::new(f64::INFINITY, f64::NEG_INFINITY);
+    pub const UNIVERSE: Self = Self::new(f64::NEG_INFINITY, f64::INFINITY);
+
     // https://stackoverflow.com/questions/26549480/how-do-i-declare-a-static-field-in-a-struct-in-rust
     pub const fn new(min: f64, max: f64) -> Self {
         Self { min, max }
     }
 
+    /// Creates the tightest interval enclosing both input intervals
+    pub fn from_intervals(a: Interval, b: Interval) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
     pub fn size(&self) -> f64 {
         self.max - self.min
     }
@@ -20,6 +32,15 @@ impl Interval {
     pub fn inside(&self, element: f64) -> bool {
         element > self.min && element < self.max
     }
+
+    /// Pads the interval by `delta`, half on each side
+    pub fn expand(&self, delta: f64) -> Self {
+        let padding = delta / 2.0;
+        Self {
+            min: self.min - padding,
+            max: self.max + padding,
+        }
+    }
 }
 
 impl Default for Interval {
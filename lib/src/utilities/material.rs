@@ -1,60 +1,142 @@
-use super::{color::Color, hit_record::HitRecord, ray::Ray, vector3::Vector3};
+use std::{f64::consts::PI, sync::Arc};
+
+use serde::Deserialize;
+
+use super::{
+    color::Color,
+    hit_record::HitRecord,
+    microfacet::{
+        fresnel_conductor, fresnel_dielectric, reflect, refract, ShadingFrame, TrowbridgeReitz,
+    },
+    point::Point3,
+    ray::Ray,
+    sampler::Sampler,
+    spectrum::{Dispersion, D_LINE},
+    texture::{SolidColor, Texture},
+    vector3::Vector3,
+};
 
 #[derive(Clone)]
 pub struct Scatter {
     pub scattered_ray: Ray,
-    pub attenuation: Color,
+    pub attenuation: Color, // BSDF times cosine, divided by `pdf`
+    pub pdf: Option<f64>, // Solid angle density of the scattered direction, None for a perfectly specular bounce
 }
 
 // https://github.com/ebkalderon/ray-tracing-in-one-weekend/commits/master/?before=afc5b8807ba4a342b09c83361968e7ddc284fc12+70
 pub trait Material: Send + Sync {
-    fn scatter(&self, incoming_ray: Ray, record: &HitRecord) -> Option<Scatter>;
+    /// The ray leaving the surface and its attenuation, or `None` if the ray is absorbed.
+    /// Random decisions draw from `sampler`.
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter>;
+
+    /// BSDF times the cosine of the angle to the normal, for light arriving from `direction`
+    /// and leaving along the reversed `incoming_ray`. Perfectly specular materials return black,
+    /// they can only be sampled by `scatter`.
+    fn eval(&self, _incoming_ray: Ray, _record: &HitRecord, _direction: Vector3) -> Color {
+        Color::default()
+    }
+
+    /// Probability density, with respect to solid angle, that `scatter` picks `direction`
+    fn pdf(&self, _incoming_ray: Ray, _record: &HitRecord, _direction: Vector3) -> f64 {
+        0.0
+    }
+
+    /// Light given off by the surface at the hit point. Most materials do not glow.
+    fn emitted(&self, _u: f64, _v: f64, _point: Point3) -> Color {
+        Color::default()
+    }
+
+    /// Whether the surface gives off light, so objects made of it are sampled as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Whether the scattered direction depends on the wavelength of the incoming ray.
+    /// A spectral path that scatters off such a material keeps only its hero wavelength.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// Whether `eval` can be nonzero, so light reaching the surface is worth sampling.
+    /// Perfect mirrors and glass scatter only through `scatter`.
+    fn has_non_specular(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone)]
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
 
 impl Default for Lambertian {
     fn default() -> Self {
-        Self {
-            albedo: Color::new(0.5, 0.5, 0.5),
-        }
+        Self::new(Color::new(0.5, 0.5, 0.5))
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _incoming_ray: Ray, record: &HitRecord) -> Option<Scatter> {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
         // Lambertian scatter
-        let mut scatter_direction: Vector3 = record.normal + Vector3::random_unit_vector();
+        let mut scatter_direction: Vector3 = record.normal + Vector3::random_unit_vector(sampler);
 
         // Catch the near-zero scatter directions
         if scatter_direction.near_zero() {
             scatter_direction = record.normal;
         }
 
+        let scattered_ray =
+            Ray::with_time(record.point, scatter_direction, incoming_ray.get_time());
         Some(Scatter {
-            scattered_ray: Ray::new(record.point, scatter_direction),
-            attenuation: self.albedo,
+            pdf: Some(self.pdf(incoming_ray, record, scatter_direction)),
+            scattered_ray,
+            attenuation: self.albedo.value(record.u, record.v, record.point),
         })
     }
+
+    fn eval(&self, _incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        let cosine = record.normal.dot_prod(direction.unit_vector()).max(0.0);
+        self.albedo.value(record.u, record.v, record.point) * (cosine / PI)
+    }
+
+    /// The normal plus a random unit vector is cosine distributed
+    fn pdf(&self, _incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        record.normal.dot_prod(direction.unit_vector()).max(0.0) / PI
+    }
 }
 
 #[derive(Clone)]
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Self {
         Self {
             albedo,
             fuzz: if fuzz < 1.0 { fuzz } else { 1.0 },
@@ -64,41 +146,94 @@ impl Metal {
 
 impl Default for Metal {
     fn default() -> Self {
-        Self {
-            albedo: Color::new(0.8, 0.8, 0.8),
-            fuzz: 1.0,
-        }
+        Self::new(Color::new(0.8, 0.8, 0.8), 1.0)
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, incoming_ray: Ray, record: &HitRecord) -> Option<Scatter> {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
         // Metal material with reflectance function
         let reflect_direction: Vector3 = (incoming_ray
             .get_direction()
             .unit_vector()
             .reflection(&record.normal))
-            + (Vector3::random_unit_vector() * self.fuzz);
-        let scattered_ray = Ray::new(record.point, reflect_direction);
-        let attenuation = self.albedo;
+            + (Vector3::random_unit_vector(sampler) * self.fuzz);
+        let scattered_ray =
+            Ray::with_time(record.point, reflect_direction, incoming_ray.get_time());
+        let attenuation = self.albedo.value(record.u, record.v, record.point);
         if scattered_ray.get_direction().dot_prod(record.normal) > 0.0 {
             return Some(Scatter {
                 scattered_ray,
                 attenuation,
+                pdf: None,
             });
         }
         None
     }
+
+    fn has_non_specular(&self) -> bool {
+        false
+    }
+}
+
+/// How a `Dielectric` computes its Fresnel reflectance
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FresnelModel {
+    /// Schlick's polynomial approximation
+    #[default]
+    Schlick,
+    /// The Fresnel equations for unpolarized light
+    Exact,
 }
 
+/// Polished glass, water or diamond. Each bounce reflects with the probability given by
+/// the Fresnel reflectance and refracts otherwise, so glass mirrors its surroundings at
+/// grazing angles. Light travelling inside is absorbed following the Beer-Lambert law:
+/// after a distance d, a fraction exp(-`absorption` d) of each channel is left.
+/// With a `dispersion`, spectral renders bend each wavelength by its own index, splitting
+/// white light into colors; RGB renders keep using `refractive_index`.
 #[derive(Clone)]
 pub struct Dielectric {
     refractive_index: f64,
+    fresnel: FresnelModel,
+    absorption: Color, // Per unit of distance
+    dispersion: Option<Dispersion>,
 }
 
 impl Dielectric {
     pub fn new(refractive_index: f64) -> Self {
-        Self { refractive_index }
+        Self {
+            refractive_index,
+            fresnel: FresnelModel::default(),
+            absorption: Color::default(),
+            dispersion: None,
+        }
+    }
+
+    /// A dispersive material, with its index at the sodium d line for RGB renders
+    pub fn from_dispersion(dispersion: Dispersion) -> Self {
+        Self::new(dispersion.refractive_index(D_LINE)).with_dispersion(dispersion)
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: FresnelModel) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
     }
 
     pub fn reflectance(cos_theta: f64, r_index: f64) -> f64 {
@@ -106,40 +241,1056 @@ impl Dielectric {
         r0 = r0 * r0;
         r0 + ((1.0 - r0) * ((1.0 - cos_theta).powi(5)))
     }
+
+    /// Fraction of each channel left after `distance` inside the material
+    pub fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            (-self.absorption.get_r() * distance).exp(),
+            (-self.absorption.get_g() * distance).exp(),
+            (-self.absorption.get_b() * distance).exp(),
+        )
+    }
 }
 
 impl Default for Dielectric {
     fn default() -> Self {
-        Self {
-            refractive_index: 1.0,
-        }
+        Self::new(1.0)
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, incoming_ray: Ray, record: &HitRecord) -> Option<Scatter> {
-        let attenuation: Color = Color::new(1.0, 1.0, 1.0);
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        // A ray hitting the inside of the surface has crossed the material
+        let attenuation: Color = match record.is_face_front {
+            true => Color::new(1.0, 1.0, 1.0),
+            false => self.transmittance(record.parameter * incoming_ray.get_direction().length()),
+        };
+        let refractive_index = match (self.dispersion, incoming_ray.get_wavelength()) {
+            (Some(dispersion), Some(wavelength)) => dispersion.refractive_index(wavelength),
+            _ => self.refractive_index,
+        };
         let r_index: f64 = if record.is_face_front {
-            1.0 / self.refractive_index
+            1.0 / refractive_index
         } else {
-            self.refractive_index
+            refractive_index
         };
         let unit_direction: Vector3 = incoming_ray.get_direction().unit_vector();
 
         let cos_theta: f64 = (-unit_direction.dot_prod(record.normal)).min(1.0); // std::fmin
         let sin_theta: f64 = (1.0 - (cos_theta * cos_theta)).sqrt();
         let can_refract: bool = r_index * sin_theta <= 1.0;
+        let reflectance = match self.fresnel {
+            FresnelModel::Schlick => Self::reflectance(cos_theta, r_index),
+            FresnelModel::Exact => fresnel_dielectric(cos_theta, 1.0 / r_index),
+        };
 
-        let ray_direction: Vector3 = if can_refract {
+        let ray_direction: Vector3 = if can_refract && sampler.get_1d() >= reflectance {
             unit_direction.refraction(&record.normal, r_index)
         } else {
             unit_direction.reflection(&record.normal)
         };
-        let scattered_ray: Ray = Ray::new(record.point, ray_direction);
+        let scattered_ray: Ray =
+            Ray::with_time(record.point, ray_direction, incoming_ray.get_time());
 
         Some(Scatter {
             scattered_ray,
             attenuation,
+            pdf: None,
+        })
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
+    fn has_non_specular(&self) -> bool {
+        false
+    }
+}
+
+/// Metals with measured optical constants, for `Conductor`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+}
+
+impl ConductorPreset {
+    /// The real and imaginary parts (eta, k) of the refractive index, at wavelengths
+    /// standing in for red, green and blue
+    pub fn complex_ior(self) -> (Color, Color) {
+        match self {
+            ConductorPreset::Gold => (
+                Color::new(0.14312, 0.37496, 1.44248),
+                Color::new(3.98316, 2.38572, 1.60322),
+            ),
+            ConductorPreset::Copper => (
+                Color::new(0.20044, 0.92404, 1.10220),
+                Color::new(3.91295, 2.45285, 2.14219),
+            ),
+            ConductorPreset::Aluminium => (
+                Color::new(1.65746, 0.88037, 0.52123),
+                Color::new(9.22387, 6.26952, 4.83700),
+            ),
+            ConductorPreset::Silver => (
+                Color::new(0.15520, 0.11671, 0.13830),
+                Color::new(4.82835, 3.12225, 2.14696),
+            ),
+        }
+    }
+}
+
+/// A metal with a GGX microfacet surface: its color comes from the Fresnel reflectance
+/// of the complex refractive index `eta + i k`, and `roughness` (0 is a mirror, 1 is
+/// very rough) blurs the reflection without losing or creating energy in a single bounce
+#[derive(Clone)]
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+
+    pub fn from_preset(preset: ConductorPreset, roughness: f64) -> Self {
+        let (eta, k) = preset.complex_ior();
+        Self::new(eta, k, roughness)
+    }
+
+    /// Reflectance when looking straight at the surface
+    pub fn normal_reflectance(&self) -> Color {
+        fresnel_conductor(1.0, self.eta, self.k)
+    }
+}
+
+impl Default for Conductor {
+    fn default() -> Self {
+        Self::from_preset(ConductorPreset::Aluminium, 0.3)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let frame = ShadingFrame::from_record(record);
+        let wo = frame.to_local(-incoming_ray.get_direction().unit_vector());
+        if wo.get_z() <= 0.0 {
+            return None;
+        }
+        let time = incoming_ray.get_time();
+
+        if self.distribution.is_smooth() {
+            let wi = Vector3::new(-wo.get_x(), -wo.get_y(), wo.get_z());
+            return Some(Scatter {
+                scattered_ray: Ray::with_time(record.point, frame.to_world(wi), time),
+                attenuation: fresnel_conductor(wo.get_z(), self.eta, self.k),
+                pdf: None,
+            });
+        }
+
+        let microfacet_normal = self.distribution.sample_visible(wo, sampler.get_2d());
+        let wi = reflect(wo, microfacet_normal);
+        if wi.get_z() <= 0.0 {
+            return None;
+        }
+        // The BSDF over the visible normal density leaves only Fresnel and masking
+        let fresnel = fresnel_conductor(wo.dot_prod(microfacet_normal), self.eta, self.k);
+        let masking = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        let direction = frame.to_world(wi);
+        Some(Scatter {
+            pdf: Some(self.pdf(incoming_ray, record, direction)),
+            scattered_ray: Ray::with_time(record.point, direction, time),
+            attenuation: fresnel * masking,
+        })
+    }
+
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        if self.distribution.is_smooth() || wo.get_z() <= 0.0 || wi.get_z() <= 0.0 {
+            return Color::default();
+        }
+        let half = wo + wi;
+        if half.near_zero() {
+            return Color::default();
+        }
+        let microfacet_normal = half.unit_vector();
+        let fresnel = fresnel_conductor(wo.dot_prod(microfacet_normal), self.eta, self.k);
+        fresnel
+            * (self.distribution.d(microfacet_normal) * self.distribution.g(wo, wi)
+                / (4.0 * wo.get_z()))
+    }
+
+    fn pdf(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        if self.distribution.is_smooth() || wo.get_z() <= 0.0 || wi.get_z() <= 0.0 {
+            return 0.0;
+        }
+        let half = wo + wi;
+        if half.near_zero() {
+            return 0.0;
+        }
+        let microfacet_normal = half.unit_vector();
+        self.distribution.visible_d(wo, microfacet_normal)
+            / (4.0 * wo.dot_prod(microfacet_normal).abs())
+    }
+
+    fn has_non_specular(&self) -> bool {
+        !self.distribution.is_smooth()
+    }
+}
+
+/// Glass with a GGX microfacet surface, like frosted or etched glass. Each bounce picks
+/// reflection or refraction through a sampled microfacet by its Fresnel reflectance.
+/// A `roughness` of 0 behaves like a polished `Dielectric`.
+#[derive(Clone)]
+pub struct RoughDielectric {
+    refractive_index: f64,
+    distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    pub fn new(refractive_index: f64, roughness: f64) -> Self {
+        Self {
+            refractive_index,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+}
+
+impl Default for RoughDielectric {
+    fn default() -> Self {
+        Self::new(1.5, 0.3)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let frame = ShadingFrame::from_record(record);
+        let wo = frame.to_local(-incoming_ray.get_direction().unit_vector());
+        if wo.get_z() <= 0.0 {
+            return None;
+        }
+        let eta = relative_index(self.refractive_index, record);
+        let time = incoming_ray.get_time();
+
+        if self.distribution.is_smooth() {
+            let reflectance = fresnel_dielectric(wo.get_z(), eta);
+            let surface_normal = Vector3::new(0.0, 0.0, 1.0);
+            let (wi, attenuation) = match sampler.get_1d() < reflectance {
+                true => (reflect(wo, surface_normal), 1.0),
+                false => match refract(wo, surface_normal, eta) {
+                    Some(wi) => (wi, 1.0 / (eta * eta)),
+                    None => (reflect(wo, surface_normal), 1.0),
+                },
+            };
+            return Some(Scatter {
+                scattered_ray: Ray::with_time(record.point, frame.to_world(wi), time),
+                attenuation: Color::new(attenuation, attenuation, attenuation),
+                pdf: None,
+            });
+        }
+
+        let direction_sample = sampler.get_2d();
+        let wi = sample_rough_dielectric(
+            &self.distribution,
+            wo,
+            eta,
+            direction_sample,
+            sampler.get_1d(),
+        )?;
+        let (bsdf_cos, pdf) = rough_dielectric_lobe(&self.distribution, wo, wi, eta);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let weight = bsdf_cos / pdf;
+        Some(Scatter {
+            scattered_ray: Ray::with_time(record.point, frame.to_world(wi), time),
+            attenuation: Color::new(weight, weight, weight),
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::default();
+        }
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        let eta = relative_index(self.refractive_index, record);
+        let (bsdf_cos, _) = rough_dielectric_lobe(&self.distribution, wo, wi, eta);
+        Color::new(bsdf_cos, bsdf_cos, bsdf_cos)
+    }
+
+    fn pdf(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        let eta = relative_index(self.refractive_index, record);
+        rough_dielectric_lobe(&self.distribution, wo, wi, eta).1
+    }
+
+    fn has_non_specular(&self) -> bool {
+        !self.distribution.is_smooth()
+    }
+}
+
+/// Index on the far side of the surface over the index on the side of the ray
+fn relative_index(refractive_index: f64, record: &HitRecord) -> f64 {
+    match record.is_face_front {
+        true => refractive_index,
+        false => 1.0 / refractive_index,
+    }
+}
+
+/// A direction reflected or refracted through a visible microfacet, chosen by the
+/// Fresnel reflectance with `choice`; `None` when it would leave through the wrong side
+fn sample_rough_dielectric(
+    distribution: &TrowbridgeReitz,
+    wo: Vector3,
+    eta: f64,
+    direction_sample: (f64, f64),
+    choice: f64,
+) -> Option<Vector3> {
+    let microfacet_normal = distribution.sample_visible(wo, direction_sample);
+    let reflectance = fresnel_dielectric(wo.dot_prod(microfacet_normal), eta);
+    match choice < reflectance {
+        true => Some(reflect(wo, microfacet_normal)),
+        false => refract(wo, microfacet_normal, eta),
+    }
+}
+
+/// BSDF times cosine and sampling density of rough glass, for local directions where
+/// `wo` is above the surface
+fn rough_dielectric_lobe(
+    distribution: &TrowbridgeReitz,
+    wo: Vector3,
+    wi: Vector3,
+    eta: f64,
+) -> (f64, f64) {
+    let (cos_outgoing, cos_incident) = (wo.get_z(), wi.get_z());
+    if cos_outgoing <= 0.0 || cos_incident == 0.0 {
+        return (0.0, 0.0);
+    }
+    // The generalized half vector
+    let is_reflection = cos_incident > 0.0;
+    let half = match is_reflection {
+        true => wi + wo,
+        false => (wi * eta) + wo,
+    };
+    if half.near_zero() {
+        return (0.0, 0.0);
+    }
+    let half = half.unit_vector();
+    let microfacet_normal = match half.get_z() < 0.0 {
+        true => -half,
+        false => half,
+    };
+    // Discard microfacets facing away from either direction
+    if microfacet_normal.dot_prod(wi) * cos_incident < 0.0 || microfacet_normal.dot_prod(wo) < 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let reflectance = fresnel_dielectric(wo.dot_prod(microfacet_normal), eta);
+    let visible_density = distribution.visible_d(wo, microfacet_normal);
+    let shared = distribution.d(microfacet_normal) * distribution.g(wo, wi);
+    match is_reflection {
+        true => (
+            shared * reflectance / (4.0 * cos_outgoing),
+            visible_density / (4.0 * wo.dot_prod(microfacet_normal)) * reflectance,
+        ),
+        false => {
+            let denominator =
+                (wi.dot_prod(microfacet_normal) + (wo.dot_prod(microfacet_normal) / eta)).powi(2);
+            let transmittance = 1.0 - reflectance;
+            let bsdf_cos = shared
+                * transmittance
+                * (wi.dot_prod(microfacet_normal) * wo.dot_prod(microfacet_normal)
+                    / (cos_outgoing * denominator))
+                    .abs()
+                // Radiance is compressed into a smaller solid angle in the denser medium
+                / (eta * eta);
+            let density = visible_density * wi.dot_prod(microfacet_normal).abs() / denominator
+                * transmittance;
+            (bsdf_cos, density)
+        }
+    }
+}
+
+/// A principled "uber" material after Burley's Disney BSDF: one model that covers plastics,
+/// metals, glass and lacquered surfaces. All parameters except `base_color` lie in [0, 1]
+/// and, like it, can be textures (scalar textures are read as the mean of their channels).
+/// - `metallic` blends from a dielectric with a diffuse base to a metal tinted by `base_color`
+/// - `roughness` widens the GGX highlights and flattens the diffuse retro-reflection
+/// - `specular` scales the reflectance of the dielectric part (0.5 is 4%, like most plastics)
+/// - `transmission` turns the dielectric part into rough glass of index `refractive_index`
+/// - `clearcoat` adds a glossy transparent layer on top
+/// - `sheen` adds the soft grazing highlight of cloth
+/// - `anisotropy` stretches the highlights along the surface u direction
+///
+/// Each bounce picks one lobe in proportion to its weight and is weighted by the combined
+/// density of all lobes, so the material works with light sampling.
+#[derive(Clone)]
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    anisotropy: Arc<dyn Texture>,
+    refractive_index: f64,
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(base_color)))
+    }
+
+    pub fn from_texture(base_color: Arc<dyn Texture>) -> Self {
+        let constant = |value| -> Arc<dyn Texture> { Arc::new(SolidColor::gray(value)) };
+        Self {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            transmission: constant(0.0),
+            clearcoat: constant(0.0),
+            sheen: constant(0.0),
+            anisotropy: constant(0.0),
+            refractive_index: 1.5,
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: Arc<dyn Texture>) -> Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: Arc<dyn Texture>) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    pub fn with_refractive_index(mut self, refractive_index: f64) -> Self {
+        self.refractive_index = refractive_index;
+        self
+    }
+
+    /// The parameters looked up at the hit point
+    fn lobes(&self, record: &HitRecord) -> PrincipledLobes {
+        let scalar = |texture: &Arc<dyn Texture>| {
+            scalar_value(texture.as_ref(), record.u, record.v, record.point)
+        };
+        let base_color = self.base_color.value(record.u, record.v, record.point);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let transmission = scalar(&self.transmission);
+        let clearcoat = scalar(&self.clearcoat);
+
+        let aspect = (1.0 - (0.9 * scalar(&self.anisotropy))).sqrt();
+        let alpha = roughness * roughness;
+        let dielectric_reflectance = 0.08 * scalar(&self.specular);
+        let weights = [
+            (1.0 - metallic) * (1.0 - transmission),
+            1.0 - ((1.0 - metallic) * transmission),
+            (1.0 - metallic) * transmission,
+            0.25 * clearcoat,
+        ];
+        let total_weight: f64 = weights.iter().sum();
+        PrincipledLobes {
+            base_color,
+            roughness,
+            sheen: scalar(&self.sheen),
+            specular_color: (Color::new(1.0, 1.0, 1.0)
+                * (dielectric_reflectance * (1.0 - metallic)))
+                + (base_color * metallic),
+            // Kept just rough enough that every lobe has a density
+            specular: TrowbridgeReitz::new(
+                (alpha / aspect).max(MIN_PRINCIPLED_ALPHA),
+                (alpha * aspect).max(MIN_PRINCIPLED_ALPHA),
+            ),
+            coat: TrowbridgeReitz::new(CLEARCOAT_ALPHA, CLEARCOAT_ALPHA),
+            eta: relative_index(self.refractive_index, record),
+            weights,
+            probabilities: weights.map(|weight| weight / total_weight),
+        }
+    }
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self::new(Color::new(0.8, 0.8, 0.8))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let frame = ShadingFrame::from_record(record);
+        let wo = frame.to_local(-incoming_ray.get_direction().unit_vector());
+        if wo.get_z() <= 0.0 {
+            return None;
+        }
+        let lobes = self.lobes(record);
+
+        // Draw the same dimensions whichever lobe is picked
+        let lobe_choice = sampler.get_1d();
+        let direction_sample = sampler.get_2d();
+        let fresnel_choice = sampler.get_1d();
+        let mut lobe = 0;
+        let mut cumulative = lobes.probabilities[0];
+        while lobe_choice >= cumulative && lobe + 1 < lobes.probabilities.len() {
+            lobe += 1;
+            cumulative += lobes.probabilities[lobe];
+        }
+        let wi = match lobe {
+            0 => {
+                let direction = Vector3::new(0.0, 0.0, 1.0)
+                    + Vector3::sample_unit_sphere(direction_sample.0, direction_sample.1);
+                match direction.near_zero() {
+                    true => Vector3::new(0.0, 0.0, 1.0),
+                    false => direction.unit_vector(),
+                }
+            }
+            1 => reflect(wo, lobes.specular.sample_visible(wo, direction_sample)),
+            2 => sample_rough_dielectric(
+                &lobes.specular,
+                wo,
+                lobes.eta,
+                direction_sample,
+                fresnel_choice,
+            )?,
+            _ => reflect(wo, lobes.coat.sample_visible(wo, direction_sample)),
+        };
+
+        let (bsdf_cos, pdf) = lobes.evaluate(wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            scattered_ray: Ray::with_time(
+                record.point,
+                frame.to_world(wi),
+                incoming_ray.get_time(),
+            ),
+            attenuation: bsdf_cos / pdf,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        self.lobes(record).evaluate(wo, wi).0
+    }
+
+    fn pdf(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        let (wo, wi) = local_directions(incoming_ray, record, direction);
+        self.lobes(record).evaluate(wo, wi).1
+    }
+}
+
+/// Below this the highlights of `Principled` would be too sharp to evaluate
+const MIN_PRINCIPLED_ALPHA: f64 = 1e-3;
+
+/// The clear coat is a fixed glossy layer
+const CLEARCOAT_ALPHA: f64 = 0.1;
+
+/// `Principled` at one hit point. The lobes are, in order: diffuse with sheen, specular
+/// reflection, glass and clear coat.
+struct PrincipledLobes {
+    base_color: Color,
+    roughness: f64,
+    sheen: f64,
+    specular_color: Color, // Reflectance at normal incidence
+    specular: TrowbridgeReitz,
+    coat: TrowbridgeReitz,
+    eta: f64,
+    weights: [f64; 4],
+    probabilities: [f64; 4],
+}
+
+impl PrincipledLobes {
+    /// BSDF times cosine and sampling density, summed over the lobes
+    fn evaluate(&self, wo: Vector3, wi: Vector3) -> (Color, f64) {
+        let mut bsdf_cos = Color::default();
+        let mut pdf = 0.0;
+
+        let [diffuse_weight, specular_weight, glass_weight, coat_weight] = self.weights;
+        if glass_weight > 0.0 {
+            let (glass, glass_pdf) = rough_dielectric_lobe(&self.specular, wo, wi, self.eta);
+            let tint = match wi.get_z() < 0.0 {
+                true => self.base_color,
+                false => Color::new(1.0, 1.0, 1.0),
+            };
+            bsdf_cos += tint * (glass * glass_weight);
+            pdf += self.probabilities[2] * glass_pdf;
+        }
+
+        let (cos_outgoing, cos_incident) = (wo.get_z(), wi.get_z());
+        let half = wo + wi;
+        if cos_outgoing <= 0.0 || cos_incident <= 0.0 || half.near_zero() {
+            return (bsdf_cos, pdf);
+        }
+        let half = half.unit_vector();
+        let cos_difference = wi.dot_prod(half);
+
+        if diffuse_weight > 0.0 {
+            // Burley's diffuse brightens at grazing angles on rough surfaces
+            let retro = 0.5 + (2.0 * self.roughness * cos_difference * cos_difference);
+            let diffuse = (1.0 + ((retro - 1.0) * schlick_weight(cos_incident)))
+                * (1.0 + ((retro - 1.0) * schlick_weight(cos_outgoing)))
+                / PI;
+            let sheen = self.sheen * schlick_weight(cos_difference);
+            bsdf_cos += ((self.base_color * diffuse) + Color::new(sheen, sheen, sheen))
+                * (diffuse_weight * cos_incident);
+            pdf += self.probabilities[0] * cos_incident / PI;
+        }
+
+        if specular_weight > 0.0 {
+            let fresnel = schlick(self.specular_color, cos_difference);
+            bsdf_cos += fresnel
+                * (specular_weight * self.specular.d(half) * self.specular.g(wo, wi)
+                    / (4.0 * cos_outgoing));
+            pdf += self.probabilities[1] * self.specular.visible_d(wo, half)
+                / (4.0 * wo.dot_prod(half));
+        }
+
+        if coat_weight > 0.0 {
+            let fresnel = 0.04 + (0.96 * schlick_weight(cos_difference));
+            let coat = coat_weight * fresnel * self.coat.d(half) * self.coat.g(wo, wi)
+                / (4.0 * cos_outgoing);
+            bsdf_cos += Color::new(coat, coat, coat);
+            pdf +=
+                self.probabilities[3] * self.coat.visible_d(wo, half) / (4.0 * wo.dot_prod(half));
+        }
+
+        (bsdf_cos, pdf)
+    }
+}
+
+/// Schlick's (1 - cos)^5 blend towards grazing reflection
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+/// A texture used as a parameter in [0, 1]: the mean of its channels
+fn scalar_value(texture: &dyn Texture, u: f64, v: f64, point: Point3) -> f64 {
+    let value = texture.value(u, v, point);
+    ((value.get_r() + value.get_g() + value.get_b()) / 3.0).clamp(0.0, 1.0)
+}
+
+/// Schlick's approximation of the Fresnel reflectance, from its value at normal incidence
+fn schlick(normal_reflectance: Color, cos_theta: f64) -> Color {
+    let weight = schlick_weight(cos_theta);
+    (normal_reflectance * (1.0 - weight)) + (Color::new(1.0, 1.0, 1.0) * weight)
+}
+
+/// The direction back along the incoming ray and `direction`, both as unit vectors in
+/// the shading frame of the hit
+fn local_directions(
+    incoming_ray: Ray,
+    record: &HitRecord,
+    direction: Vector3,
+) -> (Vector3, Vector3) {
+    let frame = ShadingFrame::from_record(record);
+    (
+        frame.to_local(-incoming_ray.get_direction().unit_vector()),
+        frame.to_local(direction.unit_vector()),
+    )
+}
+
+/// The phase function of a participating medium such as `ConstantMedium`: light is
+/// scattered with probability `albedo`, in a direction drawn from the Henyey-Greenstein
+/// distribution. `anisotropy` (g, in (-1, 1)) is the mean cosine of the scattering angle:
+/// 0 scatters uniformly in all directions, positive values forward (haze), negative
+/// values backward.
+#[derive(Clone)]
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>,
+    anisotropy: f64,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
+        Self {
+            albedo,
+            anisotropy: 0.0,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy.clamp(-0.999, 0.999);
+        self
+    }
+
+    /// Henyey-Greenstein density of turning by an angle with cosine `cos_theta`
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.anisotropy;
+        let denominator = 1.0 + (g * g) - (2.0 * g * cos_theta);
+        (1.0 - (g * g)) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Cosine of the scattering angle, distributed like `phase`
+    fn sample_cos_theta(&self, sample: f64) -> f64 {
+        let g = self.anisotropy;
+        if g.abs() < 1e-3 {
+            return 1.0 - (2.0 * sample);
+        }
+        let ratio = (1.0 - (g * g)) / (1.0 - g + (2.0 * g * sample));
+        ((1.0 + (g * g) - (ratio * ratio)) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Default for Isotropic {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0))
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let (cos_sample, phi_sample) = sampler.get_2d();
+        let cos_theta = self.sample_cos_theta(cos_sample);
+        let sin_theta = (1.0 - (cos_theta * cos_theta)).max(0.0).sqrt();
+        let phi = 2.0 * PI * phi_sample;
+
+        let forward = incoming_ray.get_direction().unit_vector();
+        let (u, v) = forward.orthonormal_basis();
+        let direction =
+            (u * (phi.cos() * sin_theta)) + (v * (phi.sin() * sin_theta)) + (forward * cos_theta);
+        Some(Scatter {
+            scattered_ray: Ray::with_time(record.point, direction, incoming_ray.get_time()),
+            attenuation: self.albedo.value(record.u, record.v, record.point),
+            pdf: Some(self.phase(cos_theta)),
         })
     }
+
+    /// Media have no surface, so there is no cosine factor
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        self.albedo.value(record.u, record.v, record.point)
+            * self.pdf(incoming_ray, record, direction)
+    }
+
+    fn pdf(&self, incoming_ray: Ray, _record: &HitRecord, direction: Vector3) -> f64 {
+        let cos_theta = incoming_ray
+            .get_direction()
+            .unit_vector()
+            .dot_prod(direction.unit_vector());
+        self.phase(cos_theta)
+    }
+}
+
+/// A light-emitting surface: it does not scatter, it only emits `emit`.
+/// Use values above 1.0 to make the light brighter than a white surface.
+#[derive(Clone)]
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+    intensity: f64, // Scales the texture
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)), 1.0)
+    }
+
+    pub fn from_texture(emit: Arc<dyn Texture>, intensity: f64) -> Self {
+        Self { emit, intensity }
+    }
+}
+
+impl Default for DiffuseLight {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0))
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _incoming_ray: Ray,
+        _record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, point: Point3) -> Color {
+        self.emit.value(u, v, point) * self.intensity
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    fn has_non_specular(&self) -> bool {
+        false
+    }
+}
+
+/// A blend of two materials: `weight` 0 is all `first`, 1 all `second`. With a texture
+/// as the weight, the blend follows it across the surface, as a mask would.
+///
+/// Each bounce scatters off one of the two, picked with the probability of its weight.
+/// Unless that bounce is perfectly specular, it is weighted by the blended BSDF and
+/// density, so the mix works with light sampling.
+#[derive(Clone)]
+pub struct MixMaterial {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+    pub fn new(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: f64) -> Self {
+        Self::from_texture(first, second, Arc::new(SolidColor::gray(weight)))
+    }
+
+    pub fn from_texture(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        weight: Arc<dyn Texture>,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            weight,
+        }
+    }
+
+    fn weight(&self, u: f64, v: f64, point: Point3) -> f64 {
+        scalar_value(self.weight.as_ref(), u, v, point)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let weight = self.weight(record.u, record.v, record.point);
+        let chosen = match sampler.get_1d() < weight {
+            true => &self.second,
+            false => &self.first,
+        };
+        let scatter = chosen.scatter(incoming_ray, record, sampler)?;
+        if scatter.pdf.is_none() {
+            // The probability of the choice cancels the weight of the material
+            return Some(scatter);
+        }
+
+        let direction = scatter.scattered_ray.get_direction();
+        let pdf = self.pdf(incoming_ray, record, direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            attenuation: self.eval(incoming_ray, record, direction) / pdf,
+            pdf: Some(pdf),
+            ..scatter
+        })
+    }
+
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        let weight = self.weight(record.u, record.v, record.point);
+        (self.first.eval(incoming_ray, record, direction) * (1.0 - weight))
+            + (self.second.eval(incoming_ray, record, direction) * weight)
+    }
+
+    fn pdf(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        let weight = self.weight(record.u, record.v, record.point);
+        (self.first.pdf(incoming_ray, record, direction) * (1.0 - weight))
+            + (self.second.pdf(incoming_ray, record, direction) * weight)
+    }
+
+    fn emitted(&self, u: f64, v: f64, point: Point3) -> Color {
+        let weight = self.weight(u, v, point);
+        (self.first.emitted(u, v, point) * (1.0 - weight))
+            + (self.second.emitted(u, v, point) * weight)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.first.is_emissive() || self.second.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.first.is_dispersive() || self.second.is_dispersive()
+    }
+
+    fn has_non_specular(&self) -> bool {
+        self.first.has_non_specular() || self.second.has_non_specular()
+    }
+}
+
+/// A smooth clear coat over a `base` material, like varnish on wood or the lacquer of
+/// car paint. Light arriving from outside is mirrored by the coat with its Fresnel
+/// reflectance; the rest reaches the base, and what the base sends back up loses the
+/// coat's reflectance once more on the way out. Light reflected back down inside the
+/// coat is not followed, so thick layers come out a little darker than in reality.
+/// Rays from inside the object see only the base.
+#[derive(Clone)]
+pub struct Coated {
+    base: Arc<dyn Material>,
+    refractive_index: f64, // Of the coat
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, refractive_index: f64) -> Self {
+        Self {
+            base,
+            refractive_index,
+        }
+    }
+
+    /// Fraction of the light crossing the coat along `direction`, which points away from
+    /// the surface, that is not reflected
+    fn coat_transmittance(&self, record: &HitRecord, direction: Vector3) -> f64 {
+        let cos_theta = direction.unit_vector().dot_prod(record.normal);
+        match cos_theta > 0.0 {
+            true => 1.0 - fresnel_dielectric(cos_theta, self.refractive_index),
+            false => 1.0,
+        }
+    }
+}
+
+impl Default for Coated {
+    fn default() -> Self {
+        Self::new(Arc::new(Lambertian::default()), 1.5)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        incoming_ray: Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        if !record.is_face_front {
+            return self.base.scatter(incoming_ray, record, sampler);
+        }
+        let outgoing = -incoming_ray.get_direction().unit_vector();
+        let entering = self.coat_transmittance(record, outgoing);
+        if sampler.get_1d() >= entering {
+            let scattered_ray = Ray::with_time(
+                record.point,
+                reflect(outgoing, record.normal),
+                incoming_ray.get_time(),
+            );
+            return Some(Scatter {
+                scattered_ray,
+                attenuation: Color::new(1.0, 1.0, 1.0),
+                pdf: None,
+            });
+        }
+
+        // Choosing the base with probability `entering` accounts for the way in
+        let scatter = self.base.scatter(incoming_ray, record, sampler)?;
+        let leaving = self.coat_transmittance(record, scatter.scattered_ray.get_direction());
+        Some(Scatter {
+            attenuation: scatter.attenuation * leaving,
+            pdf: scatter.pdf.map(|pdf| pdf * entering),
+            ..scatter
+        })
+    }
+
+    fn eval(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> Color {
+        let base = self.base.eval(incoming_ray, record, direction);
+        match record.is_face_front {
+            true => {
+                let outgoing = -incoming_ray.get_direction();
+                base * (self.coat_transmittance(record, outgoing)
+                    * self.coat_transmittance(record, direction))
+            }
+            false => base,
+        }
+    }
+
+    fn pdf(&self, incoming_ray: Ray, record: &HitRecord, direction: Vector3) -> f64 {
+        let base = self.base.pdf(incoming_ray, record, direction);
+        match record.is_face_front {
+            true => base * self.coat_transmittance(record, -incoming_ray.get_direction()),
+            false => base,
+        }
+    }
+
+    fn emitted(&self, u: f64, v: f64, point: Point3) -> Color {
+        self.base.emitted(u, v, point)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn has_non_specular(&self) -> bool {
+        self.base.has_non_specular()
+    }
 }
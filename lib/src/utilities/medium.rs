@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use super::{
+    aabb::Aabb, geometry::Hittable, hit_record::HitRecord, interval::Interval, material::Material,
+    ray::Ray, sampler::Sampler, vector3::Vector3,
+};
+
+/// A volume of constant density filling a closed `boundary` shape, such as fog or smoke.
+/// A ray going through it scatters after an exponentially distributed distance, the
+/// more likely the denser the medium; where it scatters, `phase_function` (usually an
+/// `Isotropic` material) picks the new direction. The distance is drawn from the sampler
+/// of the path, so only `sample_hit` finds the medium.
+/// The boundary must be convex: a ray is taken to be inside between its first and
+/// second intersection.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    negative_inverse_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            negative_inverse_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    /// Where a ray scatters is a random decision, so without a sampler rays pass through
+    fn hit(&self, _ray: Ray, _ray_interval: Interval) -> Option<HitRecord<'_>> {
+        None
+    }
+
+    fn sample_hit(
+        &self,
+        ray: Ray,
+        ray_interval: Interval,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let entry = self.boundary.hit(ray, Interval::UNIVERSE)?;
+        let exit = self
+            .boundary
+            .hit(ray, Interval::new(entry.parameter + 0.0001, f64::INFINITY))?;
+
+        let entry_parameter = entry.parameter.max(ray_interval.min).max(0.0);
+        let exit_parameter = exit.parameter.min(ray_interval.max);
+        if entry_parameter >= exit_parameter {
+            return None;
+        }
+
+        let ray_length = ray.get_direction().length();
+        let distance_inside = (exit_parameter - entry_parameter) * ray_length;
+        // In (0, 1], so the logarithm stays finite
+        let free_flight = 1.0 - sampler.get_1d();
+        let hit_distance = self.negative_inverse_density * free_flight.ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let parameter = entry_parameter + (hit_distance / ray_length);
+        // The normal and the face are arbitrary, phase functions do not use them
+        Some(HitRecord::new(
+            ray.position(parameter),
+            Vector3::new(1.0, 0.0, 0.0),
+            parameter,
+            true,
+            &*self.phase_function,
+            0.0,
+            0.0,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
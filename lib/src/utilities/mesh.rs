@@ -0,0 +1,160 @@
+use std::{path::Path, sync::Arc};
+
+use super::{
+    aabb::Aabb,
+    bvh::BvhNode,
+    color::Color,
+    geometry::{Hittable, Triangle},
+    hit_record::HitRecord,
+    interval::Interval,
+    material::{Dielectric, Lambertian, Material, Metal},
+    point::Point3,
+    ray::Ray,
+    transform::Transform,
+    vector3::Vector3,
+};
+
+/// A triangle mesh, stored in its own BVH so that it behaves
+/// like a single object inside the scene
+pub struct TriangleMesh {
+    triangles: BvhNode,
+    triangle_count: usize,
+}
+
+impl TriangleMesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let triangle_count = triangles.len();
+        let objects: Vec<Box<dyn Hittable>> = triangles
+            .into_iter()
+            .map(|triangle| Box::new(triangle) as Box<dyn Hittable>)
+            .collect();
+        Self {
+            triangles: BvhNode::new(objects),
+            triangle_count,
+        }
+    }
+
+    /// Loads a Wavefront OBJ file and the MTL libraries it references.
+    /// Faces with more than three vertices are triangulated.
+    /// Shapes without an MTL material use `default_material`.
+    /// `transform` is baked into the vertices and normals.
+    pub fn from_obj(
+        path: &Path,
+        transform: &Transform,
+        default_material: Arc<dyn Material>,
+    ) -> Result<Self, tobj::LoadError> {
+        let load_options = tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ..Default::default()
+        };
+        let (models, mtl_result) = tobj::load_obj(path, &load_options)?;
+        // A missing or broken MTL file is not fatal, the default material is used instead
+        let mtl_materials: Vec<Arc<dyn Material>> = mtl_result
+            .unwrap_or_default()
+            .iter()
+            .map(material_from_mtl)
+            .collect();
+
+        let mut triangles: Vec<Triangle> = Vec::new();
+        for model in models.iter() {
+            let mesh = &model.mesh;
+            let material = mesh
+                .material_id
+                .and_then(|id| mtl_materials.get(id))
+                .cloned()
+                .unwrap_or_else(|| default_material.clone());
+
+            let position = |index: usize| {
+                transform.apply_point(Point3::new(
+                    mesh.positions[3 * index] as f64,
+                    mesh.positions[(3 * index) + 1] as f64,
+                    mesh.positions[(3 * index) + 2] as f64,
+                ))
+            };
+            let normal = |index: usize| {
+                transform
+                    .apply_normal(Vector3::new(
+                        mesh.normals[3 * index] as f64,
+                        mesh.normals[(3 * index) + 1] as f64,
+                        mesh.normals[(3 * index) + 2] as f64,
+                    ))
+                    .unit_vector()
+            };
+            let uv = |index: usize| {
+                (
+                    mesh.texcoords[2 * index] as f64,
+                    mesh.texcoords[(2 * index) + 1] as f64,
+                )
+            };
+
+            for face in mesh.indices.chunks_exact(3) {
+                let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+                let mut triangle =
+                    Triangle::new(position(i0), position(i1), position(i2), material.clone());
+                if !mesh.normals.is_empty() {
+                    triangle = triangle.with_vertex_normals([normal(i0), normal(i1), normal(i2)]);
+                }
+                if !mesh.texcoords.is_empty() {
+                    triangle = triangle.with_vertex_uvs([uv(i0), uv(i1), uv(i2)]);
+                }
+                triangles.push(triangle);
+            }
+        }
+
+        Ok(Self::new(triangles))
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: Ray, ray_interval: Interval) -> Option<HitRecord<'_>> {
+        self.triangles.hit(ray, ray_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.triangles.bounding_box()
+    }
+}
+
+/// Maps an MTL material onto the closest of our materials:
+/// transparent materials (low `d`, high `Tr`, or a glass `illum` model) become `Dielectric` with `Ni`,
+/// materials whose specular color `Ks` outweighs the diffuse `Kd` become `Metal` with a fuzz
+/// derived from the shininess `Ns`, and everything else becomes `Lambertian` with `Kd`.
+fn material_from_mtl(mtl: &tobj::Material) -> Arc<dyn Material> {
+    let to_color = |rgb: [f32; 3]| Color::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64);
+    let luminance = |color: Color| {
+        (0.2126 * color.get_r()) + (0.7152 * color.get_g()) + (0.0722 * color.get_b())
+    };
+
+    let transparency = match mtl.dissolve {
+        Some(dissolve) => 1.0 - dissolve as f64,
+        None => mtl
+            .unknown_param
+            .get("Tr")
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .unwrap_or(0.0),
+    };
+    let is_glass_model = matches!(mtl.illumination_model, Some(4) | Some(6) | Some(7));
+    if transparency > 0.5 || is_glass_model {
+        let refractive_index = mtl.optical_density.map(|ni| ni as f64).unwrap_or(1.5);
+        return Arc::new(Dielectric::new(refractive_index));
+    }
+
+    let diffuse = mtl
+        .diffuse
+        .map(to_color)
+        .unwrap_or(Color::new(0.8, 0.8, 0.8));
+    let specular = mtl.specular.map(to_color).unwrap_or_default();
+    if luminance(specular) > luminance(diffuse) {
+        // Convert the Phong exponent to a roughness-like fuzz: Ns = 0 is fully rough
+        let shininess = mtl.shininess.map(|ns| ns as f64).unwrap_or(0.0).max(0.0);
+        let fuzz = (2.0 / (shininess + 2.0)).sqrt();
+        return Arc::new(Metal::new(specular, fuzz));
+    }
+
+    Arc::new(Lambertian::new(diffuse))
+}
@@ -0,0 +1,245 @@
+use std::f64::consts::PI;
+
+use super::{
+    color::Color,
+    hit_record::HitRecord,
+    vector3::{Cross, Vector3},
+};
+
+/// An orthonormal basis around the shading normal. Local coordinates put the normal
+/// on z and, when the surface has them, the u tangent on x, so that anisotropic
+/// roughness follows the surface parameterization.
+#[derive(Clone, Copy)]
+pub struct ShadingFrame {
+    tangent: Vector3,
+    bitangent: Vector3,
+    normal: Vector3,
+}
+
+impl ShadingFrame {
+    pub fn new(normal: Vector3, dpdu: Vector3) -> Self {
+        let tangent = dpdu - (normal * normal.dot_prod(dpdu));
+        match tangent.near_zero() {
+            true => {
+                let (tangent, bitangent) = normal.orthonormal_basis();
+                Self {
+                    tangent,
+                    bitangent,
+                    normal,
+                }
+            }
+            false => {
+                let tangent = tangent.unit_vector();
+                Self {
+                    tangent,
+                    bitangent: normal.cross_prod(tangent),
+                    normal,
+                }
+            }
+        }
+    }
+
+    pub fn from_record(record: &HitRecord) -> Self {
+        Self::new(record.normal, record.dpdu)
+    }
+
+    pub fn to_local(&self, vector: Vector3) -> Vector3 {
+        Vector3::new(
+            vector.dot_prod(self.tangent),
+            vector.dot_prod(self.bitangent),
+            vector.dot_prod(self.normal),
+        )
+    }
+
+    pub fn to_world(&self, vector: Vector3) -> Vector3 {
+        (self.tangent * vector.get_x())
+            + (self.bitangent * vector.get_y())
+            + (self.normal * vector.get_z())
+    }
+}
+
+/// Below this, a surface is treated as perfectly smooth and sampled as a mirror
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals, in the local shading
+/// frame with z along the normal. `alpha_x` and `alpha_y` are the widths of the
+/// distribution along the tangent and the bitangent.
+#[derive(Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    /// Perceptual roughness in [0, 1], squared into the distribution width
+    pub fn from_roughness(roughness: f64) -> Self {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        Self::new(alpha, alpha)
+    }
+
+    pub fn get_alpha_x(&self) -> f64 {
+        self.alpha_x
+    }
+
+    pub fn get_alpha_y(&self) -> f64 {
+        self.alpha_y
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacet normals, with respect to projected area
+    pub fn d(&self, normal: Vector3) -> f64 {
+        let x = normal.get_x() / self.alpha_x;
+        let y = normal.get_y() / self.alpha_y;
+        let z = normal.get_z();
+        let length_squared = (x * x) + (y * y) + (z * z);
+        1.0 / (PI * self.alpha_x * self.alpha_y * length_squared * length_squared)
+    }
+
+    /// Smith's auxiliary function: the masked microfacet area per visible area along `w`
+    pub fn lambda(&self, w: Vector3) -> f64 {
+        let cos_squared = w.get_z() * w.get_z();
+        if cos_squared == 0.0 {
+            return f64::INFINITY;
+        }
+        let x = w.get_x() * self.alpha_x;
+        let y = w.get_y() * self.alpha_y;
+        let tan_squared_alpha = ((x * x) + (y * y)) / cos_squared;
+        ((1.0 + tan_squared_alpha).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of the microfacets seen from `w` that are not masked
+    pub fn g1(&self, w: Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith shadowing-masking for the pair of directions
+    pub fn g(&self, wo: Vector3, wi: Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the microfacet normals visible from `w`
+    pub fn visible_d(&self, w: Vector3, normal: Vector3) -> f64 {
+        let cos_theta = w.get_z().abs();
+        if cos_theta == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / cos_theta * self.d(normal) * w.dot_prod(normal).abs()
+    }
+
+    /// A microfacet normal drawn from `visible_d` for `w`, which must be above the
+    /// surface (Heitz, "Sampling the GGX Distribution of Visible Normals", 2018)
+    pub fn sample_visible(&self, w: Vector3, sample: (f64, f64)) -> Vector3 {
+        // Stretch to the hemisphere configuration
+        let stretched = Vector3::new(
+            self.alpha_x * w.get_x(),
+            self.alpha_y * w.get_y(),
+            w.get_z(),
+        )
+        .unit_vector();
+        let length_squared =
+            (stretched.get_x() * stretched.get_x()) + (stretched.get_y() * stretched.get_y());
+        let first_axis = match length_squared > 0.0 {
+            true => {
+                Vector3::new(-stretched.get_y(), stretched.get_x(), 0.0) / length_squared.sqrt()
+            }
+            false => Vector3::new(1.0, 0.0, 0.0),
+        };
+        let second_axis = stretched.cross_prod(first_axis);
+
+        // A point on the projected disk, squeezed onto the visible half
+        let radius = sample.0.sqrt();
+        let phi = 2.0 * PI * sample.1;
+        let t1 = radius * phi.cos();
+        let t2 = radius * phi.sin();
+        let blend = 0.5 * (1.0 + stretched.get_z());
+        let t2 = ((1.0 - blend) * (1.0 - (t1 * t1)).max(0.0).sqrt()) + (blend * t2);
+        let height = (1.0 - (t1 * t1) - (t2 * t2)).max(0.0).sqrt();
+        let hemisphere_normal = (first_axis * t1) + (second_axis * t2) + (stretched * height);
+
+        // Unstretch
+        Vector3::new(
+            self.alpha_x * hemisphere_normal.get_x(),
+            self.alpha_y * hemisphere_normal.get_y(),
+            hemisphere_normal.get_z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
+
+/// Mirror `w` about `normal`; both point away from the surface
+pub fn reflect(w: Vector3, normal: Vector3) -> Vector3 {
+    (normal * (2.0 * w.dot_prod(normal))) - w
+}
+
+/// Refract `w` through a surface with `normal` on the side of `w`, where `eta` is the
+/// refractive index on the far side over the one on the near side.
+/// `None` on total internal reflection.
+pub fn refract(w: Vector3, normal: Vector3, eta: f64) -> Option<Vector3> {
+    let cos_incident = w.dot_prod(normal);
+    let sin_squared_incident = (1.0 - (cos_incident * cos_incident)).max(0.0);
+    let sin_squared_transmitted = sin_squared_incident / (eta * eta);
+    if sin_squared_transmitted >= 1.0 {
+        return None;
+    }
+    let cos_transmitted = (1.0 - sin_squared_transmitted).sqrt();
+    Some((-w / eta) + (normal * ((cos_incident / eta) - cos_transmitted)))
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface, for light arriving at
+/// an angle with cosine `cos_incident` and `eta` as in `refract`
+pub fn fresnel_dielectric(cos_incident: f64, eta: f64) -> f64 {
+    let (cos_incident, eta) = match cos_incident < 0.0 {
+        true => (-cos_incident, 1.0 / eta),
+        false => (cos_incident, eta),
+    };
+    let cos_incident = cos_incident.min(1.0);
+    let sin_squared_transmitted = (1.0 - (cos_incident * cos_incident)) / (eta * eta);
+    if sin_squared_transmitted >= 1.0 {
+        return 1.0;
+    }
+    let cos_transmitted = (1.0 - sin_squared_transmitted).max(0.0).sqrt();
+    let parallel =
+        ((eta * cos_incident) - cos_transmitted) / ((eta * cos_incident) + cos_transmitted);
+    let perpendicular =
+        (cos_incident - (eta * cos_transmitted)) / (cos_incident + (eta * cos_transmitted));
+    ((parallel * parallel) + (perpendicular * perpendicular)) / 2.0
+}
+
+/// Fresnel reflectance of a metal with complex refractive index `eta + i k`, for one
+/// wavelength
+pub fn fresnel_complex(cos_incident: f64, eta: f64, k: f64) -> f64 {
+    let cos_squared = cos_incident.clamp(0.0, 1.0).powi(2);
+    let sin_squared = 1.0 - cos_squared;
+    let difference = (eta * eta) - (k * k) - sin_squared;
+    let sum_squared = ((difference * difference) + (4.0 * eta * eta * k * k)).sqrt();
+    let a = (0.5 * (sum_squared + difference)).max(0.0).sqrt();
+    let cos_incident = cos_squared.sqrt();
+
+    let two_a_cos = 2.0 * a * cos_incident;
+    let perpendicular =
+        (sum_squared - two_a_cos + cos_squared) / (sum_squared + two_a_cos + cos_squared);
+    let two_a_cos_sin = two_a_cos * sin_squared;
+    let sin_fourth = sin_squared * sin_squared;
+    let parallel = perpendicular * ((cos_squared * sum_squared) - two_a_cos_sin + sin_fourth)
+        / ((cos_squared * sum_squared) + two_a_cos_sin + sin_fourth);
+    (perpendicular + parallel) / 2.0
+}
+
+/// `fresnel_complex` for the red, green and blue indices
+pub fn fresnel_conductor(cos_incident: f64, eta: Color, k: Color) -> Color {
+    Color::new(
+        fresnel_complex(cos_incident, eta.get_r(), k.get_r()),
+        fresnel_complex(cos_incident, eta.get_g(), k.get_g()),
+        fresnel_complex(cos_incident, eta.get_b(), k.get_b()),
+    )
+}
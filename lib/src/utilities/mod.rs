@@ -1,10 +1,26 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
 pub mod camera;
 pub mod color;
 pub mod geometry;
+pub mod hdr;
 pub mod hit_record;
+pub mod image;
+pub mod instance;
 pub mod interval;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod microfacet;
 pub mod point;
+pub mod random;
 pub mod ray;
+pub mod sampler;
+pub mod scene_description;
+pub mod scene_error;
 pub mod scenes;
+pub mod spectrum;
+pub mod texture;
+pub mod transform;
 pub mod vector3;
@@ -0,0 +1,71 @@
+/// PCG32 random number generator (O'Neill, "PCG: A Family of Simple Fast
+/// Space-Efficient Statistically Good Algorithms for Random Number Generation").
+/// Small, fast and fully determined by its seed and stream.
+#[derive(Clone)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64, // Selects the stream, always odd
+}
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+impl Pcg32 {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut generator = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        generator.next_u32();
+        generator.state = generator.state.wrapping_add(seed);
+        generator.next_u32();
+        generator
+    }
+
+    /// A generator on the default stream
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(seed, PCG_DEFAULT_STREAM)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Uniform in [0, 1), with 53 random bits
+    pub fn next_f64(&mut self) -> f64 {
+        let high = (self.next_u32() as u64) << 21;
+        let low = (self.next_u32() as u64) >> 11;
+        (high | low) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+impl Default for Pcg32 {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
+
+/// Combines values (seed, pixel index, sample index, ...) into one well-mixed seed
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |hash, value| {
+        mix(hash
+            ^ value
+                .wrapping_add(0x9e3779b97f4a7c15)
+                .wrapping_add(hash << 6)
+                .wrapping_add(hash >> 2))
+    })
+}
+
+/// The SplitMix64 finalizer
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
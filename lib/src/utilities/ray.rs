@@ -4,11 +4,29 @@ use super::{point::Point3, vector3::Vector3};
 pub struct Ray {
     origin: Point3,
     direction: Vector3,
+    time: f64, // When the ray was sent, for objects that move while the shutter is open
+    wavelength: Option<f64>, // Hero wavelength in nanometers when rendering spectrally
 }
 
 impl Ray {
+    /// A ray at time 0
     pub fn new(origin: Point3, direction: Vector3) -> Self {
-        Self { origin, direction }
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    pub fn with_time(origin: Point3, direction: Vector3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+        }
+    }
+
+    /// The same ray, carrying the hero wavelength of a spectral path (`None` for RGB)
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Self {
+        self.wavelength = wavelength;
+        self
     }
 
     pub fn position(&self, parameter: f64) -> Point3 {
@@ -22,4 +40,10 @@ impl Ray {
     pub fn get_direction(&self) -> Vector3 {
         self.direction
     }
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+    pub fn get_wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
 }
@@ -0,0 +1,377 @@
+use serde::Deserialize;
+
+use super::random::{self, Pcg32};
+
+/// Source of the sample values used while tracing a path.
+/// Each camera sample asks for a sequence of dimensions (pixel position, lens position,
+/// then one or two values per bounce); samplers that know how many samples a pixel takes
+/// spread the values of each dimension evenly over [0, 1).
+pub trait Sampler {
+    /// Moves to sample `sample_index` of pixel (`x`, `y`) and restarts at the first dimension
+    fn start_pixel_sample(&mut self, x: i32, y: i32, sample_index: i32);
+
+    /// The next dimension, in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    /// The next two dimensions, in [0, 1)^2
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// Which `Sampler` a camera uses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerKind {
+    /// Independent uniform random values from a PCG generator
+    Independent,
+    /// Jittered strata, shuffled independently per dimension
+    Stratified,
+    /// Owen-scrambled Halton sequence
+    Halton,
+    /// Owen-scrambled Sobol sequence, padded from two-dimensional pairs
+    #[default]
+    Sobol,
+}
+
+impl SamplerKind {
+    /// A sampler for pixels that take `samples_per_pixel` samples.
+    /// The same `seed` always gives the same values.
+    pub fn create(self, samples_per_pixel: i32, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Where a sampler is in its sequence: the pixel sample and the next dimension
+#[derive(Clone, Copy, Default)]
+struct SamplePosition {
+    x: i32,
+    y: i32,
+    sample_index: i32,
+    dimension: u32,
+}
+
+impl SamplePosition {
+    /// Seed for everything drawn for `dimension` of this pixel, shared by all its samples
+    fn dimension_hash(&self, seed: u64, dimension: u32) -> u64 {
+        random::hash(&[seed, self.x as u64, self.y as u64, dimension as u64])
+    }
+
+    /// Seed for everything drawn for `dimension` of this pixel sample
+    fn sample_hash(&self, seed: u64, dimension: u32) -> u64 {
+        random::hash(&[
+            seed,
+            self.x as u64,
+            self.y as u64,
+            self.sample_index as u64,
+            dimension as u64,
+        ])
+    }
+}
+
+/// Uniform random values: each pixel sample gets its own PCG stream
+pub struct IndependentSampler {
+    seed: u64,
+    generator: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            generator: Pcg32::from_seed(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: i32, y: i32, sample_index: i32) {
+        self.generator = Pcg32::from_seed(random::hash(&[
+            self.seed,
+            x as u64,
+            y as u64,
+            sample_index as u64,
+        ]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.generator.next_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.generator.next_f64(), self.generator.next_f64())
+    }
+}
+
+/// Divides each dimension into one stratum per sample and jitters the value inside it.
+/// Two-dimensional requests use a grid of strata as close to square as the sample count allows.
+/// The strata are visited in a different random order for every dimension.
+pub struct StratifiedSampler {
+    x_strata: i32,
+    y_strata: i32,
+    seed: u64,
+    position: SamplePosition,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: i32, seed: u64) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let mut x_strata = (samples_per_pixel as f64).sqrt() as i32;
+        while samples_per_pixel % x_strata != 0 {
+            x_strata -= 1;
+        }
+        Self {
+            x_strata,
+            y_strata: samples_per_pixel / x_strata,
+            seed,
+            position: SamplePosition::default(),
+        }
+    }
+
+    /// The stratum of the current sample in `dimension`, and a jitter value
+    fn stratum(&mut self, dimension: u32) -> (u32, f64) {
+        let count = (self.x_strata * self.y_strata) as u32;
+        let permutation_seed = self.position.dimension_hash(self.seed, dimension) as u32;
+        let stratum = permutation_element(
+            self.position.sample_index as u32 % count,
+            count,
+            permutation_seed,
+        );
+        let jitter = Pcg32::from_seed(self.position.sample_hash(self.seed, dimension)).next_f64();
+        (stratum, jitter)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: i32, y: i32, sample_index: i32) {
+        self.position = SamplePosition {
+            x,
+            y,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.dimension;
+        self.position.dimension += 1;
+        let (stratum, jitter) = self.stratum(dimension);
+        (stratum as f64 + jitter) / (self.x_strata * self.y_strata) as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.position.dimension;
+        self.position.dimension += 2;
+        let (stratum, jitter_x) = self.stratum(dimension);
+        let jitter_y =
+            Pcg32::from_seed(self.position.sample_hash(self.seed, dimension + 1)).next_f64();
+        let (stratum_x, stratum_y) = (
+            stratum % self.x_strata as u32,
+            stratum / self.x_strata as u32,
+        );
+        (
+            (stratum_x as f64 + jitter_x) / self.x_strata as f64,
+            (stratum_y as f64 + jitter_y) / self.y_strata as f64,
+        )
+    }
+}
+
+/// The Halton sequence: dimension `d` is the radical inverse of the sample index in the
+/// `d`-th prime base. Each pixel scrambles the digits with its own random Owen scrambling,
+/// so neighbouring pixels are not correlated. Dimensions past the prime table fall back
+/// to independent random values.
+pub struct HaltonSampler {
+    seed: u64,
+    position: SamplePosition,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: SamplePosition::default(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: i32, y: i32, sample_index: i32) {
+        self.position = SamplePosition {
+            x,
+            y,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.dimension;
+        self.position.dimension += 1;
+        match PRIMES.get(dimension as usize) {
+            Some(base) => owen_scrambled_radical_inverse(
+                *base,
+                self.position.sample_index as u64,
+                self.position.dimension_hash(self.seed, dimension),
+            ),
+            None => Pcg32::from_seed(self.position.sample_hash(self.seed, dimension)).next_f64(),
+        }
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+/// The first two dimensions of the Sobol sequence, reused for every pair of dimensions
+/// with an independent Owen scrambling and sample order per pair (Burley, "Practical
+/// Hash-based Owen Scrambling", 2020). Well stratified for any power-of-two sample count.
+pub struct SobolSampler {
+    seed: u64,
+    position: SamplePosition,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: SamplePosition::default(),
+        }
+    }
+
+    /// The sample index in a random order that is specific to `dimension`
+    fn shuffled_index(&self, dimension: u32) -> u32 {
+        let shuffle_seed = self.position.dimension_hash(self.seed, dimension) as u32;
+        nested_uniform_scramble(self.position.sample_index as u32, shuffle_seed)
+    }
+
+    fn scrambled_value(&self, index: u32, sobol_dimension: usize, dimension: u32) -> f64 {
+        let scramble_seed = (self.position.dimension_hash(self.seed, dimension) >> 32) as u32;
+        let value = nested_uniform_scramble(sobol(index, sobol_dimension), scramble_seed);
+        (value as f64 * (1.0 / 4294967296.0)).min(ONE_MINUS_EPSILON)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: i32, y: i32, sample_index: i32) {
+        self.position = SamplePosition {
+            x,
+            y,
+            sample_index,
+            dimension: 0,
+        };
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.dimension;
+        self.position.dimension += 1;
+        let index = self.shuffled_index(dimension);
+        self.scrambled_value(index, 0, dimension)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.position.dimension;
+        self.position.dimension += 2;
+        let index = self.shuffled_index(dimension);
+        (
+            self.scrambled_value(index, 0, dimension),
+            self.scrambled_value(index, 1, dimension + 1),
+        )
+    }
+}
+
+/// Largest f64 below 1
+const ONE_MINUS_EPSILON: f64 = 1.0 - (f64::EPSILON / 2.0);
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Radical inverse of `index` in `base`, with every digit permuted by a
+/// permutation that depends on `seed` and on the digits before it
+fn owen_scrambled_radical_inverse(base: u64, index: u64, seed: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut digit_weight = inverse_base;
+    let mut prefix_hash = seed;
+    let mut remaining = index;
+    let mut result = 0.0;
+    // Leading zero digits are permuted too; stop once digits are below the precision of the result
+    while 1.0 - ((base - 1) as f64 * digit_weight) < 1.0 {
+        let next = remaining / base;
+        let digit = remaining - (next * base);
+        let permuted_digit = permutation_element(digit as u32, base as u32, prefix_hash as u32);
+        result += permuted_digit as f64 * digit_weight;
+        prefix_hash = random::hash(&[prefix_hash, digit]);
+        digit_weight *= inverse_base;
+        remaining = next;
+    }
+    result.min(ONE_MINUS_EPSILON)
+}
+
+/// Element `index` of a random permutation of 0..`length` selected by `seed`,
+/// without building the permutation (Kensler, "Correlated Multi-Jittered Sampling")
+fn permutation_element(index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    let mut value = index;
+    loop {
+        value ^= seed;
+        value = value.wrapping_mul(0xe170893d);
+        value ^= seed >> 16;
+        value ^= (value & mask) >> 4;
+        value ^= seed >> 8;
+        value = value.wrapping_mul(0x0929eb3f);
+        value ^= seed >> 23;
+        value ^= (value & mask) >> 1;
+        value = value.wrapping_mul(1 | seed >> 27);
+        value = value.wrapping_mul(0x6935fa69);
+        value ^= (value & mask) >> 11;
+        value = value.wrapping_mul(0x74dcb303);
+        value ^= (value & mask) >> 2;
+        value = value.wrapping_mul(0x9e501cc3);
+        value ^= (value & mask) >> 2;
+        value = value.wrapping_mul(0xc860a3df);
+        value &= mask;
+        value ^= value >> 5;
+        if value < length {
+            break;
+        }
+    }
+    (value.wrapping_add(seed)) % length
+}
+
+/// Dimension 0 (van der Corput) or 1 of the Sobol sequence, as a 32-bit fixed point fraction
+fn sobol(index: u32, dimension: usize) -> u32 {
+    let mut result = 0;
+    let mut direction: u32 = 1 << 31;
+    for bit in 0..32 {
+        if (index >> bit) & 1 == 1 {
+            result ^= direction;
+        }
+        direction = match dimension {
+            0 => direction >> 1,
+            _ => direction ^ (direction >> 1),
+        };
+    }
+    result
+}
+
+/// Owen scrambling of a 32-bit fixed point fraction: every bit is flipped
+/// depending on the bits above it (Laine and Karras hash, improved by Burley)
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    let mut reversed = value.reverse_bits();
+    reversed = reversed.wrapping_add(seed);
+    reversed ^= reversed.wrapping_mul(0x6c50b47c);
+    reversed ^= reversed.wrapping_mul(0xb82f1e52);
+    reversed ^= reversed.wrapping_mul(0xc7afe638);
+    reversed ^= reversed.wrapping_mul(0x8d22f6e6);
+    reversed.reverse_bits()
+}
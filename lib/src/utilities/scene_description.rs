@@ -0,0 +1,1776 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{
+    background::{Background, EnvironmentMap},
+    bvh::BvhNode,
+    camera::Camera,
+    color::Color,
+    geometry::{Cuboid, Disk, Hittable, MovingSphere, Plane, Quad, Sphere, Triangle},
+    instance::Instance,
+    material::{
+        Coated, Conductor, ConductorPreset, Dielectric, DiffuseLight, FresnelModel, Isotropic,
+        Lambertian, Material, Metal, MixMaterial, Principled, RoughDielectric,
+    },
+    medium::ConstantMedium,
+    mesh::TriangleMesh,
+    point::Point3,
+    sampler::SamplerKind,
+    scene_error::{join_index, join_key, SceneError, SceneErrorKind},
+    scenes::{self, Scene},
+    spectrum::{Dispersion, LAMBDA_MAX, LAMBDA_MIN},
+    texture::{Checker, ImageTexture, NoiseStyle, NoiseTexture, SolidColor, Texture, WrapMode},
+    transform::Transform,
+    vector3::{Cross, Vector3},
+};
+
+/// The scene file version this build reads
+pub const SCENE_VERSION: u32 = 1;
+
+/// A complete, versioned description of a render: camera, image size,
+/// sampling settings, background, named materials and objects.
+/// Vectors and colors are written as `[x, y, z]` / `[r, g, b]` arrays,
+/// colors in linear [0, 1] units.
+/// In the file, backgrounds, materials and objects are JSON objects with a `type` field
+/// naming the variant (e.g. `{"type": "sphere", ...}`); use `from_json` to read one.
+#[derive(Deserialize)]
+pub struct SceneDescription {
+    pub version: u32,
+    #[serde(default)]
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub image: ImageDescription,
+    #[serde(default)]
+    pub render: RenderDescription,
+    #[serde(default)]
+    pub background: BackgroundDescription,
+    /// Materials that objects can refer to by name
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CameraDescription {
+    pub look_from: [f64; 3],
+    pub look_at: [f64; 3],
+    pub up: [f64; 3],
+    pub vertical_fov: f64,           // In degrees
+    pub defocus_angle: f64,          // In degrees; 0 is a pinhole camera with everything sharp
+    pub focus_distance: Option<f64>, // Defaults to the distance from look_from to look_at
+    pub shutter: [f64; 2], // Open and close times; objects that move in between are blurred
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        let camera = Camera::new();
+        Self {
+            look_from: point_to_array(camera.look_from),
+            look_at: point_to_array(camera.look_at),
+            up: vector_to_array(camera.vertical_camera_up),
+            vertical_fov: camera.vertical_field_of_view,
+            defocus_angle: camera.defocus_angle,
+            focus_distance: None,
+            shutter: [camera.shutter_open, camera.shutter_close],
+        }
+    }
+}
+
+/// Image size: `width` and either `height` or `aspect_ratio`
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ImageDescription {
+    pub width: i32,
+    pub height: Option<i32>,
+    pub aspect_ratio: f64,
+}
+
+impl Default for ImageDescription {
+    fn default() -> Self {
+        let camera = Camera::new();
+        Self {
+            width: camera.image_width,
+            height: None,
+            aspect_ratio: camera.aspect_ratio,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RenderDescription {
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    pub seed: u64,            // Renders with the same seed are identical
+    pub sampler: SamplerKind, // `independent`, `stratified`, `halton` or `sobol`
+    pub spectral: bool,       // Trace wavelengths instead of RGB, so glass can disperse
+}
+
+impl Default for RenderDescription {
+    fn default() -> Self {
+        Self {
+            samples_per_pixel: Camera::new().samples_per_pixel,
+            max_depth: 50,
+            seed: 0,
+            sampler: SamplerKind::default(),
+            spectral: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    Gradient {
+        bottom: [f64; 3],
+        top: [f64; 3],
+    },
+    Environment {
+        file: String, // Radiance .hdr file, relative to the scene file
+        #[serde(default)]
+        rotation: f64, // In degrees, about the vertical axis
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        Self::Gradient {
+            bottom: [1.0, 1.0, 1.0],
+            top: [0.5, 0.7, 1.0],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialDescription {
+    Lambertian {
+        albedo: TextureSlot,
+    },
+    Metal {
+        albedo: TextureSlot,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    /// Polished glass; `absorption` is the Beer-Lambert coefficient per unit of distance.
+    /// A `dispersion` varies the index with wavelength in spectral renders; without a
+    /// `refractive_index`, RGB renders use its index at the sodium d line.
+    Dielectric {
+        refractive_index: Option<f64>,
+        dispersion: Option<Dispersion>,
+        #[serde(default)]
+        fresnel: FresnelModel,
+        #[serde(default)]
+        absorption: [f64; 3],
+    },
+    /// A GGX microfacet metal: a named `metal`, or its complex index `eta` and `k`
+    /// (which override the preset's when both are given)
+    Conductor {
+        metal: Option<ConductorPreset>,
+        eta: Option<[f64; 3]>,
+        k: Option<[f64; 3]>,
+        #[serde(default)]
+        roughness: f64,
+    },
+    /// Frosted glass: a GGX microfacet dielectric
+    RoughDielectric {
+        refractive_index: f64,
+        #[serde(default)]
+        roughness: f64,
+    },
+    DiffuseLight {
+        emit: TextureSlot,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    /// The principled uber material; every parameter but `refractive_index` can be a texture
+    Principled {
+        #[serde(default = "default_base_color")]
+        base_color: TextureSlot,
+        #[serde(default)]
+        metallic: ScalarSlot,
+        #[serde(default = "default_half")]
+        roughness: ScalarSlot,
+        #[serde(default = "default_half")]
+        specular: ScalarSlot,
+        #[serde(default)]
+        transmission: ScalarSlot,
+        #[serde(default)]
+        clearcoat: ScalarSlot,
+        #[serde(default)]
+        sheen: ScalarSlot,
+        #[serde(default)]
+        anisotropy: ScalarSlot,
+        #[serde(default = "default_refractive_index")]
+        refractive_index: f64,
+    },
+    /// The phase function of a `constant_medium`; `anisotropy` is the Henyey-Greenstein g
+    Isotropic {
+        albedo: TextureSlot,
+        #[serde(default)]
+        anisotropy: f64,
+    },
+    /// Two inline materials blended by `weight` (0 is all `first`), a number or a texture
+    Mix {
+        first: Box<MaterialDescription>,
+        second: Box<MaterialDescription>,
+        #[serde(default = "default_half")]
+        weight: ScalarSlot,
+    },
+    /// A clear coat over an inline `base` material
+    Coated {
+        base: Box<MaterialDescription>,
+        #[serde(default = "default_refractive_index")]
+        refractive_index: f64,
+    },
+}
+
+/// A material color: one color everywhere, or a texture.
+/// In the file this is an `[r, g, b]` array or a texture object.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureSlot {
+    Color([f64; 3]),
+    Texture(Box<TextureDescription>),
+}
+
+/// A material parameter between 0 and 1: one value everywhere, or a texture read as the
+/// mean of its channels. In the file this is a number or a texture object.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalarSlot {
+    Value(f64),
+    Texture(Box<TextureDescription>),
+}
+
+impl Default for ScalarSlot {
+    fn default() -> Self {
+        ScalarSlot::Value(0.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    /// Cubes `scale` wide in space, alternating between `even` and `odd`
+    Checker {
+        #[serde(default = "default_checker_scale")]
+        scale: f64,
+        even: TextureSlot,
+        odd: TextureSlot,
+    },
+    /// A PNG, JPEG or Radiance HDR file mapped onto the surface coordinates
+    Image {
+        file: String,
+        #[serde(default)]
+        wrap: WrapMode,
+    },
+    /// Perlin noise; higher `scale` gives finer detail
+    Noise {
+        #[serde(default = "default_noise_scale")]
+        scale: f64,
+        #[serde(default)]
+        style: NoiseStyle,
+        #[serde(default = "default_noise_color")]
+        color: [f64; 3],
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+/// A material given either by the name of an entry in `materials` or inline.
+/// In the file this is just the name string or the material object.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialReference {
+    Named(String),
+    Inline(MaterialDescription),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectDescription {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        material: MaterialReference,
+    },
+    /// A sphere moving from `start` at time 0 to `end` at time 1
+    MovingSphere {
+        start: [f64; 3],
+        end: [f64; 3],
+        radius: f64,
+        material: MaterialReference,
+    },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+        normals: Option<[[f64; 3]; 3]>,
+        uvs: Option<[[f64; 2]; 3]>,
+        material: MaterialReference,
+    },
+    /// A parallelogram with one corner at `corner` and the edges `u` and `v`
+    Quad {
+        corner: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        material: MaterialReference,
+    },
+    /// An infinite plane through `point`
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+        material: MaterialReference,
+    },
+    Disk {
+        center: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+        material: MaterialReference,
+    },
+    /// An axis-aligned box between the corners `min` and `max`
+    #[serde(rename = "box")]
+    Cuboid {
+        min: [f64; 3],
+        max: [f64; 3],
+        material: MaterialReference,
+    },
+    /// A Wavefront OBJ file, relative to the scene file. `material` is used
+    /// for the shapes that have no MTL material. The mesh is scaled, rotated about x,
+    /// then y, then z, and translated, like an `instance` with those steps in that order.
+    Mesh {
+        file: String,
+        #[serde(default)]
+        translate: [f64; 3],
+        #[serde(default)]
+        rotate: [f64; 3], // In degrees, about x, then y, then z
+        #[serde(default)]
+        scale: ScaleDescription,
+        material: Option<MaterialReference>,
+    },
+    /// Another object moved into place by a list of transform steps, applied in order.
+    /// Instances of the same mesh file share one copy of its triangles.
+    Instance {
+        object: Box<ObjectDescription>,
+        transform: Vec<TransformStep>,
+    },
+    /// Fog or smoke of constant `density` filling the convex `boundary` object,
+    /// scattering with `material` (usually `isotropic`). The boundary's own material is not used.
+    ConstantMedium {
+        boundary: Box<ObjectDescription>,
+        density: f64,
+        material: MaterialReference,
+    },
+    /// The whole final scene of "Ray Tracing in One Weekend": a gray ground plane through
+    /// the origin facing +y, a `2 * grid` by `2 * grid` grid of small random spheres, and four
+    /// spheres of radius 1 at y = 1, z = 0: lambertian at x = -4, glass of index 1.55 at
+    /// x = 0, metal at x = 4 and glass of index 1 / 1.55 at x = 8
+    RandomSpheres {
+        #[serde(default = "default_grid")]
+        grid: i32,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+/// One step of an instance transform
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformStep {
+    Translate {
+        offset: [f64; 3],
+    },
+    /// Counter-clockwise about `axis`
+    Rotate {
+        axis: [f64; 3],
+        degrees: f64,
+    },
+    Scale {
+        factor: ScaleDescription,
+    },
+    /// Moves the origin to `from` and turns +z towards `at`, keeping +y close to `up`
+    LookAt {
+        from: [f64; 3],
+        at: [f64; 3],
+        #[serde(default = "default_up")]
+        up: [f64; 3],
+    },
+}
+
+/// A uniform scale factor or one factor per axis
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a number or an [x, y, z] array")]
+pub enum ScaleDescription {
+    Uniform(f64),
+    PerAxis([f64; 3]),
+}
+
+impl Default for ScaleDescription {
+    fn default() -> Self {
+        Self::Uniform(1.0)
+    }
+}
+
+impl ScaleDescription {
+    fn factors(&self) -> [f64; 3] {
+        match self {
+            ScaleDescription::Uniform(factor) => [*factor; 3],
+            ScaleDescription::PerAxis(factors) => *factors,
+        }
+    }
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_base_color() -> TextureSlot {
+    TextureSlot::Color([0.8, 0.8, 0.8])
+}
+
+fn default_half() -> ScalarSlot {
+    ScalarSlot::Value(0.5)
+}
+
+fn default_refractive_index() -> f64 {
+    1.5
+}
+
+fn default_grid() -> i32 {
+    7
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_checker_scale() -> f64 {
+    1.0
+}
+
+fn default_noise_scale() -> f64 {
+    4.0
+}
+
+fn default_noise_color() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn array_to_point(array: [f64; 3]) -> Point3 {
+    Point3::new(array[0], array[1], array[2])
+}
+
+/// The steps of a `mesh` object's placement, as one transform
+fn mesh_transform(translate: [f64; 3], rotate: [f64; 3], scale: &ScaleDescription) -> Transform {
+    [
+        Transform::scale(array_to_vector(scale.factors())),
+        Transform::rotate_x(rotate[0]),
+        Transform::rotate_y(rotate[1]),
+        Transform::rotate_z(rotate[2]),
+        Transform::translate(array_to_vector(translate)),
+    ]
+    .iter()
+    .fold(Transform::IDENTITY, |transform, step| transform.then(step))
+}
+
+fn array_to_vector(array: [f64; 3]) -> Vector3 {
+    Vector3::new(array[0], array[1], array[2])
+}
+
+fn array_to_color(array: [f64; 3]) -> Color {
+    Color::new(array[0], array[1], array[2])
+}
+
+fn point_to_array(point: Point3) -> [f64; 3] {
+    [point.get_x(), point.get_y(), point.get_z()]
+}
+
+fn vector_to_array(vector: Vector3) -> [f64; 3] {
+    [vector.get_x(), vector.get_y(), vector.get_z()]
+}
+
+impl SceneDescription {
+    /// Parses a scene file. In `strict` mode keys that are not part of the schema
+    /// are rejected, otherwise they are ignored. Values are only range-checked by `validate`.
+    pub fn from_json(json: &str, strict: bool) -> Result<Self, SceneError> {
+        let mut document: Value =
+            serde_json::from_str(json).map_err(|e| SceneError::from_json(e, ""))?;
+        check_version(&document)?;
+        retag_document(&mut document)?;
+
+        let mut unknown_fields: Vec<String> = Vec::new();
+        let retagged = document.clone();
+        let description = serde_path_to_error::deserialize(serde_ignored::Deserializer::new(
+            document,
+            &mut |path: serde_ignored::Path| unknown_fields.push(ignored_path_to_string(&path)),
+        ))
+        .map_err(|e| {
+            let path = tracked_path_to_string(e.path());
+            let is_keyword = value_at(&retagged, e.path()).is_some_and(Value::is_string);
+            let error = SceneError::from_json(e.into_inner(), &path);
+            // An unknown keyword (e.g. a sampler name) is a bad value, not an unknown `type`
+            match is_keyword && matches!(error.get_kind(), SceneErrorKind::UnknownType) {
+                true => SceneError::new(SceneErrorKind::OutOfRange, &path, error.get_message()),
+                false => error,
+            }
+        })?;
+
+        if strict {
+            if let Some(path) = unknown_fields.first() {
+                return Err(SceneError::new(
+                    SceneErrorKind::UnknownField,
+                    path,
+                    "not part of the scene schema",
+                ));
+            }
+        }
+        Ok(description)
+    }
+
+    /// Checks that every value is within its allowed range and that every
+    /// material name refers to an entry in `materials`
+    pub fn validate(&self) -> Result<(), SceneError> {
+        if self.version != SCENE_VERSION {
+            return Err(unsupported_version(self.version as u64));
+        }
+
+        let image = &self.image;
+        check(image.width >= 1, "image.width", "must be at least 1")?;
+        if let Some(height) = image.height {
+            check(height >= 1, "image.height", "must be at least 1")?;
+        }
+        check(
+            image.aspect_ratio > 0.0 && image.aspect_ratio.is_finite(),
+            "image.aspect_ratio",
+            "must be positive",
+        )?;
+
+        let render = &self.render;
+        check(
+            render.samples_per_pixel >= 1,
+            "render.samples_per_pixel",
+            "must be at least 1",
+        )?;
+        check(
+            render.max_depth >= 1,
+            "render.max_depth",
+            "must be at least 1",
+        )?;
+
+        let camera = &self.camera;
+        check(
+            camera.vertical_fov > 0.0 && camera.vertical_fov < 180.0,
+            "camera.vertical_fov",
+            "must be between 0 and 180 degrees",
+        )?;
+        check(
+            camera.defocus_angle >= 0.0 && camera.defocus_angle < 180.0,
+            "camera.defocus_angle",
+            "must be at least 0 and below 180 degrees",
+        )?;
+        if let Some(focus_distance) = camera.focus_distance {
+            check(
+                focus_distance > 0.0,
+                "camera.focus_distance",
+                "must be positive",
+            )?;
+        }
+        check(
+            camera.shutter[1] >= camera.shutter[0],
+            "camera.shutter",
+            "must not close before it opens",
+        )?;
+        check(
+            camera.look_from != camera.look_at,
+            "camera.look_at",
+            "must differ from look_from",
+        )?;
+
+        match &self.background {
+            BackgroundDescription::Solid { color } => {
+                check_color(*color, "background.color", false)?;
+            }
+            BackgroundDescription::Gradient { bottom, top } => {
+                check_color(*bottom, "background.bottom", false)?;
+                check_color(*top, "background.top", false)?;
+            }
+            BackgroundDescription::Environment { intensity, .. } => {
+                check(
+                    *intensity >= 0.0,
+                    "background.intensity",
+                    "must not be negative",
+                )?;
+            }
+        }
+
+        for (name, material) in self.materials.iter() {
+            material.validate(&join_key("materials", name))?;
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            object.validate(&join_index("objects", index), &self.materials)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the description, then creates the camera and the objects.
+    /// Relative file names (meshes, environment maps) are resolved against `scene_dir`.
+    pub fn build(&self, scene_dir: &Path) -> Result<Scene, SceneError> {
+        self.validate()?;
+
+        let mut builder = ObjectBuilder {
+            scene_dir,
+            named_materials: self
+                .materials
+                .iter()
+                .map(|(name, description)| {
+                    let material = description.build(scene_dir, &join_key("materials", name))?;
+                    Ok((name.as_str(), material))
+                })
+                .collect::<Result<_, SceneError>>()?,
+            meshes: BTreeMap::new(),
+            world: Vec::new(),
+            lights: Vec::new(),
+        };
+        for (index, object) in self.objects.iter().enumerate() {
+            builder.add(object, &join_index("objects", index))?;
+        }
+
+        Ok(Scene {
+            camera: self.build_camera(scene_dir)?,
+            world: builder.world,
+            lights: builder.lights,
+        })
+    }
+
+    fn build_camera(&self, scene_dir: &Path) -> Result<Camera, SceneError> {
+        let mut camera = Camera::new();
+
+        camera.image_width = self.image.width;
+        match self.image.height {
+            Some(height) => {
+                camera.image_height = height;
+                camera.aspect_ratio = self.image.width as f64 / height as f64;
+            }
+            None => camera.aspect_ratio = self.image.aspect_ratio,
+        }
+        camera.samples_per_pixel = self.render.samples_per_pixel;
+        camera.max_depth = self.render.max_depth;
+        camera.seed = self.render.seed;
+        camera.sampler = self.render.sampler;
+        camera.spectral = self.render.spectral;
+
+        camera.look_from = array_to_point(self.camera.look_from);
+        camera.look_at = array_to_point(self.camera.look_at);
+        camera.vertical_camera_up = array_to_vector(self.camera.up);
+        camera.vertical_field_of_view = self.camera.vertical_fov;
+        camera.defocus_angle = self.camera.defocus_angle;
+        camera.focus_distance = self
+            .camera
+            .focus_distance
+            .unwrap_or_else(|| (camera.look_from - camera.look_at).as_vec().length());
+        [camera.shutter_open, camera.shutter_close] = self.camera.shutter;
+
+        camera.background = match &self.background {
+            BackgroundDescription::Solid { color } => Background::Solid(array_to_color(*color)),
+            BackgroundDescription::Gradient { bottom, top } => Background::Gradient {
+                bottom: array_to_color(*bottom),
+                top: array_to_color(*top),
+            },
+            BackgroundDescription::Environment {
+                file,
+                rotation,
+                intensity,
+            } => {
+                let map_path = scene_dir.join(file);
+                let environment_map = EnvironmentMap::from_hdr_file(
+                    &map_path, *rotation, *intensity,
+                )
+                .map_err(|e| {
+                    SceneError::new(
+                        SceneErrorKind::Resource,
+                        "background.file",
+                        format!("{}: {}", map_path.display(), e),
+                    )
+                })?;
+                Background::Environment(environment_map)
+            }
+        };
+
+        Ok(camera)
+    }
+}
+
+/// Meshes loaded for instances, by file, baked transform and material name
+type MeshKey = (PathBuf, [u64; 9], String);
+
+/// Creates the objects of a scene, collecting the emitting ones as lights
+struct ObjectBuilder<'a> {
+    scene_dir: &'a Path,
+    named_materials: BTreeMap<&'a str, Arc<dyn Material>>,
+    meshes: BTreeMap<MeshKey, Arc<TriangleMesh>>,
+    world: Vec<Box<dyn Hittable>>,
+    lights: Vec<Box<dyn Hittable>>,
+}
+
+impl ObjectBuilder<'_> {
+    fn resolve_material(
+        &self,
+        reference: &MaterialReference,
+        path: &str,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        match reference {
+            MaterialReference::Named(name) => self
+                .named_materials
+                .get(name.as_str())
+                .cloned()
+                .ok_or_else(|| unknown_material(name, path)),
+            MaterialReference::Inline(description) => description.build(self.scene_dir, path),
+        }
+    }
+
+    /// Builds `object` (at `path` in the file) into the world
+    fn add(&mut self, object: &ObjectDescription, path: &str) -> Result<(), SceneError> {
+        let material_path = join_key(path, "material");
+        match object {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let material = self.resolve_material(material, &material_path)?;
+                let sphere = Sphere::new(array_to_point(*center), *radius, material.clone());
+                if material.is_emissive() {
+                    self.lights.push(Box::new(sphere.clone()));
+                }
+                self.world.push(Box::new(sphere));
+            }
+            ObjectDescription::MovingSphere {
+                start,
+                end,
+                radius,
+                material,
+            } => {
+                let material = self.resolve_material(material, &material_path)?;
+                self.world.push(Box::new(MovingSphere::new(
+                    array_to_point(*start),
+                    array_to_point(*end),
+                    *radius,
+                    material,
+                )));
+            }
+            ObjectDescription::Quad {
+                corner,
+                u,
+                v,
+                material,
+            } => {
+                let material = self.resolve_material(material, &material_path)?;
+                let quad = Quad::new(
+                    array_to_point(*corner),
+                    array_to_vector(*u),
+                    array_to_vector(*v),
+                    material.clone(),
+                );
+                if material.is_emissive() {
+                    self.lights.push(Box::new(quad.clone()));
+                }
+                self.world.push(Box::new(quad));
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                material,
+            } => self.world.push(Box::new(Plane::new(
+                array_to_point(*point),
+                array_to_vector(*normal),
+                self.resolve_material(material, &material_path)?,
+            ))),
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                let material = self.resolve_material(material, &material_path)?;
+                let disk = Disk::new(
+                    array_to_point(*center),
+                    array_to_vector(*normal),
+                    *radius,
+                    material.clone(),
+                );
+                if material.is_emissive() {
+                    self.lights.push(Box::new(disk.clone()));
+                }
+                self.world.push(Box::new(disk));
+            }
+            ObjectDescription::Cuboid { min, max, material } => {
+                let material = self.resolve_material(material, &material_path)?;
+                let cuboid =
+                    Cuboid::new(array_to_point(*min), array_to_point(*max), material.clone());
+                if material.is_emissive() {
+                    self.lights.push(Box::new(cuboid.clone()));
+                }
+                self.world.push(Box::new(cuboid));
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let [v0, v1, v2] = vertices.map(array_to_point);
+                let mut triangle =
+                    Triangle::new(v0, v1, v2, self.resolve_material(material, &material_path)?);
+                if let Some(normals) = normals {
+                    triangle = triangle.with_vertex_normals(normals.map(array_to_vector));
+                }
+                if let Some(uvs) = uvs {
+                    triangle = triangle.with_vertex_uvs(uvs.map(|uv| (uv[0], uv[1])));
+                }
+                self.world.push(Box::new(triangle));
+            }
+            ObjectDescription::Mesh { .. } => {
+                let mesh = self.load_mesh(object, path)?;
+                self.world.push(Box::new(mesh));
+            }
+            ObjectDescription::Instance {
+                object: instanced,
+                transform,
+            } => {
+                let instanced = self.build_shared(instanced, &join_key(path, "object"))?;
+                let transform = transform
+                    .iter()
+                    .fold(Transform::IDENTITY, |transform, step| {
+                        transform.then(&step.build())
+                    });
+                self.world
+                    .push(Box::new(Instance::new(instanced, transform)));
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                density,
+                material,
+            } => {
+                let boundary = self.build_shared(boundary, &join_key(path, "boundary"))?;
+                let phase_function = self.resolve_material(material, &material_path)?;
+                self.world.push(Box::new(ConstantMedium::new(
+                    boundary,
+                    *density,
+                    phase_function,
+                )));
+            }
+            ObjectDescription::RandomSpheres { grid, seed } => {
+                scenes::generate_scene(&mut self.world, *grid, *seed);
+            }
+        }
+        Ok(())
+    }
+
+    fn load_mesh(
+        &self,
+        object: &ObjectDescription,
+        path: &str,
+    ) -> Result<TriangleMesh, SceneError> {
+        let ObjectDescription::Mesh {
+            file,
+            translate,
+            rotate,
+            scale,
+            material,
+        } = object
+        else {
+            unreachable!("load_mesh is only called for meshes");
+        };
+        let transform = mesh_transform(*translate, *rotate, scale);
+        let default_material: Arc<dyn Material> = match material {
+            Some(reference) => self.resolve_material(reference, &join_key(path, "material"))?,
+            None => Arc::new(Lambertian::default()),
+        };
+        let mesh_path = self.scene_dir.join(file);
+        TriangleMesh::from_obj(&mesh_path, &transform, default_material).map_err(|e| {
+            SceneError::new(
+                SceneErrorKind::Resource,
+                &join_key(path, "file"),
+                format!("{}: {}", mesh_path.display(), e),
+            )
+        })
+    }
+
+    /// Builds `object` on its own, to be placed by an instance.
+    /// Meshes with a named (or no) material are loaded once and shared by all their instances.
+    fn build_shared(
+        &mut self,
+        object: &ObjectDescription,
+        path: &str,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        if let ObjectDescription::Mesh {
+            file,
+            translate,
+            rotate,
+            scale,
+            material,
+        } = object
+        {
+            let material_name = match material {
+                Some(MaterialReference::Named(name)) => Some(name.clone()),
+                Some(MaterialReference::Inline(_)) => None,
+                None => Some(String::new()),
+            };
+            if let Some(material_name) = material_name {
+                let mut transform_bits = [0; 9];
+                for (bits, value) in transform_bits
+                    .iter_mut()
+                    .zip(translate.iter().chain(rotate).chain(&scale.factors()))
+                {
+                    *bits = value.to_bits();
+                }
+                let key = (self.scene_dir.join(file), transform_bits, material_name);
+                if let Some(mesh) = self.meshes.get(&key) {
+                    return Ok(mesh.clone());
+                }
+                let mesh = Arc::new(self.load_mesh(object, path)?);
+                self.meshes.insert(key, mesh.clone());
+                return Ok(mesh);
+            }
+        }
+
+        // Build into an empty world; lights inside instances are not sampled
+        let world = std::mem::take(&mut self.world);
+        let lights = std::mem::take(&mut self.lights);
+        let result = self.add(object, path);
+        let mut objects = std::mem::replace(&mut self.world, world);
+        self.lights = lights;
+        result?;
+        Ok(match objects.len() {
+            1 => Arc::from(objects.remove(0)),
+            _ => Arc::new(BvhNode::new(objects)),
+        })
+    }
+}
+
+impl MaterialDescription {
+    /// Creates the material, loading its image textures relative to `scene_dir`.
+    /// `path` is the position of the material in the file, for errors.
+    pub fn build(&self, scene_dir: &Path, path: &str) -> Result<Arc<dyn Material>, SceneError> {
+        let texture = |slot: &TextureSlot, key: &str| slot.build(scene_dir, &join_key(path, key));
+        Ok(match self {
+            MaterialDescription::Lambertian { albedo } => {
+                Arc::new(Lambertian::from_texture(texture(albedo, "albedo")?))
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                Arc::new(Metal::from_texture(texture(albedo, "albedo")?, *fuzz))
+            }
+            MaterialDescription::Dielectric {
+                refractive_index,
+                dispersion,
+                fresnel,
+                absorption,
+            } => {
+                let dielectric = match (refractive_index, dispersion) {
+                    (Some(refractive_index), Some(dispersion)) => {
+                        Dielectric::new(*refractive_index).with_dispersion(*dispersion)
+                    }
+                    (Some(refractive_index), None) => Dielectric::new(*refractive_index),
+                    (None, Some(dispersion)) => Dielectric::from_dispersion(*dispersion),
+                    (None, None) => return Err(missing_refractive_index(path)),
+                };
+                Arc::new(
+                    dielectric
+                        .with_fresnel(*fresnel)
+                        .with_absorption(array_to_color(*absorption)),
+                )
+            }
+            MaterialDescription::Conductor {
+                metal,
+                eta,
+                k,
+                roughness,
+            } => {
+                let preset = metal.map(ConductorPreset::complex_ior);
+                let (Some(eta), Some(k)) = (
+                    eta.map(array_to_color).or(preset.map(|(eta, _)| eta)),
+                    k.map(array_to_color).or(preset.map(|(_, k)| k)),
+                ) else {
+                    return Err(missing_complex_ior(path));
+                };
+                Arc::new(Conductor::new(eta, k, *roughness))
+            }
+            MaterialDescription::RoughDielectric {
+                refractive_index,
+                roughness,
+            } => Arc::new(RoughDielectric::new(*refractive_index, *roughness)),
+            MaterialDescription::DiffuseLight { emit, intensity } => Arc::new(
+                DiffuseLight::from_texture(texture(emit, "emit")?, *intensity),
+            ),
+            MaterialDescription::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                transmission,
+                clearcoat,
+                sheen,
+                anisotropy,
+                refractive_index,
+            } => {
+                let scalar =
+                    |slot: &ScalarSlot, key: &str| slot.build(scene_dir, &join_key(path, key));
+                Arc::new(
+                    Principled::from_texture(texture(base_color, "base_color")?)
+                        .with_metallic(scalar(metallic, "metallic")?)
+                        .with_roughness(scalar(roughness, "roughness")?)
+                        .with_specular(scalar(specular, "specular")?)
+                        .with_transmission(scalar(transmission, "transmission")?)
+                        .with_clearcoat(scalar(clearcoat, "clearcoat")?)
+                        .with_sheen(scalar(sheen, "sheen")?)
+                        .with_anisotropy(scalar(anisotropy, "anisotropy")?)
+                        .with_refractive_index(*refractive_index),
+                )
+            }
+            MaterialDescription::Isotropic { albedo, anisotropy } => Arc::new(
+                Isotropic::from_texture(texture(albedo, "albedo")?).with_anisotropy(*anisotropy),
+            ),
+            MaterialDescription::Mix {
+                first,
+                second,
+                weight,
+            } => Arc::new(MixMaterial::from_texture(
+                first.build(scene_dir, &join_key(path, "first"))?,
+                second.build(scene_dir, &join_key(path, "second"))?,
+                weight.build(scene_dir, &join_key(path, "weight"))?,
+            )),
+            MaterialDescription::Coated {
+                base,
+                refractive_index,
+            } => Arc::new(Coated::new(
+                base.build(scene_dir, &join_key(path, "base"))?,
+                *refractive_index,
+            )),
+        })
+    }
+
+    fn validate(&self, path: &str) -> Result<(), SceneError> {
+        match self {
+            MaterialDescription::Lambertian { albedo } => {
+                albedo.validate(&join_key(path, "albedo"), true)
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                albedo.validate(&join_key(path, "albedo"), true)?;
+                check(
+                    (0.0..=1.0).contains(fuzz),
+                    &join_key(path, "fuzz"),
+                    "must be between 0 and 1",
+                )
+            }
+            MaterialDescription::Dielectric {
+                refractive_index,
+                dispersion,
+                absorption,
+                ..
+            } => {
+                if refractive_index.is_none() && dispersion.is_none() {
+                    return Err(missing_refractive_index(path));
+                }
+                if let Some(refractive_index) = refractive_index {
+                    check(
+                        *refractive_index > 0.0,
+                        &join_key(path, "refractive_index"),
+                        "must be positive",
+                    )?;
+                }
+                if let Some(dispersion) = dispersion {
+                    // Sellmeier poles inside the visible range leave bands without an index
+                    let steps = ((LAMBDA_MAX - LAMBDA_MIN) / 10.0) as usize;
+                    check(
+                        (0..=steps)
+                            .map(|step| LAMBDA_MIN + (10.0 * step as f64))
+                            .all(|lambda| dispersion.refractive_index(lambda) > 0.0),
+                        &join_key(path, "dispersion"),
+                        "must give a positive index over the visible range",
+                    )?;
+                }
+                check_color(*absorption, &join_key(path, "absorption"), false)
+            }
+            MaterialDescription::Conductor {
+                metal,
+                eta,
+                k,
+                roughness,
+            } => {
+                if metal.is_none() && (eta.is_none() || k.is_none()) {
+                    return Err(missing_complex_ior(path));
+                }
+                if let Some(eta) = eta {
+                    check(
+                        eta.iter().all(|value| *value > 0.0),
+                        &join_key(path, "eta"),
+                        "components must be positive",
+                    )?;
+                }
+                if let Some(k) = k {
+                    check_color(*k, &join_key(path, "k"), false)?;
+                }
+                check_roughness(*roughness, path)
+            }
+            MaterialDescription::RoughDielectric {
+                refractive_index,
+                roughness,
+            } => {
+                check(
+                    *refractive_index > 0.0,
+                    &join_key(path, "refractive_index"),
+                    "must be positive",
+                )?;
+                check_roughness(*roughness, path)
+            }
+            MaterialDescription::DiffuseLight { emit, intensity } => {
+                emit.validate(&join_key(path, "emit"), false)?;
+                check(
+                    *intensity >= 0.0,
+                    &join_key(path, "intensity"),
+                    "must not be negative",
+                )
+            }
+            MaterialDescription::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                transmission,
+                clearcoat,
+                sheen,
+                anisotropy,
+                refractive_index,
+            } => {
+                base_color.validate(&join_key(path, "base_color"), true)?;
+                for (key, slot) in [
+                    ("metallic", metallic),
+                    ("roughness", roughness),
+                    ("specular", specular),
+                    ("transmission", transmission),
+                    ("clearcoat", clearcoat),
+                    ("sheen", sheen),
+                    ("anisotropy", anisotropy),
+                ] {
+                    slot.validate(&join_key(path, key))?;
+                }
+                check(
+                    *refractive_index > 0.0,
+                    &join_key(path, "refractive_index"),
+                    "must be positive",
+                )
+            }
+            MaterialDescription::Isotropic { albedo, anisotropy } => {
+                albedo.validate(&join_key(path, "albedo"), true)?;
+                check(
+                    anisotropy.abs() < 1.0,
+                    &join_key(path, "anisotropy"),
+                    "must be between -1 and 1 (exclusive)",
+                )
+            }
+            MaterialDescription::Mix {
+                first,
+                second,
+                weight,
+            } => {
+                first.validate(&join_key(path, "first"))?;
+                second.validate(&join_key(path, "second"))?;
+                weight.validate(&join_key(path, "weight"))
+            }
+            MaterialDescription::Coated {
+                base,
+                refractive_index,
+            } => {
+                base.validate(&join_key(path, "base"))?;
+                check(
+                    *refractive_index > 0.0,
+                    &join_key(path, "refractive_index"),
+                    "must be positive",
+                )
+            }
+        }
+    }
+}
+
+impl TextureSlot {
+    fn build(&self, scene_dir: &Path, path: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            TextureSlot::Color(color) => Ok(Arc::new(SolidColor::new(array_to_color(*color)))),
+            TextureSlot::Texture(description) => description.build(scene_dir, path),
+        }
+    }
+
+    /// Reflectances (`is_reflectance`) must stay within [0, 1], other colors must not be negative
+    fn validate(&self, path: &str, is_reflectance: bool) -> Result<(), SceneError> {
+        match self {
+            TextureSlot::Color(color) => check_color(*color, path, is_reflectance),
+            TextureSlot::Texture(description) => description.validate(path, is_reflectance),
+        }
+    }
+}
+
+impl ScalarSlot {
+    fn build(&self, scene_dir: &Path, path: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            ScalarSlot::Value(value) => Ok(Arc::new(SolidColor::gray(*value))),
+            ScalarSlot::Texture(description) => description.build(scene_dir, path),
+        }
+    }
+
+    fn validate(&self, path: &str) -> Result<(), SceneError> {
+        match self {
+            ScalarSlot::Value(value) => {
+                check((0.0..=1.0).contains(value), path, "must be between 0 and 1")
+            }
+            ScalarSlot::Texture(description) => description.validate(path, true),
+        }
+    }
+}
+
+impl TextureDescription {
+    fn build(&self, scene_dir: &Path, path: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        Ok(match self {
+            TextureDescription::Solid { color } => {
+                Arc::new(SolidColor::new(array_to_color(*color)))
+            }
+            TextureDescription::Checker { scale, even, odd } => Arc::new(Checker::new(
+                *scale,
+                even.build(scene_dir, &join_key(path, "even"))?,
+                odd.build(scene_dir, &join_key(path, "odd"))?,
+            )),
+            TextureDescription::Image { file, wrap } => {
+                let image_path = scene_dir.join(file);
+                Arc::new(ImageTexture::from_file(&image_path, *wrap).map_err(|e| {
+                    SceneError::new(
+                        SceneErrorKind::Resource,
+                        &join_key(path, "file"),
+                        format!("{}: {}", image_path.display(), e),
+                    )
+                })?)
+            }
+            TextureDescription::Noise {
+                scale,
+                style,
+                color,
+                seed,
+            } => Arc::new(NoiseTexture::new(
+                *scale,
+                *style,
+                array_to_color(*color),
+                *seed,
+            )),
+        })
+    }
+
+    fn validate(&self, path: &str, is_reflectance: bool) -> Result<(), SceneError> {
+        match self {
+            TextureDescription::Solid { color } => {
+                check_color(*color, &join_key(path, "color"), is_reflectance)
+            }
+            TextureDescription::Checker { scale, even, odd } => {
+                check(*scale > 0.0, &join_key(path, "scale"), "must be positive")?;
+                even.validate(&join_key(path, "even"), is_reflectance)?;
+                odd.validate(&join_key(path, "odd"), is_reflectance)
+            }
+            TextureDescription::Image { .. } => Ok(()),
+            TextureDescription::Noise { scale, color, .. } => {
+                check(*scale > 0.0, &join_key(path, "scale"), "must be positive")?;
+                check_color(*color, &join_key(path, "color"), is_reflectance)
+            }
+        }
+    }
+}
+
+impl TransformStep {
+    fn build(&self) -> Transform {
+        match self {
+            TransformStep::Translate { offset } => Transform::translate(array_to_vector(*offset)),
+            TransformStep::Rotate { axis, degrees } => {
+                Transform::rotate(array_to_vector(*axis), *degrees)
+            }
+            TransformStep::Scale { factor } => Transform::scale(array_to_vector(factor.factors())),
+            TransformStep::LookAt { from, at, up } => Transform::look_at(
+                array_to_point(*from),
+                array_to_point(*at),
+                array_to_vector(*up),
+            ),
+        }
+    }
+
+    fn validate(&self, path: &str) -> Result<(), SceneError> {
+        match self {
+            TransformStep::Translate { .. } => Ok(()),
+            TransformStep::Rotate { axis, .. } => check_direction(*axis, &join_key(path, "axis")),
+            TransformStep::Scale { factor } => check(
+                factor.factors().iter().all(|factor| *factor != 0.0),
+                &join_key(path, "factor"),
+                "must not be zero",
+            ),
+            TransformStep::LookAt { from, at, up } => {
+                check(from != at, &join_key(path, "at"), "must differ from `from`")?;
+                let direction = array_to_vector(*at) - array_to_vector(*from);
+                check(
+                    !array_to_vector(*up).cross_prod(direction).near_zero(),
+                    &join_key(path, "up"),
+                    "must not be parallel to the viewing direction",
+                )
+            }
+        }
+    }
+}
+
+impl MaterialReference {
+    fn validate(
+        &self,
+        path: &str,
+        materials: &BTreeMap<String, MaterialDescription>,
+    ) -> Result<(), SceneError> {
+        match self {
+            MaterialReference::Named(name) => match materials.contains_key(name) {
+                true => Ok(()),
+                false => Err(unknown_material(name, path)),
+            },
+            MaterialReference::Inline(description) => description.validate(path),
+        }
+    }
+}
+
+impl ObjectDescription {
+    fn validate(
+        &self,
+        path: &str,
+        materials: &BTreeMap<String, MaterialDescription>,
+    ) -> Result<(), SceneError> {
+        let material_path = join_key(path, "material");
+        match self {
+            ObjectDescription::Sphere {
+                radius, material, ..
+            }
+            | ObjectDescription::MovingSphere {
+                radius, material, ..
+            } => {
+                check(*radius > 0.0, &join_key(path, "radius"), "must be positive")?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Quad { u, v, material, .. } => {
+                let normal = array_to_vector(*u).cross_prod(array_to_vector(*v));
+                check(
+                    !normal.near_zero(),
+                    &join_key(path, "v"),
+                    "must not be parallel to u",
+                )?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Plane {
+                normal, material, ..
+            } => {
+                check_direction(*normal, &join_key(path, "normal"))?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Disk {
+                normal,
+                radius,
+                material,
+                ..
+            } => {
+                check_direction(*normal, &join_key(path, "normal"))?;
+                check(*radius > 0.0, &join_key(path, "radius"), "must be positive")?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Cuboid { min, max, material } => {
+                check(
+                    min.iter().zip(max.iter()).all(|(min, max)| min < max),
+                    &join_key(path, "max"),
+                    "must be greater than min on every axis",
+                )?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Triangle { material, .. } => {
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::Mesh {
+                scale, material, ..
+            } => {
+                check(
+                    scale.factors().iter().all(|factor| *factor != 0.0),
+                    &join_key(path, "scale"),
+                    "must not be zero",
+                )?;
+                match material {
+                    Some(material) => material.validate(&material_path, materials),
+                    None => Ok(()),
+                }
+            }
+            ObjectDescription::Instance { object, transform } => {
+                object.validate(&join_key(path, "object"), materials)?;
+                let transform_path = join_key(path, "transform");
+                for (index, step) in transform.iter().enumerate() {
+                    step.validate(&join_index(&transform_path, index))?;
+                }
+                Ok(())
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                density,
+                material,
+            } => {
+                boundary.validate(&join_key(path, "boundary"), materials)?;
+                check(
+                    *density > 0.0,
+                    &join_key(path, "density"),
+                    "must be positive",
+                )?;
+                material.validate(&material_path, materials)
+            }
+            ObjectDescription::RandomSpheres { grid, .. } => {
+                check(*grid >= 0, &join_key(path, "grid"), "must not be negative")
+            }
+        }
+    }
+}
+
+fn check(condition: bool, path: &str, message: &str) -> Result<(), SceneError> {
+    match condition {
+        true => Ok(()),
+        false => Err(SceneError::new(SceneErrorKind::OutOfRange, path, message)),
+    }
+}
+
+fn check_direction(direction: [f64; 3], path: &str) -> Result<(), SceneError> {
+    check(
+        !array_to_vector(direction).near_zero(),
+        path,
+        "must not be the zero vector",
+    )
+}
+
+/// Colors must not be negative; reflectances must also not exceed 1
+fn check_color(color: [f64; 3], path: &str, is_reflectance: bool) -> Result<(), SceneError> {
+    match is_reflectance {
+        true => check(
+            color.iter().all(|value| (0.0..=1.0).contains(value)),
+            path,
+            "components must be between 0 and 1",
+        ),
+        false => check(
+            color.iter().all(|value| *value >= 0.0),
+            path,
+            "components must not be negative",
+        ),
+    }
+}
+
+fn check_roughness(roughness: f64, material_path: &str) -> Result<(), SceneError> {
+    check(
+        (0.0..=1.0).contains(&roughness),
+        &join_key(material_path, "roughness"),
+        "must be between 0 and 1",
+    )
+}
+
+fn missing_refractive_index(material_path: &str) -> SceneError {
+    SceneError::new(
+        SceneErrorKind::MissingField,
+        &join_key(material_path, "refractive_index"),
+        "a dielectric needs a `refractive_index` or a `dispersion`",
+    )
+}
+
+fn missing_complex_ior(material_path: &str) -> SceneError {
+    SceneError::new(
+        SceneErrorKind::MissingField,
+        &join_key(material_path, "metal"),
+        "a conductor needs a `metal` or both `eta` and `k`",
+    )
+}
+
+fn unknown_material(name: &str, path: &str) -> SceneError {
+    SceneError::new(
+        SceneErrorKind::UnknownMaterial,
+        path,
+        format!("`{}` is not defined in `materials`", name),
+    )
+}
+
+fn unsupported_version(version: u64) -> SceneError {
+    SceneError::new(
+        SceneErrorKind::UnsupportedVersion,
+        "version",
+        format!("found {}, expected {}", version, SCENE_VERSION),
+    )
+}
+
+/// Checks the version before anything else, so files written for another
+/// version of the schema are not reported field by field
+fn check_version(document: &Value) -> Result<(), SceneError> {
+    let Value::Object(root) = document else {
+        return Err(SceneError::new(
+            SceneErrorKind::WrongType,
+            "",
+            "expected a JSON object",
+        ));
+    };
+    let version = match root.get("version") {
+        None => {
+            return Err(SceneError::new(
+                SceneErrorKind::MissingField,
+                "version",
+                "missing field `version`",
+            ))
+        }
+        Some(version) => version.as_u64().ok_or_else(|| {
+            SceneError::new(
+                SceneErrorKind::WrongType,
+                "version",
+                "expected a non-negative integer",
+            )
+        })?,
+    };
+    match version == SCENE_VERSION as u64 {
+        true => Ok(()),
+        false => Err(unsupported_version(version)),
+    }
+}
+
+/// Rewrites the `{"type": "sphere", ...}` objects of the file into the
+/// `{"sphere": {...}}` form the derived deserializers read, material references
+/// into `{"named": ...}` / `{"inline": ...}` and texture slots into
+/// `{"color": ...}` / `{"texture": ...}`. Internally tagged enums would hide the
+/// position of errors inside them, so the tags are resolved here instead.
+fn retag_document(document: &mut Value) -> Result<(), SceneError> {
+    if let Some(background) = document.get_mut("background") {
+        retag(background, "background")?;
+    }
+    if let Some(Value::Object(materials)) = document.get_mut("materials") {
+        for (name, material) in materials.iter_mut() {
+            retag_material(material, &join_key("materials", name))?;
+        }
+    }
+    if let Some(Value::Array(objects)) = document.get_mut("objects") {
+        for (index, object) in objects.iter_mut().enumerate() {
+            retag_object(object, &join_index("objects", index))?;
+        }
+    }
+    Ok(())
+}
+
+/// An object with its material, the inner objects of instances and media,
+/// and the transform steps of instances
+fn retag_object(object: &mut Value, path: &str) -> Result<(), SceneError> {
+    if let Some(material) = object.get_mut("material") {
+        retag_material_reference(material, &join_key(path, "material"))?;
+    }
+    for key in ["object", "boundary"] {
+        if let Some(inner) = object.get_mut(key) {
+            retag_object(inner, &join_key(path, key))?;
+        }
+    }
+    if let Some(Value::Array(steps)) = object.get_mut("transform") {
+        let transform_path = join_key(path, "transform");
+        for (index, step) in steps.iter_mut().enumerate() {
+            retag(step, &join_index(&transform_path, index))?;
+        }
+    }
+    retag(object, path)
+}
+
+fn retag(value: &mut Value, path: &str) -> Result<(), SceneError> {
+    let Value::Object(fields) = value else {
+        return Err(SceneError::new(
+            SceneErrorKind::WrongType,
+            path,
+            "expected an object with a `type` field",
+        ));
+    };
+    let kind = match fields.remove("type") {
+        Some(Value::String(kind)) => kind,
+        Some(_) => {
+            return Err(SceneError::new(
+                SceneErrorKind::WrongType,
+                &join_key(path, "type"),
+                "expected a string",
+            ))
+        }
+        None => {
+            return Err(SceneError::new(
+                SceneErrorKind::MissingField,
+                &join_key(path, "type"),
+                "missing field `type`",
+            ))
+        }
+    };
+    let fields = std::mem::take(fields);
+    *value = Value::Object(Map::from_iter([(kind, Value::Object(fields))]));
+    Ok(())
+}
+
+/// Material fields that take a color or a texture
+const TEXTURE_SLOTS: [&str; 3] = ["albedo", "emit", "base_color"];
+
+/// Keys of the scalar slots of each material type; other materials use plain numbers
+fn scalar_slots(material_type: &str) -> &'static [&'static str] {
+    match material_type {
+        "principled" => &[
+            "metallic",
+            "roughness",
+            "specular",
+            "transmission",
+            "clearcoat",
+            "sheen",
+            "anisotropy",
+        ],
+        "mix" => &["weight"],
+        _ => &[],
+    }
+}
+
+/// Material fields that hold a nested material, which must be written inline
+const MATERIAL_SLOTS: [&str; 3] = ["first", "second", "base"];
+
+/// A material with its texture and scalar slots and its nested materials
+fn retag_material(material: &mut Value, path: &str) -> Result<(), SceneError> {
+    if let Value::Object(fields) = material {
+        for key in MATERIAL_SLOTS {
+            if let Some(slot) = fields.get_mut(key) {
+                let slot_path = join_key(path, key);
+                match slot {
+                    Value::Object(_) => retag_material(slot, &slot_path)?,
+                    _ => {
+                        return Err(SceneError::new(
+                            SceneErrorKind::WrongType,
+                            &slot_path,
+                            "expected an inline material object",
+                        ))
+                    }
+                }
+            }
+        }
+        for key in TEXTURE_SLOTS {
+            if let Some(slot) = fields.get_mut(key) {
+                retag_texture_slot(slot, &join_key(path, key))?;
+            }
+        }
+        let material_type = fields.get("type").and_then(Value::as_str).unwrap_or("");
+        for key in scalar_slots(material_type) {
+            if let Some(slot) = fields.get_mut(*key) {
+                retag_scalar_slot(slot, &join_key(path, key))?;
+            }
+        }
+        if let Some(dispersion) = fields.get_mut("dispersion") {
+            retag(dispersion, &join_key(path, "dispersion"))?;
+        }
+    }
+    retag(material, path)
+}
+
+/// Numbers become `{"value": ...}`, texture objects `{"texture": {...}}`
+fn retag_scalar_slot(value: &mut Value, path: &str) -> Result<(), SceneError> {
+    match value {
+        Value::Number(_) => {
+            *value = Value::Object(Map::from_iter([("value".to_owned(), value.take())]));
+            Ok(())
+        }
+        Value::Object(_) => retag_texture_slot(value, path),
+        _ => Err(SceneError::new(
+            SceneErrorKind::WrongType,
+            path,
+            "expected a number or a texture object",
+        )),
+    }
+}
+
+/// Colors become `{"color": [...]}`, texture objects `{"texture": {...}}`
+fn retag_texture_slot(value: &mut Value, path: &str) -> Result<(), SceneError> {
+    let variant = match value {
+        Value::Array(_) => "color",
+        Value::Object(fields) => {
+            for key in ["even", "odd"] {
+                if let Some(slot) = fields.get_mut(key) {
+                    retag_texture_slot(slot, &join_key(path, key))?;
+                }
+            }
+            retag(value, path)?;
+            "texture"
+        }
+        _ => {
+            return Err(SceneError::new(
+                SceneErrorKind::WrongType,
+                path,
+                "expected an [r, g, b] array or a texture object",
+            ))
+        }
+    };
+    *value = Value::Object(Map::from_iter([(variant.to_owned(), value.take())]));
+    Ok(())
+}
+
+fn retag_material_reference(value: &mut Value, path: &str) -> Result<(), SceneError> {
+    let variant = match value {
+        Value::Null => return Ok(()),
+        Value::String(_) => "named",
+        Value::Object(_) => {
+            retag_material(value, path)?;
+            "inline"
+        }
+        _ => {
+            return Err(SceneError::new(
+                SceneErrorKind::WrongType,
+                path,
+                "expected a material name or a material object",
+            ))
+        }
+    };
+    *value = Value::Object(Map::from_iter([(variant.to_owned(), value.take())]));
+    Ok(())
+}
+
+/// The value of the retagged document that `path` leads to
+fn value_at<'a>(document: &'a Value, path: &serde_path_to_error::Path) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(document, |value, segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => value.get(index),
+            serde_path_to_error::Segment::Map { key } => value.get(key),
+            serde_path_to_error::Segment::Enum { variant } => value.get(variant),
+            serde_path_to_error::Segment::Unknown => Some(value),
+        })
+}
+
+fn tracked_path_to_string(path: &serde_path_to_error::Path) -> String {
+    path.iter()
+        .fold(String::new(), |path, segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => join_index(&path, *index),
+            serde_path_to_error::Segment::Map { key } => join_key(&path, key),
+            // Variant names come from the retagged `type` fields, not from keys of the file
+            serde_path_to_error::Segment::Enum { .. } | serde_path_to_error::Segment::Unknown => {
+                path
+            }
+        })
+}
+
+fn ignored_path_to_string(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => {
+            join_index(&ignored_path_to_string(parent), *index)
+        }
+        serde_ignored::Path::Map { parent, key } => join_key(&ignored_path_to_string(parent), key),
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => ignored_path_to_string(parent),
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Why a scene could not be loaded
+#[derive(Debug)]
+pub enum SceneErrorKind {
+    /// The scene file could not be read
+    Io(io::Error),
+    /// The file is not valid JSON
+    Syntax { line: usize, column: usize },
+    /// A required field is absent
+    MissingField,
+    /// A value has the wrong JSON type, e.g. a string where a number is expected
+    WrongType,
+    /// A value has the right type but is outside the allowed range
+    OutOfRange,
+    /// A key the schema does not know about (only reported in strict mode)
+    UnknownField,
+    /// The `type` of an object, material or background is not one of the known kinds
+    UnknownType,
+    /// A material name that is not defined in `materials`
+    UnknownMaterial,
+    /// The `version` of the file is not `SCENE_VERSION`
+    UnsupportedVersion,
+    /// A file referenced by the scene (mesh, environment map) could not be loaded
+    Resource,
+}
+
+/// An error found while reading, checking or building a scene file.
+/// `path` locates the offending value inside the JSON document, e.g. `objects[3].material.fuzz`.
+#[derive(Debug)]
+pub struct SceneError {
+    file: Option<PathBuf>,
+    path: String,
+    kind: SceneErrorKind,
+    message: String,
+}
+
+impl SceneError {
+    pub fn new(kind: SceneErrorKind, path: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            path: path.to_owned(),
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Records which file the error was found in
+    pub fn with_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+
+    pub fn get_file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    pub fn get_kind(&self) -> &SceneErrorKind {
+        &self.kind
+    }
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// Wraps a `serde_json` error found at `path`, sorting it into a kind from its message
+    pub(crate) fn from_json(error: serde_json::Error, path: &str) -> Self {
+        if error.is_syntax() || error.is_eof() {
+            let (line, column) = (error.line(), error.column());
+            let message = error.to_string();
+            let suffix = format!(" at line {} column {}", line, column);
+            let message = message.strip_suffix(&suffix).unwrap_or(&message);
+            return Self::new(SceneErrorKind::Syntax { line, column }, path, message);
+        }
+
+        let message = error.to_string();
+        if let Some(field) = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            return Self::new(
+                SceneErrorKind::MissingField,
+                &join_key(path, field),
+                message.clone(),
+            );
+        }
+        let kind = if message.starts_with("invalid value") {
+            SceneErrorKind::OutOfRange
+        } else if message.starts_with("unknown variant") {
+            SceneErrorKind::UnknownType
+        } else {
+            // "invalid type", "invalid length" and untagged enums that matched no variant
+            SceneErrorKind::WrongType
+        };
+        let path = match kind {
+            SceneErrorKind::UnknownType => join_key(path, "type"),
+            _ => path.to_owned(),
+        };
+        Self::new(kind, &path, message)
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        if let SceneErrorKind::Syntax { line, column } = self.kind {
+            write!(f, "line {} column {}: ", line, column)?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        let description = match self.kind {
+            SceneErrorKind::Io(_) => "cannot read file",
+            SceneErrorKind::Syntax { .. } => "invalid JSON",
+            SceneErrorKind::MissingField => "missing field",
+            SceneErrorKind::WrongType => "wrong type",
+            SceneErrorKind::OutOfRange => "out of range",
+            SceneErrorKind::UnknownField => "unknown field",
+            SceneErrorKind::UnknownType => "unknown type",
+            SceneErrorKind::UnknownMaterial => "unknown material",
+            SceneErrorKind::UnsupportedVersion => "unsupported version",
+            SceneErrorKind::Resource => "cannot load file",
+        };
+        write!(f, "{} ({})", description, self.message)
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            SceneErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        let message = error.to_string();
+        Self::new(SceneErrorKind::Io(error), "", message)
+    }
+}
+
+/// `path.key`, or just `key` at the root of the document
+pub(crate) fn join_key(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+/// `path[index]`
+pub(crate) fn join_index(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
@@ -1,130 +1,100 @@
-use rand::Rng;
 use rayon::prelude::*;
-use std::{collections::HashMap, fs};
+use std::{fs, path::Path, sync::Arc};
 
 use super::{
+    camera::Camera,
     color::Color,
-    geometry::{Hittable, Sphere},
+    geometry::{Hittable, Plane, Sphere},
     material::{Dielectric, Lambertian, Metal},
     point::Point3,
+    random::{self, Pcg32},
+    scene_description::SceneDescription,
+    scene_error::SceneError,
+    vector3::Vector3,
 };
 
-const NUMBER_BALLS: i32 = 7;
-const SCENE_FILE_PATH: &str = "scene_data.json";
-
-fn translate_color_to_scale(color_component: f64) -> f64 {
-    color_component.clamp(0.0, 256.0) / 256.0
+/// Everything needed to render: the configured camera, the objects of the world
+/// and copies of the emitting objects that can be sampled as lights
+pub struct Scene {
+    pub camera: Camera,
+    pub world: Vec<Box<dyn Hittable>>,
+    pub lights: Vec<Box<dyn Hittable>>,
 }
 
-fn read_from_json(world: &mut Vec<Box<dyn Hittable>>) {
-    let scenes_file_path = SCENE_FILE_PATH.to_owned();
-    let file = fs::File::open(scenes_file_path).expect("Could not open file");
-    let json_data: HashMap<String, Vec<HashMap<String, serde_json::Value>>> =
-        serde_json::from_reader(file).expect("File is not proper JSON");
-    let json_parse_ball = json_data.get("Ball").expect("Can't read Ball data");
-
-    for ball in json_parse_ball.iter() {
-        {
-            if let Some(material_str) = ball["material"]["type"].as_str() {
-                if material_str == "lambertian" {
-                    let color_obj = Color::new(
-                        translate_color_to_scale(ball["color"]["r"].as_f64().unwrap_or_default()),
-                        translate_color_to_scale(ball["color"]["g"].as_f64().unwrap_or_default()),
-                        translate_color_to_scale(ball["color"]["b"].as_f64().unwrap_or_default()),
-                    );
-                    let material_obj = Box::new(Lambertian::new(color_obj));
-
-                    let center_obj = Point3::new(
-                        ball["center"]["x"].as_f64().unwrap_or_default(),
-                        ball["center"]["y"].as_f64().unwrap_or_default(),
-                        ball["center"]["z"].as_f64().unwrap_or_default(),
-                    );
-                    let radius_obj = ball["radius"].as_f64().unwrap_or_default();
-
-                    world.push(Box::new(Sphere::new(center_obj, radius_obj, material_obj)));
-                } else if material_str == "metal" {
-                    let color_obj = Color::new(
-                        translate_color_to_scale(ball["color"]["r"].as_f64().unwrap_or_default()),
-                        translate_color_to_scale(ball["color"]["g"].as_f64().unwrap_or_default()),
-                        translate_color_to_scale(ball["color"]["b"].as_f64().unwrap_or_default()),
-                    );
-                    let fuzz_obj = ball["material"]["fuzz"].as_f64().unwrap_or_default();
-                    let material_obj = Box::new(Metal::new(color_obj, fuzz_obj));
-
-                    let center_obj = Point3::new(
-                        ball["center"]["x"].as_f64().unwrap_or_default(),
-                        ball["center"]["y"].as_f64().unwrap_or_default(),
-                        ball["center"]["z"].as_f64().unwrap_or_default(),
-                    );
-                    let radius_obj = ball["radius"].as_f64().unwrap_or_default();
+/// Reads a scene file (see `SceneDescription` for the format) and builds the scene.
+/// In `strict` mode keys that are not part of the schema are errors.
+pub fn load_scene(path: &Path, strict: bool) -> Result<Scene, SceneError> {
+    let description = load_scene_description(path, strict)?;
+    build_scene(&description, path)
+}
 
-                    world.push(Box::new(Sphere::new(center_obj, radius_obj, material_obj)));
-                } else if material_str == "dielectric" {
-                    let rf_index_obj = ball["material"]["ref_idx"].as_f64().unwrap_or_default();
-                    let material_obj = Box::new(Dielectric::new(rf_index_obj));
+/// Reads a scene file without building it, e.g. to change settings first
+pub fn load_scene_description(path: &Path, strict: bool) -> Result<SceneDescription, SceneError> {
+    let json = fs::read_to_string(path).map_err(|e| SceneError::from(e).with_file(path))?;
+    SceneDescription::from_json(&json, strict).map_err(|e| e.with_file(path))
+}
 
-                    let center_obj = Point3::new(
-                        ball["center"]["x"].as_f64().unwrap_or_default(),
-                        ball["center"]["y"].as_f64().unwrap_or_default(),
-                        ball["center"]["z"].as_f64().unwrap_or_default(),
-                    );
-                    let radius_obj = ball["radius"].as_f64().unwrap_or_default();
+/// Builds a description read from the scene file at `path`.
+/// Relative file names are resolved against the directory of that file.
+pub fn build_scene(description: &SceneDescription, path: &Path) -> Result<Scene, SceneError> {
+    let scene_dir = path.parent().unwrap_or(Path::new(""));
+    description.build(scene_dir).map_err(|e| e.with_file(path))
+}
 
-                    world.push(Box::new(Sphere::new(center_obj, radius_obj, material_obj)));
-                } else {
-                    println!("Wrong Material");
-                }
-            }
-        }
-    }
+/// Builds a scene from JSON text. Relative file names are resolved against `scene_dir`.
+pub fn scene_from_str(json: &str, scene_dir: &Path, strict: bool) -> Result<Scene, SceneError> {
+    SceneDescription::from_json(json, strict)?.build(scene_dir)
 }
 
-pub fn generate_scene(world: &mut Vec<Box<dyn Hittable>>) {
+/// The final scene of "Ray Tracing in One Weekend": a ground plane, a
+/// `2 * number_balls` by `2 * number_balls` grid of small random spheres and four big spheres.
+/// The same `seed` always gives the same spheres.
+pub fn generate_scene(world: &mut Vec<Box<dyn Hittable>>, number_balls: i32, seed: u64) {
     // World
 
     // Scene - ground
-    let material_ground = Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
-    world.push(Box::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
+    let material_ground = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    world.push(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
         material_ground,
     )));
 
     // Scene - small balls (random)
-    let spheres_scene: Vec<Box<dyn Hittable>> = (-NUMBER_BALLS..NUMBER_BALLS)
+    let spheres_scene: Vec<Box<dyn Hittable>> = (-number_balls..number_balls)
         .into_par_iter()
         .flat_map(|x_index| {
-            (-NUMBER_BALLS..NUMBER_BALLS)
+            (-number_balls..number_balls)
                 .into_par_iter()
                 .map(|y_index| {
-                    let choose_material_random: f64 = rand::thread_rng().r#gen::<f64>();
+                    // One random stream per grid cell, independent of the thread running it
+                    let mut generator =
+                        Pcg32::from_seed(random::hash(&[seed, x_index as u64, y_index as u64]));
+                    let mut random_double = || generator.next_f64();
+                    let choose_material_random: f64 = random_double();
                     let center = Point3::new(
-                        (x_index as f64) + (0.9 * rand::thread_rng().r#gen::<f64>()),
+                        (x_index as f64) + (0.9 * random_double()),
                         0.2,
-                        (y_index as f64) + (0.9 * rand::thread_rng().r#gen::<f64>()),
+                        (y_index as f64) + (0.9 * random_double()),
                     );
                     if choose_material_random < 0.4 {
                         // Lambertian
-                        let material_lambertian = Box::new(Lambertian::new(Color::new(
-                            rand::thread_rng().r#gen::<f64>(),
-                            rand::thread_rng().r#gen::<f64>(),
-                            rand::thread_rng().r#gen::<f64>(),
+                        let material_lambertian = Arc::new(Lambertian::new(Color::new(
+                            random_double(),
+                            random_double(),
+                            random_double(),
                         )));
                         Box::new(Sphere::new(center, 0.2, material_lambertian)) as Box<dyn Hittable>
                     } else if choose_material_random < 0.8 {
                         // Metal
-                        let material_metal = Box::new(Metal::new(
-                            Color::new(
-                                rand::thread_rng().r#gen::<f64>(),
-                                rand::thread_rng().r#gen::<f64>(),
-                                rand::thread_rng().r#gen::<f64>(),
-                            ),
-                            rand::thread_rng().r#gen::<f64>(),
+                        let material_metal = Arc::new(Metal::new(
+                            Color::new(random_double(), random_double(), random_double()),
+                            random_double(),
                         ));
                         Box::new(Sphere::new(center, 0.2, material_metal)) as Box<dyn Hittable>
                     } else {
                         // Glass
-                        let material_glass = Box::new(Dielectric::new(1.33));
+                        let material_glass = Arc::new(Dielectric::new(1.33));
                         Box::new(Sphere::new(center, 0.2, material_glass)) as Box<dyn Hittable>
                     }
                 })
@@ -135,13 +105,13 @@ pub fn generate_scene(world: &mut Vec<Box<dyn Hittable>>) {
     world.extend(spheres_scene);
 
     // Scene - big balls with Glass material
-    let material_glass = Box::new(Dielectric::new(1.0 / 1.55));
+    let material_glass = Arc::new(Dielectric::new(1.0 / 1.55));
     world.push(Box::new(Sphere::new(
         Point3::new(8.0, 1.0, 0.0),
         1.0,
         material_glass,
     )));
-    let material_bubble = Box::new(Dielectric::new(1.55));
+    let material_bubble = Arc::new(Dielectric::new(1.55));
     world.push(Box::new(Sphere::new(
         Point3::new(0.0, 1.0, 0.0),
         1.0,
@@ -149,7 +119,7 @@ pub fn generate_scene(world: &mut Vec<Box<dyn Hittable>>) {
     )));
 
     // Scene - big ball with Lambertian material
-    let material_lambertian = Box::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    let material_lambertian = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
     world.push(Box::new(Sphere::new(
         Point3::new(-4.0, 1.0, 0.0),
         1.0,
@@ -157,13 +127,10 @@ pub fn generate_scene(world: &mut Vec<Box<dyn Hittable>>) {
     )));
 
     // Scene - big ball with Metal material
-    let material_metal = Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.2));
+    let material_metal = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.2));
     world.push(Box::new(Sphere::new(
         Point3::new(4.0, 1.0, 0.0),
         1.0,
         material_metal,
     )));
-
-    // Scene - Load from Json
-    read_from_json(world);
 }
@@ -0,0 +1,253 @@
+use std::{
+    ops::{AddAssign, Mul, MulAssign},
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+
+use super::color::Color;
+
+/// The visible range sampled in spectral rendering, in nanometers
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+/// The sodium d line, where refractive indices are usually quoted
+pub const D_LINE: f64 = 587.6;
+
+/// Number of wavelengths each path carries
+pub const SPECTRUM_SAMPLES: usize = 4;
+
+/// The wavelengths of one path, in nanometers. The hero wavelength is drawn uniformly and
+/// the others are spread evenly after it, wrapping around the visible range
+/// (Wilkie et al., "Hero Wavelength Spectral Sampling", 2014).
+#[derive(Clone, Copy)]
+pub struct SampledWavelengths {
+    lambda: [f64; SPECTRUM_SAMPLES],
+}
+
+impl SampledWavelengths {
+    /// Wavelengths for a uniform `sample` in [0, 1)
+    pub fn sample_uniform(sample: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = LAMBDA_MIN + (sample * range);
+        let spacing = range / SPECTRUM_SAMPLES as f64;
+        let lambda = std::array::from_fn(|index| {
+            let shifted = hero + (index as f64 * spacing);
+            match shifted > LAMBDA_MAX {
+                true => shifted - range,
+                false => shifted,
+            }
+        });
+        Self { lambda }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    pub fn get(&self) -> [f64; SPECTRUM_SAMPLES] {
+        self.lambda
+    }
+}
+
+/// Values of a spectrum at the wavelengths of a path
+#[derive(Clone, Copy, Default)]
+pub struct SampledSpectrum {
+    values: [f64; SPECTRUM_SAMPLES],
+}
+
+impl SampledSpectrum {
+    pub fn new(values: [f64; SPECTRUM_SAMPLES]) -> Self {
+        Self { values }
+    }
+
+    pub fn get(&self) -> [f64; SPECTRUM_SAMPLES] {
+        self.values
+    }
+
+    /// A smooth spectrum with the given RGB color, evaluated at `wavelengths`.
+    /// Gray colors give flat spectra; saturated colors come back approximately.
+    pub fn from_rgb(color: Color, wavelengths: &SampledWavelengths) -> Self {
+        Self {
+            values: wavelengths.get().map(|lambda| {
+                let (red, green, blue) = rgb_basis(lambda);
+                (color.get_r() * red) + (color.get_g() * green) + (color.get_b() * blue)
+            }),
+        }
+    }
+
+    /// Keeps only the hero wavelength, once the path has taken a direction that only
+    /// holds for it. Its value is scaled up so that the estimate stays unbiased.
+    pub fn terminate_secondary(self) -> Self {
+        let mut values = [0.0; SPECTRUM_SAMPLES];
+        values[0] = self.values[0] * SPECTRUM_SAMPLES as f64;
+        Self { values }
+    }
+
+    /// Monte Carlo estimate of the CIE XYZ color of the spectrum, normalized so that
+    /// a constant spectrum of 1 has Y = 1
+    pub fn to_xyz(&self, wavelengths: &SampledWavelengths) -> Xyz {
+        // Dividing by the uniform density is multiplying by the range
+        let scale = (LAMBDA_MAX - LAMBDA_MIN) / (SPECTRUM_SAMPLES as f64 * white_xyz().y);
+        let mut xyz = Xyz::default();
+        for (value, lambda) in self.values.iter().zip(wavelengths.get()) {
+            xyz += cie_xyz(lambda) * (value * scale);
+        }
+        xyz
+    }
+}
+
+impl AddAssign for SampledSpectrum {
+    fn add_assign(&mut self, rhs: Self) {
+        for (value, other) in self.values.iter_mut().zip(rhs.values) {
+            *value += other;
+        }
+    }
+}
+
+impl MulAssign for SampledSpectrum {
+    fn mul_assign(&mut self, rhs: Self) {
+        for (value, other) in self.values.iter_mut().zip(rhs.values) {
+            *value *= other;
+        }
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = Self;
+    fn mul(mut self, rhs: Self) -> Self::Output {
+        self *= rhs;
+        self
+    }
+}
+
+impl Mul<f64> for SampledSpectrum {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            values: self.values.map(|value| value * rhs),
+        }
+    }
+}
+
+/// A color in the CIE 1931 XYZ space
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Xyz {
+    /// Linear sRGB, white balanced so that a constant spectrum maps to gray
+    pub fn to_rgb(self) -> Color {
+        let white = white_xyz();
+        let (red, green, blue) = xyz_to_linear_srgb(self);
+        let (white_red, white_green, white_blue) = xyz_to_linear_srgb(*white);
+        Color::new(
+            red * white.y / white_red,
+            green * white.y / white_green,
+            blue * white.y / white_blue,
+        )
+    }
+}
+
+impl AddAssign for Xyz {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl Mul<f64> for Xyz {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+fn xyz_to_linear_srgb(xyz: Xyz) -> (f64, f64, f64) {
+    (
+        (3.2404542 * xyz.x) - (1.5371385 * xyz.y) - (0.4985314 * xyz.z),
+        (-0.9692660 * xyz.x) + (1.8760108 * xyz.y) + (0.0415560 * xyz.z),
+        (0.0556434 * xyz.x) - (0.2040259 * xyz.y) + (1.0572252 * xyz.z),
+    )
+}
+
+/// The CIE 1931 2° color matching functions at `lambda` (nm), from the piecewise
+/// Gaussian fit of Wyman, Sloan and Shirley, "Simple Analytic Approximations to the
+/// CIE XYZ Color Matching Functions", 2013
+pub fn cie_xyz(lambda: f64) -> Xyz {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let width = match lambda < mean {
+            true => below,
+            false => above,
+        };
+        (-0.5 * ((lambda - mean) / width).powi(2)).exp()
+    };
+    Xyz {
+        x: (1.056 * lobe(599.8, 37.9, 31.0)) + (0.362 * lobe(442.0, 16.0, 26.7))
+            - (0.065 * lobe(501.1, 20.4, 26.2)),
+        y: (0.821 * lobe(568.8, 46.9, 40.5)) + (0.286 * lobe(530.9, 16.3, 31.1)),
+        z: (1.217 * lobe(437.0, 11.8, 36.0)) + (0.681 * lobe(459.0, 26.0, 13.8)),
+    }
+}
+
+/// The XYZ integrals of a constant spectrum of 1 over the sampled range
+fn white_xyz() -> &'static Xyz {
+    static WHITE: OnceLock<Xyz> = OnceLock::new();
+    WHITE.get_or_init(|| {
+        let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+        let mut white = Xyz::default();
+        for step in 0..steps {
+            white += cie_xyz(LAMBDA_MIN + step as f64 + 0.5);
+        }
+        white
+    })
+}
+
+/// Weights of the red, green and blue primaries at `lambda`: smooth steps that add up
+/// to 1 everywhere, so that white reflects every wavelength fully
+fn rgb_basis(lambda: f64) -> (f64, f64, f64) {
+    let smooth_step = |start: f64, end: f64| {
+        let t = ((lambda - start) / (end - start)).clamp(0.0, 1.0);
+        t * t * (3.0 - (2.0 * t))
+    };
+    let above_blue = smooth_step(480.0, 510.0);
+    let red = smooth_step(570.0, 600.0);
+    (red, above_blue - red, 1.0 - above_blue)
+}
+
+/// How the refractive index of a dispersive material varies with wavelength
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dispersion {
+    /// n = a + b / λ², with λ in micrometers
+    Cauchy { a: f64, b: f64 },
+    /// n² = 1 + Σ b_i λ² / (λ² - c_i), with λ in micrometers
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Refractive index at `lambda` nanometers
+    pub fn refractive_index(&self, lambda: f64) -> f64 {
+        let micrometers = lambda / 1000.0;
+        let squared = micrometers * micrometers;
+        match self {
+            Dispersion::Cauchy { a, b } => a + (b / squared),
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b
+                    .iter()
+                    .zip(c)
+                    .map(|(b, c)| b * squared / (squared - c))
+                    .sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::{io, path::Path, sync::Arc};
+
+use serde::Deserialize;
+
+use super::{color::Color, image::Image, point::Point3, random::Pcg32, vector3::Vector3};
+
+/// A color that varies over a surface, looked up by the surface coordinates
+/// of the hit point or by its position in space
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color;
+}
+
+/// The same color everywhere
+#[derive(Clone)]
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+
+    /// The same value in every channel, for textures of scalar parameters
+    pub fn gray(value: f64) -> Self {
+        Self::new(Color::new(value, value, value))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: Point3) -> Color {
+        self.albedo
+    }
+}
+
+/// Space filled with cubes `scale` wide, alternating between the `even` and `odd` textures,
+/// so the pattern does not depend on how the surface is parameterized
+#[derive(Clone)]
+pub struct Checker {
+    inverse_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color {
+        let cell = |coordinate: f64| (coordinate * self.inverse_scale).floor() as i64;
+        let sum = cell(point.get_x()) + cell(point.get_y()) + cell(point.get_z());
+        match sum.rem_euclid(2) == 0 {
+            true => self.even.value(u, v, point),
+            false => self.odd.value(u, v, point),
+        }
+    }
+}
+
+/// How an image texture continues past its edges
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    /// Tiles the image
+    #[default]
+    Repeat,
+    /// Stretches the edge texels outwards
+    Clamp,
+    /// Tiles the image, flipping every other copy so that the edges meet
+    Mirror,
+}
+
+impl WrapMode {
+    /// The texel index in `0..size` that stands in for `index`
+    fn wrap(self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let folded = index.rem_euclid(2 * size);
+                match folded < size {
+                    true => folded,
+                    false => (2 * size) - 1 - folded,
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+/// An image mapped onto the surface coordinates: u runs left to right, v bottom to top.
+/// Colors between texel centers are filtered bilinearly.
+#[derive(Clone)]
+pub struct ImageTexture {
+    image: Arc<Image>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image, wrap: WrapMode) -> Self {
+        Self {
+            image: Arc::new(image),
+            wrap,
+        }
+    }
+
+    /// Loads a PNG, JPEG or Radiance HDR file
+    pub fn from_file(path: &Path, wrap: WrapMode) -> io::Result<Self> {
+        Ok(Self::new(Image::load(path)?, wrap))
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        self.image.get_pixel(
+            self.wrap.wrap(x, self.image.get_width()),
+            self.wrap.wrap(y, self.image.get_height()),
+        )
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: Point3) -> Color {
+        // Texel centers sit at half-integer image coordinates, and rows start at the top
+        let x = (u * self.image.get_width() as f64) - 0.5;
+        let y = ((1.0 - v) * self.image.get_height() as f64) - 0.5;
+        let (x_floor, y_floor) = (x.floor(), y.floor());
+        let (x_weight, y_weight) = (x - x_floor, y - y_floor);
+        let (x_index, y_index) = (x_floor as i64, y_floor as i64);
+
+        let top = (self.texel(x_index, y_index) * (1.0 - x_weight))
+            + (self.texel(x_index + 1, y_index) * x_weight);
+        let bottom = (self.texel(x_index, y_index + 1) * (1.0 - x_weight))
+            + (self.texel(x_index + 1, y_index + 1) * x_weight);
+        (top * (1.0 - y_weight)) + (bottom * y_weight)
+    }
+}
+
+const PERLIN_POINT_COUNT: usize = 256;
+
+/// Ken Perlin's gradient noise: random unit vectors on the integer lattice,
+/// blended smoothly in between
+#[derive(Clone)]
+pub struct Perlin {
+    gradients: Vec<Vector3>,
+    permutations: [Vec<usize>; 3], // One shuffle of the lattice indices per axis
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut generator = Pcg32::from_seed(seed);
+        let gradients = (0..PERLIN_POINT_COUNT)
+            .map(|_| Vector3::sample_unit_sphere(generator.next_f64(), generator.next_f64()))
+            .collect();
+        let mut permutation = || {
+            let mut indices: Vec<usize> = (0..PERLIN_POINT_COUNT).collect();
+            for index in (1..PERLIN_POINT_COUNT).rev() {
+                let target = (generator.next_u32() as usize) % (index + 1);
+                indices.swap(index, target);
+            }
+            indices
+        };
+        let permutations = [permutation(), permutation(), permutation()];
+        Self {
+            gradients,
+            permutations,
+        }
+    }
+
+    /// Noise in [-1, 1] that is zero on the lattice points
+    pub fn noise(&self, point: Point3) -> f64 {
+        let coordinates = [point.get_x(), point.get_y(), point.get_z()];
+        let floors = coordinates.map(f64::floor);
+        let [u, v, w] = [0, 1, 2].map(|axis| coordinates[axis] - floors[axis]);
+        let [i, j, k] = floors.map(|floor| floor as i64);
+
+        // Hermite smoothing hides the lattice
+        let [u_smooth, v_smooth, w_smooth] = [u, v, w].map(|t| t * t * (3.0 - (2.0 * t)));
+        let mut accumulated = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.permutations[0][((i + di) & 255) as usize]
+                        ^ self.permutations[1][((j + dj) & 255) as usize]
+                        ^ self.permutations[2][((k + dk) & 255) as usize]];
+                    let (di, dj, dk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector3::new(u - di, v - dj, w - dk);
+                    accumulated += ((di * u_smooth) + ((1.0 - di) * (1.0 - u_smooth)))
+                        * ((dj * v_smooth) + ((1.0 - dj) * (1.0 - v_smooth)))
+                        * ((dk * w_smooth) + ((1.0 - dk) * (1.0 - w_smooth)))
+                        * gradient.dot_prod(weight);
+                }
+            }
+        }
+        accumulated
+    }
+
+    /// The magnitude of `depth` octaves of noise added up, each twice as fine and half as strong
+    pub fn turbulence(&self, point: Point3, depth: u32) -> f64 {
+        let mut accumulated = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accumulated += weight * self.noise(point);
+            weight *= 0.5;
+            point = Point3::default() + (point.as_vec() * 2.0);
+        }
+        accumulated.abs()
+    }
+}
+
+const TURBULENCE_DEPTH: u32 = 7;
+
+/// Which pattern a noise texture draws
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseStyle {
+    /// Smooth blotches
+    Noise,
+    /// Several octaves of noise, like smoke
+    Turbulence,
+    /// Stripes along z, bent by turbulence
+    #[default]
+    Marble,
+}
+
+/// Solid noise scaled by `scale` (higher is finer), tinting `color`
+#[derive(Clone)]
+pub struct NoiseTexture {
+    perlin: Perlin,
+    scale: f64,
+    style: NoiseStyle,
+    color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, style: NoiseStyle, color: Color, seed: u64) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            style,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: Point3) -> Color {
+        let scaled = Point3::default() + (point.as_vec() * self.scale);
+        let intensity = match self.style {
+            NoiseStyle::Noise => 0.5 * (1.0 + self.perlin.noise(scaled)),
+            NoiseStyle::Turbulence => self.perlin.turbulence(scaled, TURBULENCE_DEPTH),
+            NoiseStyle::Marble => {
+                let turbulence = self.perlin.turbulence(point, TURBULENCE_DEPTH);
+                0.5 * (1.0 + (scaled.get_z() + (10.0 * turbulence)).sin())
+            }
+        };
+        self.color * intensity
+    }
+}
@@ -0,0 +1,262 @@
+use std::ops::Mul;
+
+use super::{
+    aabb::Aabb,
+    interval::Interval,
+    point::Point3,
+    ray::Ray,
+    vector3::{Cross, Vector3},
+};
+
+type Matrix4 = [[f64; 4]; 4];
+
+const IDENTITY_MATRIX: Matrix4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// An affine transform as a 4x4 matrix (acting on column vectors), stored together
+/// with its inverse so that rays can be taken into object space and normals back out
+/// without inverting anything while rendering.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        matrix: IDENTITY_MATRIX,
+        inverse: IDENTITY_MATRIX,
+    };
+
+    pub fn translate(offset: Vector3) -> Self {
+        let (x, y, z) = (offset.get_x(), offset.get_y(), offset.get_z());
+        Self {
+            matrix: [
+                [1.0, 0.0, 0.0, x],
+                [0.0, 1.0, 0.0, y],
+                [0.0, 0.0, 1.0, z],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            inverse: [
+                [1.0, 0.0, 0.0, -x],
+                [0.0, 1.0, 0.0, -y],
+                [0.0, 0.0, 1.0, -z],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Scales each axis by its own factor. Negative factors mirror, zero factors are not invertible.
+    pub fn scale(factors: Vector3) -> Self {
+        let (x, y, z) = (factors.get_x(), factors.get_y(), factors.get_z());
+        Self {
+            matrix: [
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            inverse: [
+                [1.0 / x, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / y, 0.0, 0.0],
+                [0.0, 0.0, 1.0 / z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Rotates counter-clockwise (looking down `axis` towards the origin) by `degrees`
+    pub fn rotate(axis: Vector3, degrees: f64) -> Self {
+        let axis = axis.unit_vector();
+        let (x, y, z) = (axis.get_x(), axis.get_y(), axis.get_z());
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let matrix = [
+            [
+                (x * x) + ((1.0 - (x * x)) * cos),
+                (x * y * (1.0 - cos)) - (z * sin),
+                (x * z * (1.0 - cos)) + (y * sin),
+                0.0,
+            ],
+            [
+                (x * y * (1.0 - cos)) + (z * sin),
+                (y * y) + ((1.0 - (y * y)) * cos),
+                (y * z * (1.0 - cos)) - (x * sin),
+                0.0,
+            ],
+            [
+                (x * z * (1.0 - cos)) - (y * sin),
+                (y * z * (1.0 - cos)) + (x * sin),
+                (z * z) + ((1.0 - (z * z)) * cos),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        // Rotations are orthogonal: the inverse is the transpose
+        Self {
+            matrix,
+            inverse: transpose(&matrix),
+        }
+    }
+
+    pub fn rotate_x(degrees: f64) -> Self {
+        Self::rotate(Vector3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Self {
+        Self::rotate(Vector3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Self {
+        Self::rotate(Vector3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    /// Places an object at `from` and turns it so that its +z axis points at `at`
+    /// and its +y axis is as close to `up` as possible
+    pub fn look_at(from: Point3, at: Point3, up: Vector3) -> Self {
+        let w = (at - from).as_vec().unit_vector();
+        let u = up.cross_prod(w).unit_vector();
+        let v = w.cross_prod(u);
+        let rotation = Self {
+            matrix: [
+                [u.get_x(), v.get_x(), w.get_x(), 0.0],
+                [u.get_y(), v.get_y(), w.get_y(), 0.0],
+                [u.get_z(), v.get_z(), w.get_z(), 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            inverse: [
+                [u.get_x(), u.get_y(), u.get_z(), 0.0],
+                [v.get_x(), v.get_y(), v.get_z(), 0.0],
+                [w.get_x(), w.get_y(), w.get_z(), 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        };
+        rotation.then(&Self::translate(from.as_vec()))
+    }
+
+    /// This transform followed by `next`
+    pub fn then(&self, next: &Transform) -> Self {
+        *next * *self
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn get_matrix(&self) -> [[f64; 4]; 4] {
+        self.matrix
+    }
+
+    pub fn apply_point(&self, point: Point3) -> Point3 {
+        let [x, y, z, w] = multiply_vector(&self.matrix, point.as_vec(), 1.0);
+        match w == 1.0 {
+            true => Point3::new(x, y, z),
+            false => Point3::new(x / w, y / w, z / w),
+        }
+    }
+
+    /// Directions ignore the translation
+    pub fn apply_vector(&self, vector: Vector3) -> Vector3 {
+        let [x, y, z, _] = multiply_vector(&self.matrix, vector, 0.0);
+        Vector3::new(x, y, z)
+    }
+
+    /// Normals transform with the inverse transpose, so they stay perpendicular to
+    /// the surface under non-uniform scaling. The result is not normalized.
+    pub fn apply_normal(&self, normal: Vector3) -> Vector3 {
+        let [x, y, z, _] = multiply_vector(&transpose(&self.inverse), normal, 0.0);
+        Vector3::new(x, y, z)
+    }
+
+    /// The direction is not normalized, so hit parameters are the same in both spaces
+    pub fn apply_ray(&self, ray: Ray) -> Ray {
+        Ray::with_time(
+            self.apply_point(ray.get_origin()),
+            self.apply_vector(ray.get_direction()),
+            ray.get_time(),
+        )
+    }
+
+    /// The box around the eight transformed corners of `bbox`
+    pub fn apply_bbox(&self, bbox: &Aabb) -> Aabb {
+        if !bbox.is_bounded() {
+            return Aabb::new(Interval::UNIVERSE, Interval::UNIVERSE, Interval::UNIVERSE);
+        }
+        let corners = (0..8).map(|corner| {
+            self.apply_point(Point3::new(
+                bound(bbox.x, corner & 1),
+                bound(bbox.y, corner & 2),
+                bound(bbox.z, corner & 4),
+            ))
+        });
+        let (mut min, mut max) = ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]);
+        for corner in corners {
+            for (axis, value) in [corner.get_x(), corner.get_y(), corner.get_z()]
+                .into_iter()
+                .enumerate()
+            {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        Aabb::from_points(
+            Point3::new(min[0], min[1], min[2]),
+            Point3::new(max[0], max[1], max[2]),
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `a * b` applies `b` first, like the product of the matrices
+impl Mul for Transform {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            matrix: multiply(&self.matrix, &rhs.matrix),
+            inverse: multiply(&rhs.inverse, &self.inverse),
+        }
+    }
+}
+
+fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut product = [[0.0; 4]; 4];
+    for (row, product_row) in product.iter_mut().enumerate() {
+        for (column, value) in product_row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    product
+}
+
+fn multiply_vector(matrix: &Matrix4, vector: Vector3, w: f64) -> [f64; 4] {
+    let column = [vector.get_x(), vector.get_y(), vector.get_z(), w];
+    matrix.map(|row| (0..4).map(|k| row[k] * column[k]).sum())
+}
+
+fn transpose(matrix: &Matrix4) -> Matrix4 {
+    let mut transposed = [[0.0; 4]; 4];
+    for (row, values) in matrix.iter().enumerate() {
+        for (column, value) in values.iter().enumerate() {
+            transposed[column][row] = *value;
+        }
+    }
+    transposed
+}
+
+fn bound(interval: Interval, use_max: usize) -> f64 {
+    match use_max == 0 {
+        true => interval.min,
+        false => interval.max,
+    }
+}
@@ -1,6 +1,9 @@
-use rand::Rng;
 use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub};
 
+use std::f64::consts::PI;
+
+use super::sampler::Sampler;
+
 #[derive(Clone, Copy)]
 pub struct Vector3 {
     x: f64,
@@ -13,20 +16,17 @@ impl Vector3 {
         Self { x, y, z }
     }
 
-    pub fn random_new() -> Self {
+    pub fn random_new(sampler: &mut dyn Sampler) -> Self {
+        let (x, y) = sampler.get_2d();
         Self {
-            x: rand::thread_rng().r#gen::<f64>(),
-            y: rand::thread_rng().r#gen::<f64>(),
-            z: rand::thread_rng().r#gen::<f64>(),
+            x,
+            y,
+            z: sampler.get_1d(),
         }
     }
 
-    pub fn random_range_new(low: f64, high: f64) -> Self {
-        Self {
-            x: rand::thread_rng().gen_range(low, high),
-            y: rand::thread_rng().gen_range(low, high),
-            z: rand::thread_rng().gen_range(low, high),
-        }
+    pub fn random_range_new(low: f64, high: f64, sampler: &mut dyn Sampler) -> Self {
+        Self::random_new(sampler) * (high - low) + Self::new(low, low, low)
     }
 
     pub fn length_squared(&self) -> f64 {
@@ -57,6 +57,31 @@ impl Vector3 {
         self.z
     }
 
+    /// Component by index: 0 = x, 1 = y, 2 = z
+    pub fn get_axis(self, axis: usize) -> f64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
+    /// Index of the component with the largest absolute value
+    pub fn max_dimension(self) -> usize {
+        let (x, y, z) = (self.x.abs(), self.y.abs(), self.z.abs());
+        if x > y {
+            if x > z {
+                0
+            } else {
+                2
+            }
+        } else if y > z {
+            1
+        } else {
+            2
+        }
+    }
+
     pub fn dot_prod(&self, rhs: Self) -> f64 {
         (self.x * rhs.x) + (self.y * rhs.y) + (self.z * rhs.z)
     }
@@ -65,31 +90,59 @@ impl Vector3 {
     /// to determine if it's in the correct hemisphere.
     /// If the dot product is positive, then the vector is in the correct hemisphere.
     /// If the dot product is negative, then we need to invert the vector.
-    pub fn random_on_hemisphere(&self) -> Self {
-        let on_unit_sphere = Self::random_unit_vector();
+    pub fn random_on_hemisphere(&self, sampler: &mut dyn Sampler) -> Self {
+        let on_unit_sphere = Self::random_unit_vector(sampler);
         match self.dot_prod(on_unit_sphere) > 0.0 {
             true => on_unit_sphere,
             false => -on_unit_sphere,
         }
     }
 
-    /// With a random vector in the unit sphere we need to normalize it to
-    /// get a vector on the unit sphere
-    pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vector()
+    /// Uniform direction on the unit sphere. Maps one 2D sample directly (no rejection),
+    /// so stratified samples stay stratified: z is uniform in [-1, 1], the angle around z in [0, 2π)
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Self {
+        let (u, v) = sampler.get_2d();
+        Self::sample_unit_sphere(u, v)
     }
 
-    /// We will use a rejection method to generate the random vector inside
-    /// of the unit sphere. Pick a random point in the unit cube, where x, y, z
-    /// all range from −1 to +1, and reject this point if it is outside the unit sphere.
-    fn random_in_unit_sphere() -> Self {
-        loop {
-            // use loop instead of while(true)
-            let random_vec: Vector3 = Vector3::random_range_new(-1.0, 1.0);
-            if random_vec.length_squared() < 1.0 {
-                break random_vec; // to return from loop
-            }
+    /// The uniform sphere mapping of `random_unit_vector` for a given point (u, v) of [0, 1)^2
+    pub fn sample_unit_sphere(u: f64, v: f64) -> Self {
+        let z = 1.0 - 2.0 * u;
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        Self::new(radius * phi.cos(), radius * phi.sin(), z)
+    }
+
+    /// Uniform point inside the unit disk in the xy plane, with Shirley's concentric
+    /// mapping of the square onto the disk
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Self {
+        let (u, v) = sampler.get_2d();
+        Self::sample_unit_disk(u, v)
+    }
+
+    /// The concentric disk mapping of `random_in_unit_disk` for a given point (u, v) of [0, 1)^2
+    pub fn sample_unit_disk(u: f64, v: f64) -> Self {
+        let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return Self::default();
         }
+        let (radius, theta) = match a.abs() > b.abs() {
+            true => (a, (PI / 4.0) * (b / a)),
+            false => (b, (PI / 2.0) - (PI / 4.0) * (a / b)),
+        };
+        Self::new(radius * theta.cos(), radius * theta.sin(), 0.0)
+    }
+
+    /// Two unit vectors that form a right-handed orthonormal basis with this unit vector
+    /// (Duff et al., "Building an Orthonormal Basis, Revisited", 2017)
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let sign = 1.0_f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Self::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Self::new(b, sign + self.y * self.y * a, -self.y),
+        )
     }
 
     pub fn reflection(&self, normal_vec: &Self) -> Self {
@@ -0,0 +1,186 @@
+use std::path::PathBuf;
+
+use clap::{value_parser, Args, Parser, Subcommand, ValueEnum};
+use lib::utilities::{
+    image::{ExrPrecision, ImageFormat},
+    sampler::SamplerKind,
+};
+
+/// Renders scenes described in JSON scene files.
+/// Without a subcommand the options of `render` apply.
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub render: RenderArgs,
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed once at startup
+pub enum Command {
+    /// Renders a scene to an image file
+    Render(RenderArgs),
+    /// Checks a scene file (including the files it references) without rendering it
+    ValidateScene(SceneArgs),
+    /// Prints a summary of a scene: image, sampling and camera settings, objects
+    Info(SceneArgs),
+}
+
+#[derive(Args)]
+pub struct SceneArgs {
+    /// Scene file to read
+    #[arg(default_value = "scene_data.json")]
+    pub scene: PathBuf,
+
+    /// Reject keys that are not part of the scene schema
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub scene: SceneArgs,
+
+    /// Image file to write
+    #[arg(short, long, default_value = "image_test.ppm")]
+    pub output: PathBuf,
+
+    /// Image format [default: guessed from the output file extension]
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Image width in pixels. Without --height the aspect ratio of the scene is kept.
+    #[arg(long, value_parser = value_parser!(i32).range(1..))]
+    pub width: Option<i32>,
+
+    /// Image height in pixels
+    #[arg(long, value_parser = value_parser!(i32).range(1..))]
+    pub height: Option<i32>,
+
+    /// Samples per pixel
+    #[arg(long, value_parser = value_parser!(i32).range(1..))]
+    pub spp: Option<i32>,
+
+    /// Maximum number of ray bounces
+    #[arg(long, value_parser = value_parser!(i32).range(1..))]
+    pub depth: Option<i32>,
+
+    /// Seed of the random sampling; the same seed gives the same image [default: from the scene]
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// How the samples of each pixel are placed [default: from the scene]
+    #[arg(long, value_enum)]
+    pub sampler: Option<SamplerArg>,
+
+    /// Trace wavelengths instead of RGB, so dispersive glass splits light into colors
+    #[arg(long)]
+    pub spectral: bool,
+
+    /// Number of render threads [default: one per CPU]
+    #[arg(long, value_parser = value_parser!(u16).range(1..))]
+    pub threads: Option<u16>,
+
+    #[command(flatten)]
+    pub camera: CameraArgs,
+}
+
+/// Overrides for the camera of the scene file
+#[derive(Args)]
+#[command(next_help_heading = "Camera overrides")]
+pub struct CameraArgs {
+    /// Camera position
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_numbers::<3>, allow_hyphen_values = true)]
+    pub look_from: Option<[f64; 3]>,
+
+    /// Point the camera looks at
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_numbers::<3>, allow_hyphen_values = true)]
+    pub look_at: Option<[f64; 3]>,
+
+    /// Camera-relative up direction
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_numbers::<3>, allow_hyphen_values = true)]
+    pub up: Option<[f64; 3]>,
+
+    /// Vertical field of view, in degrees
+    #[arg(long)]
+    pub fov: Option<f64>,
+
+    /// Variation angle of rays through each pixel, in degrees (0 disables depth of field)
+    #[arg(long)]
+    pub defocus_angle: Option<f64>,
+
+    /// Distance from the camera to the plane of perfect focus
+    #[arg(long)]
+    pub focus_distance: Option<f64>,
+
+    /// Shutter open and close times; objects that move in between are blurred
+    #[arg(long, value_name = "OPEN,CLOSE", value_parser = parse_numbers::<2>, allow_hyphen_values = true)]
+    pub shutter: Option<[f64; 2]>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    /// Binary PPM (P6)
+    Ppm,
+    /// Plain text PPM (P3)
+    PpmAscii,
+    /// OpenEXR with half floats
+    Exr,
+    /// OpenEXR with 32-bit floats
+    ExrFloat,
+    /// Radiance RGBE
+    Hdr,
+}
+
+impl From<OutputFormat> for ImageFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Ppm => ImageFormat::PpmBinary,
+            OutputFormat::PpmAscii => ImageFormat::PpmAscii,
+            OutputFormat::Exr => ImageFormat::Exr(ExrPrecision::Half),
+            OutputFormat::ExrFloat => ImageFormat::Exr(ExrPrecision::Float),
+            OutputFormat::Hdr => ImageFormat::Hdr,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SamplerArg {
+    /// Independent random values
+    Independent,
+    /// Jittered strata
+    Stratified,
+    /// Owen-scrambled Halton sequence
+    Halton,
+    /// Owen-scrambled Sobol sequence
+    Sobol,
+}
+
+impl From<SamplerArg> for SamplerKind {
+    fn from(sampler: SamplerArg) -> Self {
+        match sampler {
+            SamplerArg::Independent => SamplerKind::Independent,
+            SamplerArg::Stratified => SamplerKind::Stratified,
+            SamplerArg::Halton => SamplerKind::Halton,
+            SamplerArg::Sobol => SamplerKind::Sobol,
+        }
+    }
+}
+
+/// Parses `N` comma-separated numbers, like `x,y,z`
+fn parse_numbers<const N: usize>(text: &str) -> Result<[f64; N], String> {
+    let components: Vec<f64> = text
+        .split(',')
+        .map(|component| component.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("{} in `{}`", e, text))?;
+    components
+        .try_into()
+        .map_err(|_| format!("expected {} comma-separated numbers, got `{}`", N, text))
+}
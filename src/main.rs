@@ -1,27 +1,186 @@
-use lib::utilities::{camera::Camera, geometry::Hittable, point::Point3, scenes, vector3::Vector3};
+mod cli;
 
-const ASPECT_RATIO: f64 = 16.0 / 9.0;
-const IMAGE_WIDTH: i32 = 1600;
-const SAMPLES_PER_PIXEL: i32 = 200;
-const MAX_DEPTH: i32 = 80;
-const VERTICAL_FOV: f64 = 40.0;
+use std::{path::Path, process};
+
+use clap::Parser;
+use cli::{CameraArgs, Cli, Command, RenderArgs, SceneArgs};
+use lib::utilities::{
+    bvh::BvhNode,
+    geometry::Hittable,
+    image::ImageFormat,
+    sampler::SamplerKind,
+    scene_description::{BackgroundDescription, SceneDescription},
+    scenes::{self, Scene},
+};
 
 fn main() {
     // https://raytracing.github.io/books/RayTracingInOneWeekend.html
-    let mut world: Vec<Box<dyn Hittable>> = Vec::new();
-    scenes::generate_scene(&mut world);
-
-    // Camera
-    let mut cam: Camera = Camera::new();
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_depth = MAX_DEPTH;
-
-    cam.vertical_field_of_view = VERTICAL_FOV; // Zooms in/out of the image
-    cam.look_from = Point3::new(13.0, 2.0, 3.0);
-    cam.look_at = Point3::new(0.0, 0.0, 0.0);
-    cam.vertical_camera_up = Vector3::new(0.0, 1.0, 0.0);
-
-    cam.render(world);
+    let cli = Cli::parse();
+    let result = match cli.command.unwrap_or(Command::Render(cli.render)) {
+        Command::Render(args) => render(&args),
+        Command::ValidateScene(args) => validate_scene(&args),
+        Command::Info(args) => info(&args),
+    };
+    if let Err(message) = result {
+        eprintln!("Error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn render(args: &RenderArgs) -> Result<(), String> {
+    let format = match args.format {
+        Some(format) => ImageFormat::from(format),
+        None => ImageFormat::from_path(&args.output).ok_or_else(|| {
+            format!(
+                "cannot tell the image format of {}, use --format",
+                args.output.display()
+            )
+        })?,
+    };
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut description = read_description(&args.scene)?;
+    apply_overrides(&mut description, args);
+    let scene = build(&description, &args.scene.scene)?;
+
+    let world = BvhNode::new(scene.world);
+    let mut camera = scene.camera;
+    let (width, height) = camera.image_size();
+    println!(
+        "Rendering {}x{} pixels, {} samples per pixel",
+        width, height, camera.samples_per_pixel
+    );
+    let image = camera.render(&world, &scene.lights);
+
+    image
+        .save(&args.output, format)
+        .map_err(|e| format!("cannot write {}: {}", args.output.display(), e))?;
+    println!("Done! Wrote {}", args.output.display());
+    Ok(())
+}
+
+fn validate_scene(args: &SceneArgs) -> Result<(), String> {
+    let description = read_description(args)?;
+    let scene = build(&description, &args.scene)?;
+    println!(
+        "{}: OK ({} objects)",
+        args.scene.display(),
+        scene.world.len()
+    );
+    Ok(())
+}
+
+fn info(args: &SceneArgs) -> Result<(), String> {
+    let description = read_description(args)?;
+    let scene = build(&description, &args.scene)?;
+    let camera = &scene.camera;
+    let (width, height) = camera.image_size();
+    let bounds = scene.world.bounding_box();
+
+    println!("Scene:             {}", args.scene.display());
+    println!("Version:           {}", description.version);
+    println!("Image:             {}x{}", width, height);
+    println!("Samples per pixel: {}", camera.samples_per_pixel);
+    println!("Max depth:         {}", camera.max_depth);
+    println!("Seed:              {}", camera.seed);
+    println!("Sampler:           {:?}", camera.sampler);
+    println!("Spectral:          {}", camera.spectral);
+    println!(
+        "Camera:            from {} to {}, up {}",
+        format_array(description.camera.look_from),
+        format_array(description.camera.look_at),
+        format_array(description.camera.up)
+    );
+    println!(
+        "Lens:              {} degrees field of view, {} degrees defocus, focused at {}",
+        camera.vertical_field_of_view, camera.defocus_angle, camera.focus_distance
+    );
+    println!(
+        "Shutter:           open from {} to {}",
+        camera.shutter_open, camera.shutter_close
+    );
+    let background = match &description.background {
+        BackgroundDescription::Solid { .. } => "solid color".to_owned(),
+        BackgroundDescription::Gradient { .. } => "gradient".to_owned(),
+        BackgroundDescription::Environment { file, .. } => format!("environment map {}", file),
+    };
+    println!("Background:        {}", background);
+    println!(
+        "Materials:         {}",
+        description
+            .materials
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    println!("Objects:           {}", scene.world.len());
+    println!("Lights:            {}", scene.lights.len());
+    if !bounds.is_empty() {
+        println!(
+            "Bounds:            {} to {}",
+            format_array([bounds.x.min, bounds.y.min, bounds.z.min]),
+            format_array([bounds.x.max, bounds.y.max, bounds.z.max])
+        );
+    }
+    Ok(())
+}
+
+fn read_description(args: &SceneArgs) -> Result<SceneDescription, String> {
+    scenes::load_scene_description(&args.scene, args.strict).map_err(|e| e.to_string())
+}
+
+fn build(description: &SceneDescription, path: &Path) -> Result<Scene, String> {
+    scenes::build_scene(description, path).map_err(|e| e.to_string())
+}
+
+/// Command-line settings win over the scene file
+fn apply_overrides(description: &mut SceneDescription, args: &RenderArgs) {
+    let image = &mut description.image;
+    if let Some(width) = args.width {
+        if args.height.is_none() {
+            // Keep the shape of the image
+            if let Some(height) = image.height.take() {
+                image.aspect_ratio = image.width as f64 / height as f64;
+            }
+        }
+        image.width = width;
+    }
+    if let Some(height) = args.height {
+        image.height = Some(height);
+    }
+
+    let render = &mut description.render;
+    render.samples_per_pixel = args.spp.unwrap_or(render.samples_per_pixel);
+    render.max_depth = args.depth.unwrap_or(render.max_depth);
+    render.seed = args.seed.unwrap_or(render.seed);
+    render.sampler = args.sampler.map_or(render.sampler, SamplerKind::from);
+    render.spectral = render.spectral || args.spectral;
+
+    let CameraArgs {
+        look_from,
+        look_at,
+        up,
+        fov,
+        defocus_angle,
+        focus_distance,
+        shutter,
+    } = args.camera;
+    let camera = &mut description.camera;
+    camera.look_from = look_from.unwrap_or(camera.look_from);
+    camera.look_at = look_at.unwrap_or(camera.look_at);
+    camera.up = up.unwrap_or(camera.up);
+    camera.vertical_fov = fov.unwrap_or(camera.vertical_fov);
+    camera.defocus_angle = defocus_angle.unwrap_or(camera.defocus_angle);
+    camera.focus_distance = focus_distance.or(camera.focus_distance);
+    camera.shutter = shutter.unwrap_or(camera.shutter);
+}
+
+fn format_array(array: [f64; 3]) -> String {
+    format!("({}, {}, {})", array[0], array[1], array[2])
 }
@@ -0,0 +1,53 @@
+use std::fs;
+
+use lib::utilities::{
+    background::{Background, EnvironmentMap},
+    color::Color,
+    hdr::read_hdr,
+    point::Point3,
+    ray::Ray,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+#[test]
+fn gradient_background_test() {
+    let background = Background::default();
+    let up = Ray::new(Point3::default(), Vector3::new(0.0, 1.0, 0.0));
+    let color = background.color(&up);
+    assert_eq!(color.get_r(), 0.5);
+    assert_eq!(color.get_g(), 0.7);
+    assert_eq!(color.get_b(), 1.0);
+}
+
+#[test]
+fn read_flat_hdr_test() {
+    let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+    bytes.extend([128, 64, 0, 129, 0, 0, 0, 0]);
+    let dir = common_config::TestDir::new("read_flat_hdr_test");
+    let path = dir.join("flat.hdr");
+    fs::write(&path, bytes).unwrap();
+
+    let image = read_hdr(&path).expect("hdr should load");
+    assert_eq!((image.get_width(), image.get_height()), (2, 1));
+    // 128.5 * 2^(129 - 136) = 1.00390625
+    assert_eq!(image.get_pixel(0, 0).get_r(), 128.5 / 128.0);
+    assert_eq!(image.get_pixel(1, 0).get_g(), 0.0);
+}
+
+#[test]
+fn environment_sample_pdf_test() {
+    // A dark map with one bright texel: samples land on it and agree with pdf()
+    let (width, height) = (8, 4);
+    let mut pixels = vec![Color::new(0.01, 0.01, 0.01); width * height];
+    pixels[width + 5] = Color::new(100.0, 100.0, 100.0);
+    let environment_map = EnvironmentMap::new(width, height, pixels, 30.0, 1.0);
+
+    for (random_u, random_v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7), (0.3, 0.99)] {
+        let (direction, pdf) = environment_map.sample(random_u, random_v);
+        assert!((direction.length() - 1.0).abs() < 1e-9);
+        assert!((environment_map.pdf(direction) - pdf).abs() < 1e-6 * pdf);
+        assert!(environment_map.lookup(direction).get_r() > 1.0);
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use lib::utilities::{
+    aabb::Aabb, bvh::BvhNode, geometry::Hittable, geometry::Sphere, interval::Interval,
+    material::Lambertian, point::Point3, ray::Ray, vector3::Vector3,
+};
+
+mod common_config;
+
+fn sphere_grid(count: i32) -> Vec<Box<dyn Hittable>> {
+    (0..count)
+        .flat_map(|x_index| {
+            (0..count).map(move |z_index| {
+                Box::new(Sphere::new(
+                    Point3::new(x_index as f64, 0.0, z_index as f64),
+                    0.3,
+                    Arc::new(Lambertian::default()),
+                )) as Box<dyn Hittable>
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn aabb_hit_test() {
+    let bbox = Aabb::from_points(Point3::new(1.0, 1.0, 1.0), Point3::new(-1.0, -1.0, -1.0));
+    let towards = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let away = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+    let beside = Ray::new(Point3::new(2.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+
+    assert!(bbox.hit(&towards, Interval::new(0.0, f64::INFINITY)));
+    assert!(!bbox.hit(&away, Interval::new(0.0, f64::INFINITY)));
+    assert!(!bbox.hit(&beside, Interval::new(0.0, f64::INFINITY)));
+}
+
+#[test]
+fn aabb_flat_box_padded_test() {
+    let bbox = Aabb::from_points(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 0.0));
+    assert!(bbox.z.size() > 0.0);
+    assert_eq!(bbox.longest_axis(), 1);
+}
+
+#[test]
+fn bvh_bounding_box_test() {
+    let world = BvhNode::new(sphere_grid(4));
+    let bbox = world.bounding_box();
+    assert_eq!(bbox.x.min, -0.3);
+    assert_eq!(bbox.x.max, 3.3);
+    assert_eq!(bbox.z.max, 3.3);
+}
+
+#[test]
+fn bvh_matches_linear_scan_test() {
+    let linear = sphere_grid(12);
+    let world = BvhNode::new(sphere_grid(12));
+
+    for x_index in 0..24 {
+        for z_index in 0..24 {
+            let ray = Ray::new(
+                Point3::new(x_index as f64 * 0.5, 5.0, z_index as f64 * 0.5),
+                Vector3::new(0.1, -1.0, 0.05),
+            );
+            let expected = linear.hit(ray, Interval::new(0.001, f64::INFINITY));
+            let actual = world.hit(ray, Interval::new(0.001, f64::INFINITY));
+
+            assert_eq!(
+                expected.map(|hit| hit.parameter),
+                actual.map(|hit| hit.parameter)
+            );
+        }
+    }
+}
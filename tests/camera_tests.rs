@@ -0,0 +1,72 @@
+use lib::utilities::{camera::Camera, point::Point3, sampler::SamplerKind, vector3::Cross};
+
+mod common_config;
+
+fn test_camera() -> Camera {
+    let mut camera = Camera::new();
+    camera.image_width = 40;
+    camera.image_height = 30;
+    camera.vertical_field_of_view = 50.0;
+    camera.look_from = Point3::new(3.0, 2.0, 4.0);
+    camera.look_at = Point3::new(0.0, 0.5, -1.0);
+    camera.focus_distance = 7.0;
+    camera
+}
+
+#[test]
+fn pinhole_camera_ray_test() {
+    // Without defocus every ray leaves look_from, through the pixel on a viewport placed
+    // at the look_at distance as before the lens existed
+    let mut camera = test_camera();
+    let w = (camera.look_from - camera.look_at).as_vec();
+    let focal_length = w.length();
+    let w = w.unit_vector();
+    let u = camera.vertical_camera_up.cross_prod(w).unit_vector();
+    let v = w.cross_prod(u);
+    let viewport_height = 2.0 * focal_length * (25.0_f64).to_radians().tan();
+    let viewport_width = viewport_height * 40.0 / 30.0;
+
+    let mut sampler = SamplerKind::Independent.create(1, 3);
+    let mut offsets = SamplerKind::Independent.create(1, 3);
+    for (x, y) in [(0, 0), (17, 9), (39, 29)] {
+        sampler.start_pixel_sample(x, y, 0);
+        offsets.start_pixel_sample(x, y, 0);
+        let ray = camera.sample_ray(x, y, sampler.as_mut());
+        assert_eq!((ray.get_origin() - camera.look_from).as_vec().length(), 0.0);
+
+        let (offset_x, offset_y) = offsets.get_2d();
+        let horizontal = (((x as f64 + offset_x) / 40.0) - 0.5) * viewport_width;
+        let vertical = (0.5 - ((y as f64 + offset_y) / 30.0)) * viewport_height;
+        let expected = ((u * horizontal) + (v * vertical) - (w * focal_length)).unit_vector();
+        let direction = ray.get_direction().unit_vector();
+        assert!((direction - expected).length() < 1e-9);
+    }
+}
+
+#[test]
+fn defocus_disk_test() {
+    let mut camera = test_camera();
+    camera.defocus_angle = 4.0;
+    let radius = 7.0 * (2.0_f64).to_radians().tan();
+    let w = (camera.look_from - camera.look_at).as_vec().unit_vector();
+
+    let mut sampler = SamplerKind::Independent.create(1, 8);
+    let mut largest_offset: f64 = 0.0;
+    for sample_index in 0..500 {
+        sampler.start_pixel_sample(20, 15, sample_index);
+        let ray = camera.sample_ray(20, 15, sampler.as_mut());
+        // Origins lie on the lens disk, around look_from and across the view direction
+        let offset = (ray.get_origin() - camera.look_from).as_vec();
+        assert!(offset.dot_prod(w).abs() < 1e-9);
+        assert!(offset.length() <= radius + 1e-9);
+        largest_offset = largest_offset.max(offset.length());
+
+        // and all rays through a pixel meet on the plane of focus
+        let direction = ray.get_direction();
+        let to_focus_plane = 7.0 / -direction.dot_prod(w);
+        let on_plane = ray.get_origin() + (direction * to_focus_plane);
+        let center = camera.look_from - (w * 7.0);
+        assert!((on_plane - center).as_vec().length() < 0.5);
+    }
+    assert!(largest_offset > 0.9 * radius);
+}
@@ -1,2 +1,41 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A scratch directory for one test, removed when dropped. The name holds the process id
+/// and the test name, so concurrent `cargo test` runs never share files.
+#[allow(dead_code)] // Only the tests that write files use it
+pub struct TestDir {
+    path: PathBuf,
+}
+
+#[allow(dead_code)]
+impl TestDir {
+    pub fn new(test_name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("ray_tracer_{}_{}", test_name, std::process::id()));
+        // Whatever an earlier process with the same id left behind must not leak in
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, file_name: &str) -> PathBuf {
+        self.path.join(file_name)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod common_config {}
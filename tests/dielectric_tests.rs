@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    hit_record::HitRecord,
+    interval::Interval,
+    material::{Dielectric, FresnelModel, Material},
+    microfacet::fresnel_dielectric,
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    vector3::{Cross, Vector3},
+};
+
+mod common_config;
+
+fn record(material: &dyn Material, is_face_front: bool, parameter: f64) -> HitRecord<'_> {
+    HitRecord::new(
+        Point3::default(),
+        Vector3::new(0.0, 0.0, 1.0),
+        parameter,
+        is_face_front,
+        material,
+        0.0,
+        0.0,
+    )
+}
+
+/// Fraction of rays arriving with `cos_theta` to the normal that are reflected
+fn reflected_fraction(material: &Dielectric, cos_theta: f64, is_face_front: bool) -> f64 {
+    let sin_theta = (1.0 - (cos_theta * cos_theta)).sqrt();
+    let incoming = Ray::new(
+        Point3::new(-sin_theta, 0.0, cos_theta),
+        Vector3::new(sin_theta, 0.0, -cos_theta),
+    );
+    let record = record(material, is_face_front, 1.0);
+    let mut sampler = SamplerKind::Independent.create(1, 6);
+    let count = 40_000;
+    let reflected = (0..count)
+        .filter(|sample_index| {
+            sampler.start_pixel_sample(0, 0, *sample_index);
+            let scatter = material
+                .scatter(incoming, &record, sampler.as_mut())
+                .unwrap();
+            assert!(scatter.pdf.is_none());
+            scatter.scattered_ray.get_direction().get_z() > 0.0
+        })
+        .count();
+    reflected as f64 / count as f64
+}
+
+#[test]
+fn dielectric_fresnel_test() {
+    let schlick = Dielectric::new(1.5);
+    let exact = Dielectric::new(1.5).with_fresnel(FresnelModel::Exact);
+    assert!((reflected_fraction(&schlick, 1.0, true) - 0.04).abs() < 0.005);
+    assert!((reflected_fraction(&exact, 1.0, true) - 0.04).abs() < 0.005);
+
+    // Glass mirrors at grazing angles, with either model
+    let cos_theta: f64 = 0.1;
+    let expected_schlick = Dielectric::reflectance(cos_theta, 1.0 / 1.5);
+    let expected_exact = fresnel_dielectric(cos_theta, 1.5);
+    assert!((expected_schlick - expected_exact).abs() > 0.01);
+    assert!((reflected_fraction(&schlick, cos_theta, true) - expected_schlick).abs() < 0.01);
+    assert!((reflected_fraction(&exact, cos_theta, true) - expected_exact).abs() < 0.01);
+
+    // From inside, past the critical angle, everything reflects
+    assert_eq!(reflected_fraction(&exact, 0.5, false), 1.0);
+    assert_eq!(reflected_fraction(&schlick, 0.5, false), 1.0);
+}
+
+#[test]
+fn beer_lambert_test() {
+    let absorption = Color::new(0.1, 0.5, 2.0);
+    let glass = Dielectric::new(1.5).with_absorption(absorption);
+    let mut sampler = SamplerKind::Independent.create(1, 0);
+    sampler.start_pixel_sample(0, 0, 0);
+    let down = Ray::new(Point3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, -2.0));
+
+    // Entering, nothing is absorbed yet
+    let entering = glass
+        .scatter(down, &record(&glass, true, 1.0), sampler.as_mut())
+        .unwrap();
+    assert_eq!(entering.attenuation.get_b(), 1.0);
+
+    // Leaving after 1.5 units inside, each channel falls off exponentially
+    let leaving = glass
+        .scatter(down, &record(&glass, false, 0.75), sampler.as_mut())
+        .unwrap();
+    assert!((leaving.attenuation.get_r() - (-0.15_f64).exp()).abs() < 1e-12);
+    assert!((leaving.attenuation.get_g() - (-0.75_f64).exp()).abs() < 1e-12);
+    assert!((leaving.attenuation.get_b() - (-3.0_f64).exp()).abs() < 1e-12);
+    let thicker = glass.transmittance(3.0);
+    assert!((thicker.get_b() - leaving.attenuation.get_b().powi(2)).abs() < 1e-12);
+}
+
+#[test]
+fn dielectric_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {
+                "bottle": {"type": "dielectric", "refractive_index": 1.5, "fresnel": "exact",
+                    "absorption": [0.8, 0.1, 0.6]},
+                "lens": {"type": "dielectric", "refractive_index": 1.7}
+            },
+            "objects": [
+                {"type": "sphere", "center": [0, 0, -1], "radius": 0.5, "material": "bottle"},
+                {"type": "sphere", "center": [1, 0, -1], "radius": 0.5, "material": "lens"}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 2);
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let mut sampler = SamplerKind::Independent.create(1, 4);
+
+    // Leaving the bottle from its center, light has crossed half a unit of glass
+    let outwards = Ray::new(Point3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = scene.world.hit(outwards, everywhere).unwrap();
+    for sample_index in 0..32 {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        let scatter = hit
+            .material
+            .scatter(outwards, &hit, sampler.as_mut())
+            .unwrap();
+        assert!((scatter.attenuation.get_r() - (-0.4_f64).exp()).abs() < 1e-12);
+        assert!((scatter.attenuation.get_g() - (-0.05_f64).exp()).abs() < 1e-12);
+        assert!((scatter.attenuation.get_b() - (-0.3_f64).exp()).abs() < 1e-12);
+    }
+
+    // The lens bends light by Snell's law with its own index
+    let inwards = Ray::new(Point3::new(1.3, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = scene.world.hit(inwards, everywhere).unwrap();
+    let mut refracted = 0;
+    for sample_index in 0..32 {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        let scatter = hit
+            .material
+            .scatter(inwards, &hit, sampler.as_mut())
+            .unwrap();
+        let direction = scatter.scattered_ray.get_direction().unit_vector();
+        if direction.dot_prod(hit.normal) < 0.0 {
+            let sin_theta = direction.cross_prod(hit.normal).length();
+            assert!((sin_theta - (0.6 / 1.7)).abs() < 1e-9, "{}", sin_theta);
+            refracted += 1;
+        }
+    }
+    assert!(refracted > 0);
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"bottle": {"type": "dielectric", "refractive_index": 1.5, "absorption": [0, -1, 0]}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.bottle.absorption");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"bottle": {"type": "dielectric", "refractive_index": 1.5, "fresnel": "fast"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.bottle.fresnel");
+}
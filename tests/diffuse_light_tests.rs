@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use lib::utilities::{
+    background::Background,
+    camera::Camera,
+    color::Color,
+    geometry::{Cuboid, Hittable, Quad},
+    hit_record::HitRecord,
+    material::{DiffuseLight, Lambertian, Material},
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    texture::SolidColor,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+#[test]
+fn diffuse_light_emits_without_scattering_test() {
+    let light =
+        DiffuseLight::from_texture(Arc::new(SolidColor::new(Color::new(1.0, 0.5, 0.25))), 4.0);
+    assert!(light.is_emissive());
+    assert!(!Lambertian::default().is_emissive());
+
+    let emitted = light.emitted(0.3, 0.7, Point3::default());
+    assert_eq!(emitted.get_r(), 4.0);
+    assert_eq!(emitted.get_g(), 2.0);
+    assert_eq!(emitted.get_b(), 1.0);
+
+    let record = HitRecord::new(
+        Point3::default(),
+        Vector3::new(0.0, 0.0, 1.0),
+        1.0,
+        true,
+        &light,
+        0.0,
+        0.0,
+    );
+    let incoming = Ray::new(Point3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut sampler = SamplerKind::Independent.create(1, 0);
+    sampler.start_pixel_sample(0, 0, 0);
+    assert!(light.scatter(incoming, &record, sampler.as_mut()).is_none());
+}
+
+#[test]
+fn closed_box_lit_by_diffuse_light_test() {
+    // No light gets in from outside: everything seen comes from the panel on the ceiling
+    let walls: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7)));
+    let panel: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(8.0, 8.0, 8.0)));
+    let world: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Cuboid::new(
+            Point3::new(-2.0, -2.0, -2.0),
+            Point3::new(2.0, 2.0, 2.0),
+            walls,
+        )),
+        Box::new(Quad::new(
+            Point3::new(-1.0, 1.99, -1.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 2.0),
+            panel,
+        )),
+    ];
+
+    let mut camera = Camera::new();
+    camera.image_width = 12;
+    camera.image_height = 12;
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 8;
+    camera.look_from = Point3::new(0.0, 0.0, 1.5);
+    camera.look_at = Point3::new(0.0, 0.0, -2.0);
+    camera.vertical_field_of_view = 60.0;
+    camera.background = Background::Solid(Color::default());
+    let image = camera.render(&world, &[]);
+    for pixel in image.pixels() {
+        assert!(pixel.get_r() > 0.0 && pixel.get_g() > 0.0 && pixel.get_b() > 0.0);
+    }
+}
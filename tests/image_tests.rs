@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use lib::utilities::{
+    color::Color,
+    image::{ExrPrecision, Image, ImageFormat},
+};
+
+mod common_config;
+
+fn two_pixel_image() -> Image {
+    let mut image = Image::new(2, 1);
+    image.set_pixel(0, 0, Color::new(1.0, 0.25, 0.0));
+    image.set_pixel(1, 0, Color::new(4.0, -1.0, 0.01));
+    image
+}
+
+#[test]
+fn color_to_bytes_test() {
+    // Gamma 2 correction, then clamping to [0, 255]
+    assert_eq!(Color::new(1.0, 0.25, 0.0).to_bytes(), [255, 128, 0]);
+    assert_eq!(Color::new(4.0, -1.0, 0.01).to_bytes(), [255, 0, 25]);
+}
+
+#[test]
+fn ppm_ascii_test() {
+    let mut buffer: Vec<u8> = Vec::new();
+    two_pixel_image()
+        .write(&mut buffer, ImageFormat::PpmAscii)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "P3\n2 1\n255\n255 128 0\n255 0 25\n"
+    );
+}
+
+#[test]
+fn ppm_binary_test() {
+    let mut buffer: Vec<u8> = Vec::new();
+    two_pixel_image()
+        .write(&mut buffer, ImageFormat::PpmBinary)
+        .unwrap();
+    let mut expected = b"P6\n2 1\n255\n".to_vec();
+    expected.extend([255, 128, 0, 255, 0, 25]);
+    assert_eq!(buffer, expected);
+}
+
+#[test]
+fn png_signature_test() {
+    let mut buffer: Vec<u8> = Vec::new();
+    two_pixel_image()
+        .write(&mut buffer, ImageFormat::Png)
+        .unwrap();
+    assert_eq!(&buffer[..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+}
+
+#[test]
+fn format_from_path_test() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("render.PNG")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("render.ppm")),
+        Some(ImageFormat::PpmAscii)
+    );
+    assert_eq!(ImageFormat::from_path(Path::new("render")), None);
+}
+
+#[test]
+fn hdr_round_trip_test() {
+    let mut image = Image::new(16, 2);
+    image.set_pixel(3, 0, Color::new(1000.0, 0.5, 0.0));
+    image.set_pixel(15, 1, Color::new(0.001, 0.002, 0.003));
+
+    let dir = common_config::TestDir::new("hdr_round_trip_test");
+    let path = dir.join("round_trip.hdr");
+    image.save(&path, ImageFormat::Hdr).unwrap();
+    let loaded = lib::utilities::hdr::read_hdr(&path).expect("hdr should load");
+
+    assert_eq!((loaded.get_width(), loaded.get_height()), (16, 2));
+    for (expected, actual) in image.pixels().iter().zip(loaded.pixels()) {
+        // RGBE keeps 8 bits of mantissa relative to the brightest channel
+        let tolerance = expected.get_r().max(expected.get_g()).max(expected.get_b()) / 100.0;
+        assert!((expected.get_r() - actual.get_r()).abs() <= tolerance);
+        assert!((expected.get_g() - actual.get_g()).abs() <= tolerance);
+        assert!((expected.get_b() - actual.get_b()).abs() <= tolerance);
+    }
+}
+
+#[test]
+fn exr_half_and_float_test() {
+    let image = two_pixel_image();
+    let mut half_buffer: Vec<u8> = Vec::new();
+    let mut float_buffer: Vec<u8> = Vec::new();
+    image
+        .write(&mut half_buffer, ImageFormat::Exr(ExrPrecision::Half))
+        .unwrap();
+    image
+        .write(&mut float_buffer, ImageFormat::Exr(ExrPrecision::Float))
+        .unwrap();
+
+    let exr_magic = [0x76, 0x2f, 0x31, 0x01];
+    assert_eq!(&half_buffer[..4], &exr_magic);
+    assert_eq!(&float_buffer[..4], &exr_magic);
+    assert!(float_buffer.len() > half_buffer.len());
+}
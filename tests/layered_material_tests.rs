@@ -0,0 +1,226 @@
+use std::{path::Path, sync::Arc};
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    interval::Interval,
+    material::{Coated, Lambertian, Material, Metal, MixMaterial},
+    microfacet::fresnel_dielectric,
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    texture::{Checker, SolidColor, Texture},
+    vector3::Vector3,
+};
+
+mod common_config;
+mod material_sampling;
+
+use material_sampling::{check_sampling, incoming, record_at};
+
+#[test]
+fn mix_material_test() {
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1)));
+    let blue: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.1, 0.1, 0.8)));
+    let mix = MixMaterial::new(red.clone(), blue.clone(), 0.25);
+    let record = record_at(&mix, Point3::default(), true);
+    let direction = Vector3::new(0.3, 0.2, 0.9);
+    let expected = (red.eval(incoming(), &record, direction) * 0.75)
+        + (blue.eval(incoming(), &record, direction) * 0.25);
+    let blended = mix.eval(incoming(), &record, direction);
+    assert!((blended.get_r() - expected.get_r()).abs() < 1e-12);
+    assert!((blended.get_b() - expected.get_b()).abs() < 1e-12);
+    let (kept, specular) = check_sampling(&mix, &record, 11);
+    assert_eq!(specular, 0.0);
+    assert!((kept.get_r() - 0.625).abs() < 1e-9 && (kept.get_b() - 0.275).abs() < 1e-9);
+
+    // Half mirror, half diffuse: the mirror keeps its share of the bounces
+    let mirror: Arc<dyn Material> = Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0));
+    let satin = MixMaterial::new(red.clone(), mirror, 0.5);
+    let (kept, specular) = check_sampling(&satin, &record_at(&satin, Point3::default(), true), 11);
+    assert!((specular - 0.5).abs() < 0.02, "{}", specular);
+    assert!((kept.get_g() - 0.5).abs() < 0.02);
+
+    // A texture weight masks one material with the other
+    let gray = |value| -> Arc<dyn Texture> { Arc::new(SolidColor::gray(value)) };
+    let mask: Arc<dyn Texture> = Arc::new(Checker::new(1.0, gray(0.0), gray(1.0)));
+    let masked = MixMaterial::from_texture(red, blue, mask);
+    let red_patch = record_at(&masked, Point3::new(0.5, 0.5, 0.5), true);
+    let blue_patch = record_at(&masked, Point3::new(1.5, 0.5, 0.5), true);
+    let on_red = masked.eval(incoming(), &red_patch, direction);
+    let on_blue = masked.eval(incoming(), &blue_patch, direction);
+    assert!(on_red.get_r() > on_red.get_b() && on_blue.get_b() > on_blue.get_r());
+}
+
+#[test]
+fn coated_test() {
+    // Over a black base, only the coat's own reflection is left
+    let black: Arc<dyn Material> = Arc::new(Lambertian::new(Color::default()));
+    let lacquer = Coated::new(black, 1.5);
+    let record = record_at(&lacquer, Point3::default(), true);
+    let (kept, specular) = check_sampling(&lacquer, &record, 11);
+    let expected = fresnel_dielectric(0.8, 1.5);
+    assert!((specular - expected).abs() < 0.005, "{}", specular);
+    assert!((kept.get_g() - expected).abs() < 0.005);
+
+    // Varnish over a white base keeps most of the light, but not more than the base
+    let white: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0)));
+    let varnished = Coated::new(white.clone(), 1.5);
+    let (kept, _) = check_sampling(
+        &varnished,
+        &record_at(&varnished, Point3::default(), true),
+        11,
+    );
+    assert!(
+        kept.get_g() > 0.85 && kept.get_g() < 1.0,
+        "{}",
+        kept.get_g()
+    );
+
+    // The coat dims the base's reflection once on the way in and once on the way out
+    let direction = Vector3::new(0.3, 0.2, 0.9);
+    let outside = record_at(&varnished, Point3::default(), true);
+    let coated = varnished.eval(incoming(), &outside, direction);
+    let bare = white.eval(incoming(), &outside, direction);
+    let crossings = (1.0 - fresnel_dielectric(0.8, 1.5))
+        * (1.0 - fresnel_dielectric(direction.unit_vector().get_z(), 1.5));
+    assert!((coated.get_g() - (bare.get_g() * crossings)).abs() < 1e-12);
+
+    // From inside, there is no coat
+    let inside = record_at(&varnished, Point3::default(), false);
+    assert_eq!(
+        varnished.eval(incoming(), &inside, direction).get_g(),
+        white.eval(incoming(), &inside, direction).get_g()
+    );
+}
+
+#[test]
+fn layered_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {
+                "varnished_wood": {"type": "coated", "refractive_index": 1.55,
+                    "base": {"type": "lambertian", "albedo": {"type": "noise", "style": "marble",
+                        "color": [0.6, 0.35, 0.15]}}},
+                "car_paint": {"type": "coated",
+                    "base": {"type": "metal", "albedo": [0.6, 0.05, 0.05], "fuzz": 0.3}},
+                "rusty": {"type": "mix", "weight": {"type": "noise", "scale": 4},
+                    "first": {"type": "conductor", "metal": "silver", "roughness": 0.2},
+                    "second": {"type": "lambertian", "albedo": [0.4, 0.2, 0.1]}}
+            },
+            "objects": [
+                {"type": "sphere", "center": [0, 0, -1], "radius": 0.5, "material": "varnished_wood"},
+                {"type": "sphere", "center": [1, 0, -1], "radius": 0.5, "material": "car_paint"},
+                {"type": "sphere", "center": [-1, 0, -1], "radius": 0.5, "material": "rusty"},
+                {"type": "sphere", "center": [0, 1, -1], "radius": 0.5,
+                    "material": {"type": "mix", "weight": 0.3,
+                        "first": {"type": "dielectric", "refractive_index": 1.5},
+                        "second": {"type": "coated", "base": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}}}}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 4);
+    // A ray meeting the front of the sphere centered at (`x`, `y`) head-on
+    let head_on = |x: f64, y: f64| Ray::new(Point3::new(x, y, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let mut sampler = SamplerKind::Independent.create(1, 5);
+    let count = 4000;
+    let mut scatters_at = |x: f64, y: f64| {
+        let hit = scene.world.hit(head_on(x, y), everywhere).unwrap();
+        (0..count)
+            .filter_map(|sample_index| {
+                sampler.start_pixel_sample(0, 0, sample_index);
+                hit.material.scatter(head_on(x, y), &hit, sampler.as_mut())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // The varnish mirrors the Fresnel reflectance of its own index, over brown wood
+    let varnish = scatters_at(0.0, 0.0);
+    let mirrored = varnish
+        .iter()
+        .filter(|scatter| scatter.pdf.is_none())
+        .count();
+    let expected = fresnel_dielectric(1.0, 1.55);
+    assert!((mirrored as f64 / count as f64 - expected).abs() < 0.012);
+    for scatter in varnish.iter().filter(|scatter| scatter.pdf.is_some()) {
+        assert!(scatter.attenuation.get_r() > scatter.attenuation.get_g());
+        assert!(scatter.attenuation.get_g() > scatter.attenuation.get_b());
+    }
+
+    // The default coat mirrors white light, and the rest reflects off the red metal
+    let paint = scatters_at(1.0, 0.0);
+    let white = paint
+        .iter()
+        .filter(|scatter| scatter.attenuation.get_g() == 1.0)
+        .count();
+    assert!((white as f64 / count as f64 - fresnel_dielectric(1.0, 1.5)).abs() < 0.012);
+    for scatter in paint
+        .iter()
+        .filter(|scatter| scatter.attenuation.get_g() < 1.0)
+    {
+        assert!(scatter.pdf.is_none());
+        assert!(scatter.attenuation.get_r() > 10.0 * scatter.attenuation.get_g());
+    }
+
+    // Away from the mirror direction, only the brown lambertian under the rust mask remains
+    let rust = head_on(-1.0, 0.0);
+    let hit = scene.world.hit(rust, everywhere).unwrap();
+    let grazing = hit.material.eval(rust, &hit, Vector3::new(0.95, 0.0, 0.3));
+    assert!(grazing.get_r() > 0.0);
+    assert!((grazing.get_r() / grazing.get_g() - 2.0).abs() < 0.01);
+    assert!((grazing.get_g() / grazing.get_b() - 2.0).abs() < 0.01);
+    let mirror = hit.material.eval(rust, &hit, Vector3::new(0.0, 0.0, 1.0));
+    assert!(mirror.get_b() > 10.0 * grazing.get_b());
+
+    // Seven in ten samples go to the glass, which also lets light through
+    let glaze = scatters_at(0.0, 1.0);
+    let specular = glaze.iter().filter(|scatter| scatter.pdf.is_none()).count();
+    let expected = 0.7 + 0.3 * fresnel_dielectric(1.0, 1.5);
+    assert!((specular as f64 / count as f64 - expected).abs() < 0.02);
+    assert!(glaze
+        .iter()
+        .any(|scatter| scatter.scattered_ray.get_direction().get_z() < 0.0));
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"rusty": {"type": "mix", "weight": 1.5,
+            "first": {"type": "lambertian", "albedo": [1, 1, 1]},
+            "second": {"type": "lambertian", "albedo": [0, 0, 0]}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.rusty.weight");
+
+    // Nested materials report errors at their own path
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"rusty": {"type": "mix",
+            "first": {"type": "lambertian", "albedo": [1, 2, 1]},
+            "second": {"type": "lambertian", "albedo": [0, 0, 0]}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.rusty.first.albedo");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"wood": {"type": "lambertian", "albedo": [0.5, 0.3, 0.1]},
+            "varnished": {"type": "coated", "base": "wood"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::WrongType));
+    assert_eq!(error.get_path(), "materials.varnished.base");
+}
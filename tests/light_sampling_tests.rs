@@ -0,0 +1,241 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use lib::utilities::{
+    background::Background,
+    camera::Camera,
+    color::Color,
+    geometry::{Cuboid, Disk, Hittable, Quad, Sphere},
+    hit_record::HitRecord,
+    interval::Interval,
+    material::{Coated, Dielectric, DiffuseLight, Lambertian, Material, Metal, MixMaterial},
+    point::Point3,
+    random::Pcg32,
+    ray::Ray,
+    sampler::SamplerKind,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+const EVERYWHERE: Interval = Interval::new(0.001, f64::INFINITY);
+
+/// Monte Carlo estimate of the integral of `pdf_value` over all directions, which must be 1
+fn integrate_pdf(light: &dyn Hittable, origin: Point3) -> f64 {
+    let mut generator = Pcg32::from_seed(17);
+    let samples = 400_000;
+    let total: f64 = (0..samples)
+        .map(|_| {
+            let direction = Vector3::sample_unit_sphere(generator.next_f64(), generator.next_f64());
+            light.pdf_value(Ray::new(origin, direction), EVERYWHERE)
+        })
+        .sum();
+    total * 4.0 * PI / samples as f64
+}
+
+/// Every sampled direction must point at the light
+fn check_random_directions(light: &dyn Hittable, origin: Point3) {
+    let mut generator = Pcg32::from_seed(5);
+    for _ in 0..1000 {
+        let sample = (generator.next_f64(), generator.next_f64());
+        let direction = light.random_direction(origin, sample);
+        assert!(light.pdf_value(Ray::new(origin, direction), EVERYWHERE) > 0.0);
+    }
+}
+
+#[test]
+fn sphere_light_pdf_test() {
+    let sphere = Sphere::new(
+        Point3::new(0.0, 3.0, 0.0),
+        1.0,
+        Arc::new(DiffuseLight::default()),
+    );
+    let outside = Point3::new(0.5, 0.0, 0.0);
+    assert!((integrate_pdf(&sphere, outside) - 1.0).abs() < 0.02);
+    check_random_directions(&sphere, outside);
+
+    let inside = Point3::new(0.0, 3.5, 0.2);
+    assert!((integrate_pdf(&sphere, inside) - 1.0).abs() < 0.02);
+    check_random_directions(&sphere, inside);
+}
+
+#[test]
+fn quad_light_pdf_test() {
+    let quad = Quad::new(
+        Point3::new(-1.0, 2.0, -0.5),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.5, 1.0),
+        Arc::new(DiffuseLight::default()),
+    );
+    let origin = Point3::new(0.3, 0.0, 0.1);
+    assert!((integrate_pdf(&quad, origin) - 1.0).abs() < 0.02);
+    check_random_directions(&quad, origin);
+
+    // Behind the cut-off distance the quad does not count
+    let up = Ray::new(origin, Vector3::new(0.0, 1.0, 0.0));
+    assert!(quad.pdf_value(up, EVERYWHERE) > 0.0);
+    assert_eq!(quad.pdf_value(up, Interval::new(0.001, 1.0)), 0.0);
+}
+
+#[test]
+fn quad_hit_uv_test() {
+    let quad = Quad::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 4.0, 0.0),
+        Arc::new(Lambertian::default()),
+    );
+    let ray = Ray::new(Point3::new(0.5, 3.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = quad.hit(ray, EVERYWHERE).unwrap();
+    assert!((hit.parameter - 5.0).abs() < 1e-12);
+    assert!((hit.u - 0.25).abs() < 1e-12 && (hit.v - 0.75).abs() < 1e-12);
+    assert!(hit.is_face_front);
+
+    let beside = Ray::new(Point3::new(2.5, 3.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(quad.hit(beside, EVERYWHERE).is_none());
+}
+
+#[test]
+fn lambertian_eval_matches_scatter_test() {
+    let material = Lambertian::new(Color::new(0.6, 0.4, 0.2));
+    let record = HitRecord::new(
+        Point3::default(),
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        true,
+        &material,
+        0.0,
+        0.0,
+    );
+    let incoming = Ray::new(Point3::new(1.0, 1.0, 0.0), Vector3::new(-1.0, -1.0, 0.0));
+    let mut sampler = SamplerKind::Independent.create(1, 3);
+    for sample_index in 0..64 {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        let scatter = material
+            .scatter(incoming, &record, sampler.as_mut())
+            .unwrap();
+        let direction = scatter.scattered_ray.get_direction();
+        let pdf = scatter.pdf.unwrap();
+        assert!((pdf - material.pdf(incoming, &record, direction)).abs() < 1e-12);
+        let weight = material.eval(incoming, &record, direction) * (1.0 / pdf);
+        assert!((weight.get_r() - scatter.attenuation.get_r()).abs() < 1e-9);
+    }
+}
+
+/// A sphere light centered `height` above the origin
+fn lamp(height: f64, radius: f64) -> Sphere {
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
+    Sphere::new(Point3::new(0.0, height, 0.0), radius, light)
+}
+
+/// A floor lit by sphere lights, in a black void
+fn render_lit_floor(floor: Arc<dyn Material>, lamps: &[Sphere], sample_lights: bool) -> f64 {
+    let mut world: Vec<Box<dyn Hittable>> = vec![Box::new(Quad::new(
+        Point3::new(-5.0, 0.0, -5.0),
+        Vector3::new(0.0, 0.0, 10.0),
+        Vector3::new(10.0, 0.0, 0.0),
+        floor,
+    ))];
+    let mut lights: Vec<Box<dyn Hittable>> = Vec::new();
+    for lamp in lamps {
+        world.push(Box::new(lamp.clone()));
+        if sample_lights {
+            lights.push(Box::new(lamp.clone()));
+        }
+    }
+
+    let mut camera = Camera::new();
+    camera.image_width = 24;
+    camera.image_height = 16;
+    camera.samples_per_pixel = 256;
+    camera.max_depth = 4;
+    camera.look_from = Point3::new(0.0, 3.0, 6.0);
+    camera.look_at = Point3::new(0.0, 0.0, 0.0);
+    camera.background = Background::Solid(Color::default());
+    let image = camera.render(&world, &lights);
+    let total: f64 = image.pixels().iter().map(|pixel| pixel.get_g()).sum();
+    total / image.pixels().len() as f64
+}
+
+#[test]
+fn next_event_estimation_unbiased_test() {
+    // Light sampling changes the noise, not the expected brightness
+    let floor: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7)));
+    let with_lights = render_lit_floor(floor.clone(), &[lamp(2.0, 0.7)], true);
+    let without_lights = render_lit_floor(floor, &[lamp(2.0, 0.7)], false);
+    assert!(
+        (with_lights - without_lights).abs() < 0.03 * without_lights,
+        "{} vs {}",
+        with_lights,
+        without_lights
+    );
+}
+
+#[test]
+fn stacked_lights_unbiased_test() {
+    // The upper lamp hides partly behind the lower one: a light sample is weighted only
+    // against the BSDF finding the light it aims at, not the one behind it
+    let floor: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7)));
+    let lamps = [lamp(2.5, 2.0), lamp(7.0, 2.0)];
+    let with_lights = render_lit_floor(floor.clone(), &lamps, true);
+    let without_lights = render_lit_floor(floor, &lamps, false);
+    assert!(
+        (with_lights - without_lights).abs() < 0.01 * without_lights,
+        "{} vs {}",
+        with_lights,
+        without_lights
+    );
+}
+
+#[test]
+fn next_event_estimation_layered_unbiased_test() {
+    // Lights are sampled for the diffuse part even when a bounce takes the specular part
+    let diffuse: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7)));
+    let mirror: Arc<dyn Material> = Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0));
+    let floors: [(&str, Arc<dyn Material>); 3] = [
+        ("varnish", Arc::new(Coated::new(diffuse.clone(), 1.5))),
+        (
+            "satin",
+            Arc::new(MixMaterial::new(diffuse.clone(), mirror, 0.5)),
+        ),
+        (
+            "glaze",
+            Arc::new(MixMaterial::new(
+                diffuse,
+                Arc::new(Dielectric::new(1.5)),
+                0.3,
+            )),
+        ),
+    ];
+    for (name, floor) in floors {
+        let with_lights = render_lit_floor(floor.clone(), &[lamp(2.0, 0.7)], true);
+        let without_lights = render_lit_floor(floor, &[lamp(2.0, 0.7)], false);
+        assert!(
+            (with_lights - without_lights).abs() < 0.03 * without_lights,
+            "{}: {} vs {}",
+            name,
+            with_lights,
+            without_lights
+        );
+    }
+}
+
+#[test]
+fn box_and_disk_light_pdf_test() {
+    let cuboid = Cuboid::new(
+        Point3::new(-1.0, 2.0, -1.0),
+        Point3::new(1.0, 3.0, 0.5),
+        Arc::new(DiffuseLight::default()),
+    );
+    let origin = Point3::new(0.5, 0.0, 0.3);
+    assert!((integrate_pdf(&cuboid, origin) - 1.0).abs() < 0.02);
+    check_random_directions(&cuboid, origin);
+
+    let disk = Disk::new(
+        Point3::new(0.0, 2.0, 0.0),
+        Vector3::new(0.3, -1.0, 0.2),
+        1.5,
+        Arc::new(DiffuseLight::default()),
+    );
+    assert!((integrate_pdf(&disk, origin) - 1.0).abs() < 0.02);
+    check_random_directions(&disk, origin);
+}
@@ -0,0 +1,56 @@
+use lib::utilities::{
+    color::Color, hit_record::HitRecord, material::Material, point::Point3, ray::Ray,
+    sampler::SamplerKind, vector3::Vector3,
+};
+
+/// A hit at `point` on a surface facing +z, with +x as its tangent
+pub fn record_at(material: &dyn Material, point: Point3, is_face_front: bool) -> HitRecord<'_> {
+    let mut record = HitRecord::new(
+        point,
+        Vector3::new(0.0, 0.0, 1.0),
+        1.0,
+        is_face_front,
+        material,
+        0.0,
+        0.0,
+    );
+    record.dpdu = Vector3::new(1.0, 0.0, 0.0);
+    record
+}
+
+/// A ray arriving at the origin from above, with a cosine of 0.8 to the normal
+pub fn incoming() -> Ray {
+    Ray::new(Point3::new(-1.0, 0.0, 1.0), Vector3::new(0.6, 0.0, -0.8))
+}
+
+/// Scatters `incoming()` many times, checking the non-specular samples against `eval`
+/// and `pdf`. Returns the mean weight, which is the energy the surface keeps, and the
+/// fraction of samples that were specular.
+pub fn check_sampling(material: &dyn Material, record: &HitRecord, seed: u64) -> (Color, f64) {
+    let mut sampler = SamplerKind::Independent.create(1, seed);
+    let count = 20_000;
+    let mut total = Color::default();
+    let mut specular = 0;
+    for sample_index in 0..count {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        let Some(scatter) = material.scatter(incoming(), record, sampler.as_mut()) else {
+            continue;
+        };
+        total += scatter.attenuation;
+        let Some(pdf) = scatter.pdf else {
+            specular += 1;
+            continue;
+        };
+        let direction = scatter.scattered_ray.get_direction();
+        assert!((pdf - material.pdf(incoming(), record, direction)).abs() < 1e-6 * pdf);
+        let weight = material.eval(incoming(), record, direction) / pdf;
+        assert!((weight.get_r() - scatter.attenuation.get_r()).abs() < 1e-6);
+        assert!((weight.get_g() - scatter.attenuation.get_g()).abs() < 1e-6);
+        assert!((weight.get_b() - scatter.attenuation.get_b()).abs() < 1e-6);
+    }
+    (total / count as f64, specular as f64 / count as f64)
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod material_sampling {}
@@ -0,0 +1,197 @@
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+use lib::utilities::{
+    color::Color,
+    geometry::{Hittable, Sphere},
+    hit_record::HitRecord,
+    interval::Interval,
+    material::{Isotropic, Lambertian, Material},
+    medium::ConstantMedium,
+    point::Point3,
+    random::Pcg32,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+const EVERYWHERE: Interval = Interval::new(0.001, f64::INFINITY);
+
+fn medium_record(material: &dyn Material) -> HitRecord<'_> {
+    HitRecord::new(
+        Point3::default(),
+        Vector3::new(1.0, 0.0, 0.0),
+        1.0,
+        true,
+        material,
+        0.0,
+        0.0,
+    )
+}
+
+#[test]
+fn henyey_greenstein_test() {
+    let incoming = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    for anisotropy in [0.0, 0.7, -0.5] {
+        let material = Isotropic::new(Color::new(0.8, 0.6, 0.4)).with_anisotropy(anisotropy);
+        let record = medium_record(&material);
+
+        // The phase function is a density over all directions
+        let mut generator = Pcg32::from_seed(9);
+        let samples = 200_000;
+        let total: f64 = (0..samples)
+            .map(|_| {
+                let direction =
+                    Vector3::sample_unit_sphere(generator.next_f64(), generator.next_f64());
+                material.pdf(incoming, &record, direction)
+            })
+            .sum();
+        assert!((total * 4.0 * PI / samples as f64 - 1.0).abs() < 0.02);
+
+        // Sampled directions follow it: their mean cosine is g, and the weight is the albedo
+        let mut sampler = SamplerKind::Independent.create(1, 4);
+        let count = 20_000;
+        let mut cosine_sum = 0.0;
+        for sample_index in 0..count {
+            sampler.start_pixel_sample(0, 0, sample_index);
+            let scatter = material
+                .scatter(incoming, &record, sampler.as_mut())
+                .unwrap();
+            let direction = scatter.scattered_ray.get_direction();
+            cosine_sum += direction.unit_vector().get_x();
+            let pdf = scatter.pdf.unwrap();
+            assert!((pdf - material.pdf(incoming, &record, direction)).abs() < 1e-9 * pdf);
+            let weight = material.eval(incoming, &record, direction) * (1.0 / pdf);
+            assert!((weight.get_g() - 0.6).abs() < 1e-9);
+            assert!((scatter.attenuation.get_g() - 0.6).abs() < 1e-12);
+        }
+        assert!((cosine_sum / count as f64 - anisotropy).abs() < 0.02);
+    }
+}
+
+fn fog_ball(density: f64) -> ConstantMedium {
+    let boundary: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point3::default(),
+        1.0,
+        Arc::new(Lambertian::default()),
+    ));
+    ConstantMedium::new(boundary, density, Arc::new(Isotropic::default()))
+}
+
+#[test]
+fn constant_medium_transmittance_test() {
+    let density = 0.4;
+    let medium = fog_ball(density);
+    // Rays through the center cross 2 units of fog. The same ray scatters at a new depth
+    // for every sample, drawn from the sampler of the path.
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -2.0));
+    let mut sampler = SamplerKind::Independent.create(1, 12);
+    let count = 20_000;
+    let mut depths = Vec::new();
+    for sample_index in 0..count {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        if let Some(hit) = medium.sample_hit(ray, EVERYWHERE, sampler.as_mut()) {
+            let z = hit.point.get_z();
+            assert!((-1.0..=1.0).contains(&z));
+            assert!((hit.parameter - ((5.0 - z) / 2.0)).abs() < 1e-9);
+            depths.push(z);
+        }
+    }
+    let expected = 1.0 - (-density * 2.0_f64).exp();
+    assert!((depths.len() as f64 / count as f64 - expected).abs() < 0.015);
+    assert!(depths.iter().any(|z| (z - depths[0]).abs() > 0.1));
+    // Without a sampler there is nothing to decide with, and the ray passes
+    assert!(medium.hit(ray, EVERYWHERE).is_none());
+
+    // Nothing past the end of the interval, and rays starting inside scatter inside
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let short = Interval::new(0.001, 3.9);
+    assert!(medium.sample_hit(ray, short, sampler.as_mut()).is_none());
+    let dense = fog_ball(1e6);
+    let inside = Ray::new(Point3::new(0.0, 0.0, 0.5), Vector3::new(0.0, 0.0, -1.0));
+    let hit = dense
+        .sample_hit(inside, EVERYWHERE, sampler.as_mut())
+        .unwrap();
+    assert!(hit.parameter < 0.01);
+    assert_eq!(dense.bounding_box().x.max, 1.0);
+}
+
+#[test]
+fn medium_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {"haze": {"type": "isotropic", "albedo": [0.9, 0.9, 0.9], "anisotropy": 0.6}},
+            "objects": [
+                {"type": "constant_medium", "density": 0.2, "material": "haze",
+                 "boundary": {"type": "box", "min": [-1, 0, -1], "max": [1, 2, 1], "material": "haze"}}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 1);
+    // Rays across the box cross 2 units of haze of density 0.2
+    let through = Ray::new(Point3::new(0.0, 1.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut sampler = SamplerKind::Independent.create(1, 5);
+    let count = 20_000;
+    let mut cosine_sum = 0.0;
+    let mut scattered = 0;
+    for sample_index in 0..count {
+        sampler.start_pixel_sample(0, 0, sample_index);
+        let Some(hit) = scene
+            .world
+            .sample_hit(through, EVERYWHERE, sampler.as_mut())
+        else {
+            continue;
+        };
+        let scatter = hit
+            .material
+            .scatter(through, &hit, sampler.as_mut())
+            .unwrap();
+        assert!((scatter.attenuation.get_g() - 0.9).abs() < 1e-12);
+        cosine_sum -= scatter.scattered_ray.get_direction().unit_vector().get_z();
+        scattered += 1;
+    }
+    let expected = 1.0 - (-0.4_f64).exp();
+    assert!((scattered as f64 / count as f64 - expected).abs() < 0.015);
+    // Forward scattering, with the mean cosine of the Henyey-Greenstein g
+    assert!((cosine_sum / scattered as f64 - 0.6).abs() < 0.03);
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"haze": {"type": "isotropic", "albedo": [1, 1, 1], "anisotropy": 1}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.haze.anisotropy");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "constant_medium", "density": 1,
+            "material": {"type": "isotropic", "albedo": [1, 1, 1]},
+            "boundary": {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "missing"}}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownMaterial));
+    assert_eq!(error.get_path(), "objects[0].boundary.material");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "constant_medium", "density": 0,
+            "material": {"type": "isotropic", "albedo": [1, 1, 1]},
+            "boundary": {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": {"type": "lambertian", "albedo": [1, 1, 1]}}}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.get_path(), "objects[0].density");
+}
@@ -0,0 +1,68 @@
+use std::{fs, sync::Arc};
+
+use lib::utilities::{
+    geometry::Hittable, interval::Interval, material::Lambertian, mesh::TriangleMesh,
+    point::Point3, ray::Ray, transform::Transform, vector3::Vector3,
+};
+
+mod common_config;
+
+const QUAD_OBJ: &str = "mtllib quad.mtl
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+vt 0.0 0.0
+vt 1.0 0.0
+vt 1.0 1.0
+vt 0.0 1.0
+usemtl shiny
+f 1/1 2/2 3/3 4/4
+";
+
+const QUAD_MTL: &str = "newmtl shiny
+Kd 0.1 0.1 0.1
+Ks 0.9 0.9 0.9
+Ns 200
+";
+
+#[test]
+fn obj_quad_load_test() {
+    let dir = common_config::TestDir::new("obj_quad_load_test");
+    fs::write(dir.join("quad.obj"), QUAD_OBJ).unwrap();
+    fs::write(dir.join("quad.mtl"), QUAD_MTL).unwrap();
+
+    let transform = Transform::scale(Vector3::new(2.0, 2.0, 2.0))
+        .then(&Transform::translate(Vector3::new(0.0, 0.0, -2.0)));
+    let mesh = TriangleMesh::from_obj(
+        &dir.join("quad.obj"),
+        &transform,
+        Arc::new(Lambertian::default()),
+    )
+    .expect("mesh should load");
+
+    assert_eq!(mesh.triangle_count(), 2);
+    let bbox = mesh.bounding_box();
+    assert_eq!(bbox.x.max, 2.0);
+    assert_eq!(bbox.y.max, 2.0);
+
+    let ray = Ray::new(Point3::new(1.5, 0.5, 0.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = mesh
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .expect("ray should hit the mesh");
+    assert_eq!(hit.parameter, 2.0);
+    assert!((hit.u - 0.75).abs() < 1e-6);
+    assert!((hit.v - 0.25).abs() < 1e-6);
+}
+
+#[test]
+fn obj_missing_file_test() {
+    let dir = common_config::TestDir::new("obj_missing_file_test");
+    let missing = dir.join("missing.obj");
+    let result = TriangleMesh::from_obj(
+        &missing,
+        &Transform::IDENTITY,
+        Arc::new(Lambertian::default()),
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,239 @@
+use std::{f64::consts::PI, path::Path};
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    interval::Interval,
+    material::{Conductor, ConductorPreset, Material, RoughDielectric},
+    microfacet::{fresnel_complex, fresnel_dielectric, TrowbridgeReitz},
+    point::Point3,
+    random::Pcg32,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    vector3::Vector3,
+};
+
+mod common_config;
+mod material_sampling;
+
+use material_sampling::{check_sampling, incoming, record_at};
+
+const SAMPLES: usize = 200_000;
+
+/// Monte Carlo integral of `function` over the sphere of directions
+fn integrate_sphere(function: impl Fn(Vector3) -> f64) -> f64 {
+    let mut generator = Pcg32::from_seed(5);
+    let total: f64 = (0..SAMPLES)
+        .map(|_| {
+            function(Vector3::sample_unit_sphere(
+                generator.next_f64(),
+                generator.next_f64(),
+            ))
+        })
+        .sum();
+    total * 4.0 * PI / SAMPLES as f64
+}
+
+#[test]
+fn fresnel_test() {
+    assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+    // Seen from inside the glass, past the critical angle everything reflects
+    assert_eq!(fresnel_dielectric(0.3, 1.0 / 1.5), 1.0);
+    assert_eq!(fresnel_dielectric(0.0, 1.5), 1.0);
+    // A metal without absorption is a dielectric
+    for cos in [0.1, 0.5, 0.9] {
+        assert!((fresnel_complex(cos, 1.5, 0.0) - fresnel_dielectric(cos, 1.5)).abs() < 1e-9);
+    }
+
+    // Gold is yellow, silver is nearly white, and all metals whiten at grazing angles
+    let gold = Conductor::from_preset(ConductorPreset::Gold, 0.0).normal_reflectance();
+    assert!(gold.get_r() > 0.9 && gold.get_b() < 0.4);
+    let silver = Conductor::from_preset(ConductorPreset::Silver, 0.0).normal_reflectance();
+    assert!(silver.get_b() > 0.9);
+    assert!(fresnel_complex(0.01, 1.44248, 1.60322) > 0.9);
+}
+
+#[test]
+fn ggx_distribution_test() {
+    for (alpha_x, alpha_y) in [(0.3, 0.3), (0.1, 0.5), (0.8, 0.8)] {
+        let distribution = TrowbridgeReitz::new(alpha_x, alpha_y);
+        // Projected microfacet area adds up to the macro surface
+        let projected = integrate_sphere(|m| match m.get_z() > 0.0 {
+            true => distribution.d(m) * m.get_z(),
+            false => 0.0,
+        });
+        assert!((projected - 1.0).abs() < 0.03, "{}", projected);
+
+        // The visible normals are a density, and the sampling routine follows it
+        let w = Vector3::new(0.5, -0.3, 0.8).unit_vector();
+        let visible = |m: Vector3| match m.get_z() > 0.0 && m.dot_prod(w) > 0.0 {
+            true => distribution.visible_d(w, m),
+            false => 0.0,
+        };
+        assert!((integrate_sphere(visible) - 1.0).abs() < 0.03);
+        let expected_x = integrate_sphere(|m| visible(m) * m.get_x());
+        let mut generator = Pcg32::from_seed(8);
+        let sampled_x = (0..SAMPLES)
+            .map(|_| {
+                let m =
+                    distribution.sample_visible(w, (generator.next_f64(), generator.next_f64()));
+                assert!(m.get_z() > 0.0);
+                m.get_x()
+            })
+            .sum::<f64>()
+            / SAMPLES as f64;
+        assert!((sampled_x - expected_x).abs() < 0.01);
+    }
+}
+
+#[test]
+fn conductor_test() {
+    let white = Conductor::new(Color::new(1.0, 1.0, 1.0), Color::new(1e4, 1e4, 1e4), 0.5);
+    let (kept, _) = check_sampling(&white, &record_at(&white, Point3::default(), true), 2);
+    let kept = kept.get_g();
+    // A perfect reflector only loses the energy of single-scattering masking
+    assert!(kept > 0.85 && kept <= 1.0, "{}", kept);
+    let rougher = Conductor::new(Color::new(1.0, 1.0, 1.0), Color::new(1e4, 1e4, 1e4), 1.0);
+    let (rougher_kept, _) =
+        check_sampling(&rougher, &record_at(&rougher, Point3::default(), true), 2);
+    assert!(rougher_kept.get_g() < kept);
+
+    // Zero roughness is a mirror
+    let mirror = Conductor::from_preset(ConductorPreset::Copper, 0.0);
+    let record = record_at(&mirror, Point3::default(), true);
+    let incoming = Ray::new(Point3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut sampler = SamplerKind::Independent.create(1, 0);
+    sampler.start_pixel_sample(0, 0, 0);
+    let scatter = mirror.scatter(incoming, &record, sampler.as_mut()).unwrap();
+    assert!(scatter.pdf.is_none());
+    assert!((scatter.scattered_ray.get_direction().get_z() - 1.0).abs() < 1e-12);
+    let reflectance = mirror.normal_reflectance();
+    assert!((scatter.attenuation.get_r() - reflectance.get_r()).abs() < 1e-12);
+}
+
+#[test]
+fn rough_dielectric_test() {
+    for is_face_front in [true, false] {
+        let glass = RoughDielectric::new(1.5, 0.4);
+        let record = record_at(&glass, Point3::default(), is_face_front);
+        let (kept, _) = check_sampling(&glass, &record, 2);
+        let kept = kept.get_g();
+        // Light entering the glass is squeezed into a narrower solid angle, so seen from
+        // inside the outside gets brighter by the squared index
+        let limit = match is_face_front {
+            true => 1.0,
+            false => 1.5 * 1.5,
+        };
+        assert!(kept > 0.3 && kept <= limit, "{}", kept);
+
+        // Reflection and refraction together form one density over the sphere
+        let total = integrate_sphere(|direction| glass.pdf(incoming(), &record, direction));
+        assert!(total > 0.9 && total < 1.02, "{}", total);
+        let transmitted = integrate_sphere(|direction| match direction.get_z() < 0.0 {
+            true => glass.pdf(incoming(), &record, direction),
+            false => 0.0,
+        });
+        assert!(transmitted > 0.5);
+    }
+}
+
+#[test]
+fn microfacet_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {
+                "ring": {"type": "conductor", "metal": "gold", "roughness": 0.2},
+                "custom": {"type": "conductor", "eta": [0.2, 0.9, 1.1], "k": [3.9, 2.4, 2.1]},
+                "frosted": {"type": "rough_dielectric", "refractive_index": 1.5, "roughness": 0.3}
+            },
+            "objects": [
+                {"type": "sphere", "center": [0, 0, -1], "radius": 0.5, "material": "ring"},
+                {"type": "sphere", "center": [1, 0, -1], "radius": 0.5, "material": "custom"},
+                {"type": "sphere", "center": [-1, 0, -1], "radius": 0.5, "material": "frosted"}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 3);
+    // A ray meeting the front of the sphere centered at `x` head-on
+    let head_on = |x: f64| Ray::new(Point3::new(x, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let mut sampler = SamplerKind::Independent.create(1, 9);
+    let mut scatter_at = |x: f64, sample_index: i32| {
+        let hit = scene.world.hit(head_on(x), everywhere).unwrap();
+        sampler.start_pixel_sample(0, 0, sample_index);
+        hit.material.scatter(head_on(x), &hit, sampler.as_mut())
+    };
+
+    // Rough gold blurs a yellow reflection
+    let mut gold = Color::default();
+    for sample_index in 0..64 {
+        let Some(scatter) = scatter_at(0.0, sample_index) else {
+            continue;
+        };
+        assert!(scatter.pdf.is_some());
+        gold += scatter.attenuation;
+    }
+    assert!(gold.get_r() > 2.0 * gold.get_b());
+
+    // Without a roughness the custom metal is a mirror with the Fresnel reflectance of its index
+    let mirrored = scatter_at(1.0, 0).unwrap();
+    assert!(mirrored.pdf.is_none());
+    assert!((mirrored.scattered_ray.get_direction().unit_vector().get_z() - 1.0).abs() < 1e-12);
+    for (value, eta, k) in [
+        (mirrored.attenuation.get_r(), 0.2, 3.9),
+        (mirrored.attenuation.get_g(), 0.9, 2.4),
+        (mirrored.attenuation.get_b(), 1.1, 2.1),
+    ] {
+        assert!((value - fresnel_complex(1.0, eta, k)).abs() < 1e-12);
+    }
+
+    // Frosted glass both reflects and lets light in, over a spread of directions
+    let directions: Vec<Vector3> = (0..64)
+        .filter_map(|sample_index| scatter_at(-1.0, sample_index))
+        .map(|scatter| {
+            assert!(scatter.pdf.is_some());
+            scatter.scattered_ray.get_direction().unit_vector()
+        })
+        .collect();
+    assert!(directions.iter().any(|direction| direction.get_z() > 0.0));
+    assert!(directions.iter().any(|direction| direction.get_z() < 0.0));
+    assert!(directions
+        .iter()
+        .any(|direction| direction.get_x().abs() > 0.01));
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"ring": {"type": "conductor", "eta": [0.2, 0.9, 1.1]}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::MissingField));
+    assert_eq!(error.get_path(), "materials.ring.metal");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"ring": {"type": "conductor", "metal": "brass"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.ring.metal");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"frosted": {"type": "rough_dielectric", "refractive_index": 1.5, "roughness": 2}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.frosted.roughness");
+}
@@ -0,0 +1,168 @@
+use std::{path::Path, sync::Arc};
+
+use lib::utilities::{
+    background::Background,
+    bvh::BvhNode,
+    camera::Camera,
+    color::Color,
+    geometry::{Hittable, MovingSphere, Sphere},
+    hit_record::HitRecord,
+    instance::Instance,
+    interval::Interval,
+    material::{DiffuseLight, Lambertian, Material},
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    transform::Transform,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+const EVERYWHERE: Interval = Interval::new(0.001, f64::INFINITY);
+
+fn down_at(x: f64, time: f64) -> Ray {
+    Ray::with_time(Point3::new(x, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), time)
+}
+
+#[test]
+fn ray_time_test() {
+    let ray = Ray::new(Point3::default(), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(ray.get_time(), 0.0);
+    assert_eq!(down_at(0.0, 0.25).get_time(), 0.25);
+
+    // Scattered rays and rays taken into object space keep the time
+    let material = Lambertian::default();
+    let record = HitRecord::new(
+        Point3::default(),
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        true,
+        &material,
+        0.0,
+        0.0,
+    );
+    let mut sampler = SamplerKind::Independent.create(1, 0);
+    sampler.start_pixel_sample(0, 0, 0);
+    let scatter = material
+        .scatter(down_at(0.0, 0.75), &record, sampler.as_mut())
+        .unwrap();
+    assert_eq!(scatter.scattered_ray.get_time(), 0.75);
+    let moved = Transform::translate(Vector3::new(1.0, 0.0, 0.0)).apply_ray(down_at(0.0, 0.5));
+    assert_eq!(moved.get_time(), 0.5);
+}
+
+#[test]
+fn moving_sphere_test() {
+    let sphere = MovingSphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(4.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::default()),
+    );
+    assert!(sphere.hit(down_at(0.0, 0.0), EVERYWHERE).is_some());
+    assert!(sphere.hit(down_at(0.0, 1.0), EVERYWHERE).is_none());
+    assert!(sphere.hit(down_at(4.0, 1.0), EVERYWHERE).is_some());
+    let hit = sphere.hit(down_at(2.0, 0.5), EVERYWHERE).unwrap();
+    assert!((hit.parameter - 4.0).abs() < 1e-12);
+    assert!((hit.normal.get_y() - 1.0).abs() < 1e-12);
+
+    // The box covers the whole motion
+    let bbox = sphere.bounding_box();
+    assert!((bbox.x.min + 1.0).abs() < 1e-12 && (bbox.x.max - 5.0).abs() < 1e-12);
+
+    // so that a BVH finds the sphere at either end
+    let material: Arc<dyn Material> = Arc::new(Lambertian::default());
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(sphere.clone()),
+        Box::new(Sphere::new(
+            Point3::new(-5.0, 0.0, 0.0),
+            1.0,
+            material.clone(),
+        )),
+        Box::new(Sphere::new(Point3::new(9.0, 0.0, 0.0), 1.0, material)),
+    ];
+    let bvh = BvhNode::new(objects);
+    assert!(bvh.hit(down_at(0.0, 0.0), EVERYWHERE).is_some());
+    assert!(bvh.hit(down_at(4.0, 1.0), EVERYWHERE).is_some());
+    assert!(bvh.hit(down_at(4.0, 0.0), EVERYWHERE).is_none());
+
+    // and instances move with it
+    let instance = Instance::new(
+        Arc::new(sphere),
+        Transform::translate(Vector3::new(0.0, 0.0, 10.0)),
+    );
+    let down = Ray::with_time(
+        Point3::new(4.0, 5.0, 10.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        1.0,
+    );
+    assert!(instance.hit(down, EVERYWHERE).is_some());
+}
+
+/// The brightest pixel and the mean of a glowing ball crossing the view
+fn render_moving_lamp(shutter_close: f64) -> (f64, f64) {
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+    let world: Vec<Box<dyn Hittable>> = vec![Box::new(MovingSphere::new(
+        Point3::new(-2.0, 0.0, 0.0),
+        Point3::new(2.0, 0.0, 0.0),
+        0.5,
+        light,
+    ))];
+
+    let mut camera = Camera::new();
+    camera.image_width = 64;
+    camera.image_height = 16;
+    camera.samples_per_pixel = 64;
+    camera.max_depth = 1;
+    camera.look_from = Point3::new(0.0, 0.0, 5.0);
+    camera.look_at = Point3::new(0.0, 0.0, 0.0);
+    camera.vertical_field_of_view = 45.0;
+    camera.background = Background::Solid(Color::default());
+    camera.shutter_close = shutter_close;
+    let image = camera.render(&world, &[]);
+    let values: Vec<f64> = image.pixels().iter().map(|pixel| pixel.get_g()).collect();
+    let brightest = values.iter().cloned().fold(0.0, f64::max);
+    (brightest, values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[test]
+fn motion_blur_render_test() {
+    let (still_brightest, still_mean) = render_moving_lamp(0.0);
+    let (blurred_brightest, blurred_mean) = render_moving_lamp(1.0);
+    // Still, the ball is sharp; moving, it is smeared thin along its path
+    assert!(still_brightest > 0.99);
+    assert!(blurred_brightest < 0.5, "{}", blurred_brightest);
+    assert!((blurred_mean - still_mean).abs() < 0.1 * still_mean);
+}
+
+#[test]
+fn moving_sphere_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "camera": {"shutter": [0, 1]},
+            "objects": [
+                {"type": "moving_sphere", "start": [0, 0, -1], "end": [0, 0.5, -1], "radius": 0.5,
+                 "material": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.camera.shutter_close, 1.0);
+    assert!((scene.world.bounding_box().y.max - 1.0).abs() < 1e-12);
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "camera": {"shutter": [1, 0.5]}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "camera.shutter");
+}
@@ -0,0 +1,231 @@
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    interval::Interval,
+    material::{Material, Principled},
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    texture::{Checker, SolidColor, Texture},
+    vector3::Vector3,
+};
+
+mod common_config;
+mod material_sampling;
+
+use material_sampling::{check_sampling, incoming, record_at};
+
+fn constant(value: f64) -> Arc<dyn Texture> {
+    Arc::new(SolidColor::gray(value))
+}
+
+/// Scatters many times, checking each sample against `eval` and `pdf`.
+/// Returns the mean weight, the fraction of energy the surface keeps.
+fn kept_energy(material: &dyn Material) -> Color {
+    let (kept, specular) =
+        check_sampling(material, &record_at(material, Point3::default(), true), 3);
+    assert_eq!(specular, 0.0);
+    kept
+}
+
+fn presets() -> Vec<(&'static str, Principled)> {
+    let base = Color::new(0.9, 0.6, 0.2);
+    vec![
+        ("plastic", Principled::new(base)),
+        (
+            "brushed metal",
+            Principled::new(base)
+                .with_metallic(constant(1.0))
+                .with_roughness(constant(0.4))
+                .with_anisotropy(constant(0.8)),
+        ),
+        (
+            "glass",
+            Principled::new(base)
+                .with_transmission(constant(1.0))
+                .with_roughness(constant(0.2)),
+        ),
+        (
+            "lacquered cloth",
+            Principled::new(base)
+                .with_sheen(constant(1.0))
+                .with_clearcoat(constant(1.0))
+                .with_roughness(constant(0.9)),
+        ),
+    ]
+}
+
+#[test]
+fn principled_sampling_test() {
+    for (name, material) in presets() {
+        let kept = kept_energy(&material);
+        assert!(kept.get_r() > 0.2 && kept.get_r() < 1.1, "{}", name);
+
+        // The lobes together form one density, short of the rough reflections that
+        // would leave below the surface
+        let record = record_at(&material, Point3::default(), true);
+        // A midpoint grid rather than random directions, as the glass lobe is narrow
+        let steps = 600;
+        let total: f64 = (0..steps * steps)
+            .map(|index| {
+                let direction = Vector3::sample_unit_sphere(
+                    ((index / steps) as f64 + 0.5) / steps as f64,
+                    ((index % steps) as f64 + 0.5) / steps as f64,
+                );
+                material.pdf(incoming(), &record, direction)
+            })
+            .sum::<f64>()
+            * 4.0
+            * PI
+            / (steps * steps) as f64;
+        assert!(total > 0.75 && total < 1.03, "{}: {}", name, total);
+    }
+}
+
+#[test]
+fn principled_parameters_test() {
+    // A metal is tinted by the base color, a plastic highlight is not
+    let red = Color::new(0.9, 0.1, 0.1);
+    let metal = Principled::new(red)
+        .with_metallic(constant(1.0))
+        .with_roughness(constant(0.3));
+    let kept = kept_energy(&metal);
+    assert!(kept.get_r() > 0.8 && kept.get_g() < 0.4);
+
+    let record = record_at(&metal, Point3::default(), true);
+    let mirror_direction = Vector3::new(0.6, 0.0, 0.8);
+    let plastic = Principled::new(red).with_roughness(constant(0.3));
+    let highlight = plastic.eval(incoming(), &record, mirror_direction);
+    let diffuse_only = Principled::new(red)
+        .with_roughness(constant(0.3))
+        .with_specular(constant(0.0))
+        .eval(incoming(), &record, mirror_direction);
+    let gloss = highlight.get_g() - diffuse_only.get_g();
+    assert!(gloss > 0.1 && (highlight.get_r() - diffuse_only.get_r() - gloss).abs() < 1e-9);
+
+    // Glass lets light through, tinted by the base color
+    let glass = Principled::new(Color::new(1.0, 0.5, 0.5)).with_transmission(constant(1.0));
+    let through = Vector3::new(0.3, 0.0, -0.9);
+    let transmitted = glass.eval(incoming(), &record, through);
+    assert!(transmitted.get_r() > 0.0);
+    assert!((transmitted.get_g() - 0.5 * transmitted.get_r()).abs() < 1e-9);
+    assert_eq!(plastic.eval(incoming(), &record, through).get_r(), 0.0);
+
+    // Parameters follow their textures
+    let patches: Arc<dyn Texture> = Arc::new(Checker::new(1.0, constant(0.0), constant(1.0)));
+    let patchy = Principled::new(red)
+        .with_metallic(patches)
+        .with_roughness(constant(0.2));
+    let off_specular = Vector3::new(-0.5, 0.0, 0.8);
+    let dielectric_patch = record_at(&patchy, Point3::new(0.5, 0.5, 0.5), true);
+    let metal_patch = record_at(&patchy, Point3::new(1.5, 0.5, 0.5), true);
+    let diffuse = patchy.eval(incoming(), &dielectric_patch, off_specular);
+    let dark = patchy.eval(incoming(), &metal_patch, off_specular);
+    assert!(diffuse.get_r() > 0.1 && dark.get_r() < 0.01);
+}
+
+#[test]
+fn principled_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {
+                "car_paint": {"type": "principled", "base_color": [0.6, 0.05, 0.05], "metallic": 0.3,
+                    "roughness": 0.4, "clearcoat": 1},
+                "floor": {"type": "principled", "base_color": {"type": "noise", "color": [0.5, 0.4, 0.3]},
+                    "roughness": {"type": "checker", "scale": 0.5, "even": [0.1, 0.1, 0.1], "odd": [0.9, 0.9, 0.9]}},
+                "bottle": {"type": "principled", "transmission": 1, "roughness": 0, "refractive_index": 1.45},
+                "steel": {"type": "conductor", "metal": "aluminium", "roughness": 0.3}
+            },
+            "objects": [
+                {"type": "sphere", "center": [0, 0, -1], "radius": 0.5, "material": "car_paint"},
+                {"type": "plane", "point": [0, -0.5, 0], "normal": [0, 1, 0], "material": "floor"},
+                {"type": "sphere", "center": [1, 0, -1], "radius": 0.5, "material": "bottle"},
+                {"type": "sphere", "center": [-1, 0, -1], "radius": 0.5, "material": "steel"}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 4);
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let head_on = |x: f64| Ray::new(Point3::new(x, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut sampler = SamplerKind::Independent.create(1, 2);
+
+    // Clear glass lets nearly all light in
+    let hit = scene.world.hit(head_on(1.0), everywhere).unwrap();
+    let entering = (0..100)
+        .filter(|sample_index| {
+            sampler.start_pixel_sample(0, 0, *sample_index);
+            let scatter = hit
+                .material
+                .scatter(head_on(1.0), &hit, sampler.as_mut())
+                .unwrap();
+            scatter.scattered_ray.get_direction().get_z() < 0.0
+        })
+        .count();
+    assert!(entering > 90, "{}", entering);
+
+    // The conductor keeps its preset: a bright, nearly white metal
+    let hit = scene.world.hit(head_on(-1.0), everywhere).unwrap();
+    sampler.start_pixel_sample(0, 0, 0);
+    let steel = hit
+        .material
+        .scatter(head_on(-1.0), &hit, sampler.as_mut())
+        .unwrap()
+        .attenuation;
+    assert!(steel.get_r() > 0.7 && (steel.get_r() - steel.get_b()).abs() < 0.05);
+
+    // The car paint is mostly a red dielectric away from its highlight
+    let hit = scene.world.hit(head_on(0.0), everywhere).unwrap();
+    let diffuse = hit
+        .material
+        .eval(head_on(0.0), &hit, Vector3::new(0.6, 0.0, 0.8));
+    assert!(diffuse.get_r() > 5.0 * diffuse.get_g());
+
+    // The floor's roughness follows the checker: sharp highlights on even squares
+    let highlight = |x: f64| {
+        let down = Ray::new(Point3::new(x, 1.0, 0.4), Vector3::new(0.1, -1.0, 0.0));
+        let hit = scene.world.hit(down, everywhere).unwrap();
+        hit.material
+            .eval(down, &hit, Vector3::new(0.1, 1.0, 0.0))
+            .get_g()
+    };
+    assert!(highlight(2.7) > 3.0 * highlight(2.3));
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"paint": {"type": "principled", "metallic": 1.5}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.paint.metallic");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"paint": {"type": "principled", "roughness": "high"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::WrongType));
+    assert_eq!(error.get_path(), "materials.paint.roughness");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1,
+            "material": {"type": "principled", "sheen": {"type": "solid", "color": [2, 0, 0]}}}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "objects[0].material.sheen.color");
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    image::Image,
+    random::{self, Pcg32},
+    scenes,
+};
+
+mod common_config;
+
+const SCENE: &str = r#"{
+    "version": 1,
+    "camera": {"look_from": [13, 2, 3], "look_at": [0, 0, 0], "vertical_fov": 30, "defocus_angle": 0.6},
+    "image": {"width": 24, "height": 16},
+    "render": {"samples_per_pixel": 4, "max_depth": 8, "seed": 7},
+    "objects": [{"type": "random_spheres", "grid": 2, "seed": 3}]
+}"#;
+
+fn render_with_threads(json: &str, threads: usize) -> Image {
+    let scene = scenes::scene_from_str(json, Path::new(""), false).unwrap();
+    let mut camera = scene.camera;
+    let world = scene.world;
+    let lights = scene.lights;
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+        .install(|| camera.render(&world, &lights))
+}
+
+fn color_bits(color: Color) -> [u64; 3] {
+    [
+        color.get_r().to_bits(),
+        color.get_g().to_bits(),
+        color.get_b().to_bits(),
+    ]
+}
+
+#[test]
+fn pcg32_reference_output_test() {
+    // First outputs of the reference pcg32-demo for seed 42, stream 54
+    let mut generator = Pcg32::new(42, 54);
+    let outputs: Vec<u32> = (0..6).map(|_| generator.next_u32()).collect();
+    assert_eq!(
+        outputs,
+        [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+    );
+}
+
+#[test]
+fn seeded_stream_test() {
+    let mut generator = Pcg32::from_seed(11);
+    let first: Vec<f64> = (0..4).map(|_| generator.next_f64()).collect();
+    let mut generator = Pcg32::from_seed(11);
+    let second: Vec<f64> = (0..4).map(|_| generator.next_f64()).collect();
+    assert_eq!(first, second);
+    assert!(first.iter().all(|value| (0.0..1.0).contains(value)));
+    assert_ne!(random::hash(&[1, 2]), random::hash(&[2, 1]));
+}
+
+#[test]
+fn render_independent_of_thread_count_test() {
+    for sampler in ["independent", "stratified", "halton", "sobol"] {
+        let scene = SCENE.replace(
+            r#""seed": 7"#,
+            &format!(r#""seed": 7, "sampler": "{}""#, sampler),
+        );
+        let single = render_with_threads(&scene, 1);
+        let many = render_with_threads(&scene, 4);
+        let single_bits: Vec<[u64; 3]> = single.pixels().iter().map(|c| color_bits(*c)).collect();
+        let many_bits: Vec<[u64; 3]> = many.pixels().iter().map(|c| color_bits(*c)).collect();
+        assert!(single_bits == many_bits, "{} sampler", sampler);
+
+        let reseeded = render_with_threads(&scene.replace(r#""seed": 7"#, r#""seed": 8"#), 4);
+        let reseeded_bits: Vec<[u64; 3]> =
+            reseeded.pixels().iter().map(|c| color_bits(*c)).collect();
+        assert!(single_bits != reseeded_bits, "{} sampler", sampler);
+    }
+}
+
+#[test]
+fn random_spheres_seed_test() {
+    let sphere_bounds = |seed: u64| {
+        let mut world: Vec<Box<dyn Hittable>> = Vec::new();
+        scenes::generate_scene(&mut world, 2, seed);
+        world
+            .iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                [bbox.x.min, bbox.y.min, bbox.z.min]
+            })
+            .collect::<Vec<[f64; 3]>>()
+    };
+    assert_eq!(sphere_bounds(5), sphere_bounds(5));
+    assert_ne!(sphere_bounds(5), sphere_bounds(6));
+}
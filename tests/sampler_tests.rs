@@ -0,0 +1,98 @@
+use lib::utilities::sampler::{Sampler, SamplerKind};
+
+mod common_config;
+
+const KINDS: [SamplerKind; 4] = [
+    SamplerKind::Independent,
+    SamplerKind::Stratified,
+    SamplerKind::Halton,
+    SamplerKind::Sobol,
+];
+
+/// The first `dimensions` values of every sample of pixel (`x`, `y`)
+fn pixel_values(
+    sampler: &mut dyn Sampler,
+    x: i32,
+    y: i32,
+    samples: i32,
+    dimensions: usize,
+) -> Vec<Vec<f64>> {
+    (0..samples)
+        .map(|sample_index| {
+            sampler.start_pixel_sample(x, y, sample_index);
+            (0..dimensions).map(|_| sampler.get_1d()).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn values_in_unit_interval_test() {
+    for kind in KINDS {
+        let mut sampler = kind.create(16, 1);
+        for values in pixel_values(sampler.as_mut(), 3, 5, 16, 40) {
+            assert!(values.iter().all(|value| (0.0..1.0).contains(value)));
+        }
+        sampler.start_pixel_sample(3, 5, 0);
+        let (u, v) = sampler.get_2d();
+        assert!((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v));
+    }
+}
+
+#[test]
+fn deterministic_test() {
+    for kind in KINDS {
+        let first = pixel_values(kind.create(8, 4).as_mut(), 10, 2, 8, 6);
+        let second = pixel_values(kind.create(8, 4).as_mut(), 10, 2, 8, 6);
+        assert_eq!(first, second, "{:?}", kind);
+
+        let reseeded = pixel_values(kind.create(8, 5).as_mut(), 10, 2, 8, 6);
+        assert_ne!(first, reseeded, "{:?}", kind);
+        let other_pixel = pixel_values(kind.create(8, 4).as_mut(), 11, 2, 8, 6);
+        assert_ne!(first, other_pixel, "{:?}", kind);
+    }
+}
+
+#[test]
+fn stratified_1d_test() {
+    // With 16 samples every dimension puts exactly one value in each sixteenth of [0, 1)
+    for kind in [SamplerKind::Stratified, SamplerKind::Sobol] {
+        let values = pixel_values(kind.create(16, 9).as_mut(), 7, 1, 16, 5);
+        for dimension in 0..5 {
+            let mut strata: Vec<usize> = values
+                .iter()
+                .map(|sample| (sample[dimension] * 16.0) as usize)
+                .collect();
+            strata.sort();
+            assert_eq!(strata, (0..16).collect::<Vec<usize>>(), "{:?}", kind);
+        }
+    }
+}
+
+#[test]
+fn stratified_2d_test() {
+    // 16 Sobol points fill each cell of a 4 by 4 grid once
+    let mut sampler = SamplerKind::Sobol.create(16, 2);
+    let mut cells: Vec<usize> = (0..16)
+        .map(|sample_index| {
+            sampler.start_pixel_sample(0, 0, sample_index);
+            let (u, v) = sampler.get_2d();
+            (u * 4.0) as usize + 4 * (v * 4.0) as usize
+        })
+        .collect();
+    cells.sort();
+    assert_eq!(cells, (0..16).collect::<Vec<usize>>());
+}
+
+#[test]
+fn halton_mean_test() {
+    let values = pixel_values(SamplerKind::Halton.create(64, 3).as_mut(), 1, 1, 64, 4);
+    for dimension in 0..4 {
+        let mean: f64 = values.iter().map(|sample| sample[dimension]).sum::<f64>() / 64.0;
+        assert!(
+            (mean - 0.5).abs() < 0.05,
+            "dimension {}: {}",
+            dimension,
+            mean
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use lib::utilities::{
+    geometry::Hittable,
+    interval::Interval,
+    point::Point3,
+    ray::Ray,
+    scene_error::{SceneError, SceneErrorKind},
+    scenes,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+const SCENE: &str = r#"{
+    "version": 1,
+    "camera": {"look_from": [0, 0, 5], "look_at": [0, 0, 0], "vertical_fov": 30},
+    "image": {"width": 64, "height": 32},
+    "render": {"samples_per_pixel": 4, "max_depth": 8},
+    "background": {"type": "solid", "color": [0.1, 0.2, 0.3]},
+    "materials": {
+        "red": {"type": "lambertian", "albedo": [0.8, 0.1, 0.1]}
+    },
+    "objects": [
+        {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red"},
+        {"type": "sphere", "center": [3, 0, 0], "radius": 0.5,
+         "material": {"type": "metal", "albedo": [0.9, 0.9, 0.9], "fuzz": 0.1}},
+        {"type": "triangle", "vertices": [[0, 0, -3], [1, 0, -3], [0, 1, -3]], "material": "red"}
+    ]
+}"#;
+
+#[test]
+fn parse_and_build_scene_test() {
+    let scene = scenes::scene_from_str(SCENE, Path::new(""), true).unwrap();
+    assert_eq!(scene.world.len(), 3);
+    assert_eq!(scene.camera.image_width, 64);
+    assert_eq!(scene.camera.image_height, 32);
+    assert_eq!(scene.camera.samples_per_pixel, 4);
+    assert_eq!(scene.camera.max_depth, 8);
+    assert_eq!(scene.camera.vertical_field_of_view, 30.0);
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let record = scene
+        .world
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .unwrap();
+    assert!((record.parameter - 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn scene_defaults_test() {
+    let scene = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "random_spheres", "grid": 2}]}"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    // Ground, a 4 by 4 grid of small spheres and four big spheres
+    assert_eq!(scene.world.len(), 1 + 16 + 4);
+    assert_eq!(scene.camera.max_depth, 50);
+}
+
+fn load_error(json: &str, strict: bool) -> SceneError {
+    match scenes::scene_from_str(json, Path::new(""), strict) {
+        Ok(_) => panic!("scene should not load"),
+        Err(e) => e,
+    }
+}
+
+#[test]
+fn scene_error_kind_test() {
+    let error = load_error(
+        r#"{"version": 1, "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": "big", "material": "red"}]}"#,
+        false,
+    );
+    assert!(matches!(error.get_kind(), SceneErrorKind::WrongType));
+    assert_eq!(error.get_path(), "objects[0].radius");
+
+    let error = load_error(
+        r#"{"version": 1, "objects": [{"type": "sphere", "center": [0, 0, 0], "material": "red"}]}"#,
+        false,
+    );
+    assert!(matches!(error.get_kind(), SceneErrorKind::MissingField));
+    assert_eq!(error.get_path(), "objects[0].radius");
+
+    let error = load_error(
+        r#"{"version": 1, "objects": [
+            {"type": "random_spheres", "grid": 1},
+            {"type": "sphere", "center": [0, 0, 0], "radius": 1,
+             "material": {"type": "metal", "albedo": [0.5, 0.5, 0.5], "fuzz": 2}}]}"#,
+        false,
+    );
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "objects[1].material.fuzz");
+
+    let error = load_error(
+        r#"{"version": 1, "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red"}]}"#,
+        false,
+    );
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownMaterial));
+    assert_eq!(error.get_path(), "objects[0].material");
+
+    let error = load_error(r#"{"version": 1, "objects": [{"type": "cube"}]}"#, false);
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownType));
+    assert_eq!(error.get_path(), "objects[0].type");
+
+    let error = load_error(r#"{"version": 2}"#, false);
+    assert!(matches!(
+        error.get_kind(),
+        SceneErrorKind::UnsupportedVersion
+    ));
+
+    let error = load_error("{\"version\": 1,\n \"objects\": [}", false);
+    assert!(matches!(
+        error.get_kind(),
+        SceneErrorKind::Syntax { line: 2, .. }
+    ));
+}
+
+#[test]
+fn strict_scene_test() {
+    let json = r#"{"version": 1, "render": {"samples": 4},
+        "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1,
+                     "material": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}}]}"#;
+    assert!(scenes::scene_from_str(json, Path::new(""), false).is_ok());
+
+    let error = load_error(json, true);
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownField));
+    assert_eq!(error.get_path(), "render.samples");
+}
+
+#[test]
+fn scene_file_error_test() {
+    let dir = common_config::TestDir::new("scene_file_error_test");
+    let path = dir.join("missing_scene.json");
+    let error = match scenes::load_scene(&path, false) {
+        Ok(_) => panic!("scene should not load"),
+        Err(e) => e,
+    };
+    assert!(matches!(error.get_kind(), SceneErrorKind::Io(_)));
+    assert_eq!(error.get_file(), Some(path.as_path()));
+    assert!(error.to_string().contains("missing_scene.json"));
+}
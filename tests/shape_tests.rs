@@ -0,0 +1,210 @@
+use std::{path::Path, sync::Arc};
+
+use lib::utilities::{
+    bvh::BvhNode,
+    geometry::{Cuboid, Disk, Hittable, Plane, Quad, Sphere},
+    interval::Interval,
+    material::Lambertian,
+    point::Point3,
+    ray::Ray,
+    scene_error::SceneErrorKind,
+    scenes,
+    vector3::{Cross, Vector3},
+};
+
+mod common_config;
+
+const EVERYWHERE: Interval = Interval::new(0.001, f64::INFINITY);
+
+fn down_from(x: f64, z: f64) -> Ray {
+    Ray::new(Point3::new(x, 5.0, z), Vector3::new(0.0, -1.0, 0.0))
+}
+
+#[test]
+fn plane_hit_test() {
+    let plane = Plane::new(
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+        Arc::new(Lambertian::default()),
+    );
+    let hit = plane.hit(down_from(3.0, -2.0), EVERYWHERE).unwrap();
+    assert!((hit.parameter - 4.0).abs() < 1e-12);
+    assert!(hit.is_face_front);
+    // Surface coordinates measure distances in the plane
+    assert!(((hit.u * hit.u) + (hit.v * hit.v) - 13.0).abs() < 1e-9);
+
+    let parallel = Ray::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert!(plane.hit(parallel, EVERYWHERE).is_none());
+
+    let bbox = plane.bounding_box();
+    assert!(!bbox.is_bounded());
+    assert!(bbox.y.size() < 0.01);
+}
+
+#[test]
+fn bvh_with_plane_test() {
+    let material = Arc::new(Lambertian::default());
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Plane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 1.0),
+            material.clone(),
+        )),
+        Box::new(Cuboid::new(
+            Point3::new(-1.0, 1.0, -1.0),
+            Point3::new(1.0, 2.0, 1.0),
+            material,
+        )),
+    ];
+    let bvh = BvhNode::new(objects);
+
+    // Hits the box first, and the tilted plane far away from the box
+    let hit = bvh.hit(down_from(0.0, 0.0), EVERYWHERE).unwrap();
+    assert!((hit.parameter - 3.0).abs() < 1e-9);
+    let hit = bvh.hit(down_from(100.0, 100.0), EVERYWHERE).unwrap();
+    assert!((hit.parameter - 105.0).abs() < 1e-9);
+}
+
+#[test]
+fn disk_hit_test() {
+    let disk = Disk::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        2.0,
+        Arc::new(Lambertian::default()),
+    );
+    let hit = disk.hit(down_from(1.0, 0.0), EVERYWHERE).unwrap();
+    assert!((hit.v - 0.5).abs() < 1e-12);
+    assert!((0.0..1.0).contains(&hit.u));
+    assert!(disk.hit(down_from(1.5, 1.5), EVERYWHERE).is_none());
+
+    let bbox = disk.bounding_box();
+    assert!((bbox.x.max - 2.0).abs() < 1e-12 && (bbox.z.min + 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn flat_shapes_grazing_ray_test() {
+    // The three flat primitives agree on when a ray is too close to parallel to hit them
+    let size = 1e10;
+    let shapes: [Box<dyn Hittable>; 3] = [
+        Box::new(Plane::new(
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            Arc::new(Lambertian::default()),
+        )),
+        Box::new(Disk::new(
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            size,
+            Arc::new(Lambertian::default()),
+        )),
+        Box::new(Quad::new(
+            Point3::new(-size, 0.0, -size),
+            Vector3::new(0.0, 0.0, 2.0 * size),
+            Vector3::new(2.0 * size, 0.0, 0.0),
+            Arc::new(Lambertian::default()),
+        )),
+    ];
+    let grazing = |drop: f64| Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(1.0, -drop, 0.0));
+    for shape in &shapes {
+        assert!(shape.hit(grazing(1e-7), EVERYWHERE).is_some());
+        assert!(shape.hit(grazing(1e-9), EVERYWHERE).is_none());
+    }
+}
+
+#[test]
+fn cuboid_normals_test() {
+    let cuboid = Cuboid::new(
+        Point3::new(1.0, 1.0, 1.0),
+        Point3::new(-1.0, -1.0, -1.0),
+        Arc::new(Lambertian::default()),
+    );
+    for axis in 0..3 {
+        for sign in [-1.0, 1.0] {
+            let mut origin = [0.2, 0.3, 0.1];
+            origin[axis] = 5.0 * sign;
+            let mut direction = [0.0; 3];
+            direction[axis] = -sign;
+            let ray = Ray::new(
+                Point3::new(origin[0], origin[1], origin[2]),
+                Vector3::new(direction[0], direction[1], direction[2]),
+            );
+            let hit = cuboid.hit(ray, EVERYWHERE).unwrap();
+            assert!((hit.parameter - 4.0).abs() < 1e-12);
+            assert!(hit.is_face_front);
+            assert!((hit.normal.get_axis(axis) - sign).abs() < 1e-12);
+            assert!((0.0..=1.0).contains(&hit.u) && (0.0..=1.0).contains(&hit.v));
+        }
+    }
+}
+
+#[test]
+fn flat_shapes_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {"lamp": {"type": "diffuse_light", "emit": [1, 1, 1]}},
+            "objects": [
+                {"type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0], "material": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}},
+                {"type": "quad", "corner": [-1, 3, -1], "u": [2, 0, 0], "v": [0, 0, 2], "material": "lamp"},
+                {"type": "disk", "center": [0, 1, 0], "normal": [0, 0, 1], "radius": 0.5, "material": "lamp"},
+                {"type": "box", "min": [2, 0, 2], "max": [3, 1, 3], "material": {"type": "metal", "albedo": [0.8, 0.8, 0.8], "fuzz": 0}}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 4);
+    assert_eq!(scene.lights.len(), 2);
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "quad", "corner": [0, 0, 0], "u": [1, 0, 0], "v": [2, 0, 0], "material": {"type": "lambertian", "albedo": [1, 1, 1]}}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "objects[0].v");
+}
+
+#[test]
+fn sphere_uv_test() {
+    let sphere = Sphere::new(
+        Point3::new(1.0, 2.0, 3.0),
+        2.0,
+        Arc::new(Lambertian::default()),
+    );
+    let towards_center = |direction: Vector3| {
+        let origin = Point3::new(1.0, 2.0, 3.0) + (direction * 5.0);
+        sphere
+            .hit(Ray::new(origin, -direction), EVERYWHERE)
+            .unwrap()
+    };
+    for (direction, u, v) in [
+        (Vector3::new(1.0, 0.0, 0.0), 0.5, 0.5),
+        (Vector3::new(0.0, 0.0, 1.0), 0.25, 0.5),
+        (Vector3::new(0.0, 0.0, -1.0), 0.75, 0.5),
+        (Vector3::new(0.0, 1.0, 0.0), 0.5, 1.0),
+        (Vector3::new(0.0, -1.0, 0.0), 0.5, 0.0),
+    ] {
+        let hit = towards_center(direction);
+        assert!((hit.u - u).abs() < 1e-9 && (hit.v - v).abs() < 1e-9);
+        // Tangents lie in the surface
+        assert!(hit.dpdu.dot_prod(hit.normal).abs() < 1e-9);
+        assert!(hit.dpdv.dot_prod(hit.normal).abs() < 1e-9);
+        assert!(!hit.dpdu.near_zero() && !hit.dpdv.near_zero());
+    }
+
+    // Away from the poles, the tangents predict where nearby coordinates land
+    let direction = Vector3::new(0.3, 0.5, -0.8).unit_vector();
+    let nearby = (direction + Vector3::new(0.002, -0.001, 0.003)).unit_vector();
+    let (hit, nearby_hit) = (towards_center(direction), towards_center(nearby));
+    let predicted =
+        hit.point + (hit.dpdu * (nearby_hit.u - hit.u)) + (hit.dpdv * (nearby_hit.v - hit.v));
+    let step = (nearby_hit.point - hit.point).as_vec().length();
+    assert!((nearby_hit.point - predicted).as_vec().length() < 0.01 * step);
+    // and they face the way the outward normal does
+    assert!(hit.dpdu.cross_prod(hit.dpdv).dot_prod(hit.normal) > 0.0);
+}
@@ -0,0 +1,270 @@
+use std::{path::Path, sync::Arc};
+
+use lib::utilities::{
+    background::Background,
+    camera::Camera,
+    color::Color,
+    geometry::{Hittable, Quad, Sphere},
+    hit_record::HitRecord,
+    interval::Interval,
+    material::{Dielectric, DiffuseLight, Lambertian, Material},
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    spectrum::{
+        Dispersion, SampledSpectrum, SampledWavelengths, Xyz, D_LINE, LAMBDA_MAX, LAMBDA_MIN,
+        SPECTRUM_SAMPLES,
+    },
+    vector3::{Cross, Vector3},
+};
+
+mod common_config;
+
+const BK7_CAUCHY: Dispersion = Dispersion::Cauchy {
+    a: 1.5046,
+    b: 0.00420,
+};
+const BK7_SELLMEIER: Dispersion = Dispersion::Sellmeier {
+    b: [1.03961212, 0.231792344, 1.01046945],
+    c: [0.00600069867, 0.0200179144, 103.560653],
+};
+
+/// The RGB color of `color` lifted to a spectrum, averaged over evenly spread hero
+/// wavelengths
+fn round_trip(color: Color) -> Color {
+    let count = 1000;
+    let mut xyz = Xyz::default();
+    for index in 0..count {
+        let wavelengths = SampledWavelengths::sample_uniform((index as f64 + 0.5) / count as f64);
+        xyz += SampledSpectrum::from_rgb(color, &wavelengths).to_xyz(&wavelengths);
+    }
+    (xyz * (1.0 / count as f64)).to_rgb()
+}
+
+#[test]
+fn sampled_wavelengths_test() {
+    for sample in [0.0, 0.3, 0.99] {
+        let wavelengths = SampledWavelengths::sample_uniform(sample);
+        let lambda = wavelengths.get();
+        assert_eq!(lambda[0], wavelengths.hero());
+        assert!(lambda
+            .iter()
+            .all(|lambda| (LAMBDA_MIN..=LAMBDA_MAX).contains(lambda)));
+        // Evenly spread, wrapping around the range
+        let mut sorted = lambda;
+        sorted.sort_by(f64::total_cmp);
+        let spacing = (LAMBDA_MAX - LAMBDA_MIN) / SPECTRUM_SAMPLES as f64;
+        for pair in sorted.windows(2) {
+            assert!((pair[1] - pair[0] - spacing).abs() < 1e-9);
+        }
+    }
+
+    // Only the hero survives a dispersive bounce, carrying the weight of all of them
+    let spectrum = SampledSpectrum::new([0.5, 0.2, 0.3, 0.4]).terminate_secondary();
+    assert_eq!(spectrum.get(), [2.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn rgb_round_trip_test() {
+    // White reflects every wavelength and comes back white
+    let wavelengths = SampledWavelengths::sample_uniform(0.42);
+    let white = SampledSpectrum::from_rgb(Color::new(1.0, 1.0, 1.0), &wavelengths);
+    assert!(white.get().iter().all(|value| (value - 1.0).abs() < 1e-12));
+    let gray = round_trip(Color::new(0.5, 0.5, 0.5));
+    for channel in [gray.get_r(), gray.get_g(), gray.get_b()] {
+        assert!((channel - 0.5).abs() < 0.01, "{}", channel);
+    }
+
+    // Saturated colors keep their dominant primary
+    let red = round_trip(Color::new(0.9, 0.1, 0.1));
+    assert!(red.get_r() > 0.6 && red.get_g() < 0.3 && red.get_b() < 0.3);
+    let blue = round_trip(Color::new(0.1, 0.1, 0.9));
+    assert!(blue.get_b() > 0.6 && blue.get_r() < 0.3);
+}
+
+#[test]
+fn dispersion_test() {
+    // Both fits of BK7 agree on the index quoted at the d line
+    for dispersion in [BK7_CAUCHY, BK7_SELLMEIER] {
+        assert!((dispersion.refractive_index(D_LINE) - 1.5168).abs() < 2e-3);
+        assert!(dispersion.refractive_index(450.0) > dispersion.refractive_index(650.0));
+    }
+    let glass = Dielectric::from_dispersion(BK7_SELLMEIER);
+    assert!(glass.is_dispersive());
+    assert!(!Dielectric::new(1.5).is_dispersive());
+
+    // Blue light is bent closer to the normal than red light
+    let record = HitRecord::new(
+        Point3::default(),
+        Vector3::new(0.0, 0.0, 1.0),
+        1.0,
+        true,
+        &glass,
+        0.0,
+        0.0,
+    );
+    let incoming = Ray::new(Point3::new(-1.0, 0.0, 1.0), Vector3::new(0.8, 0.0, -0.6));
+    let refracted_x = |wavelength: Option<f64>| {
+        let mut sampler = SamplerKind::Independent.create(1, 9);
+        (0..100)
+            .find_map(|sample_index| {
+                sampler.start_pixel_sample(0, 0, sample_index);
+                let scatter = glass
+                    .scatter(
+                        incoming.with_wavelength(wavelength),
+                        &record,
+                        sampler.as_mut(),
+                    )
+                    .unwrap();
+                let direction = scatter.scattered_ray.get_direction().unit_vector();
+                (direction.get_z() < 0.0).then_some(direction.get_x())
+            })
+            .unwrap()
+    };
+    let blue = refracted_x(Some(450.0));
+    let red = refracted_x(Some(650.0));
+    let rgb = refracted_x(None);
+    assert!(blue < red, "{} vs {}", blue, red);
+    assert!(blue < rgb && rgb < red);
+}
+
+/// A gray floor lit by a sphere light, in a black void
+fn render_lit_floor(spectral: bool) -> f64 {
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
+    let lamp = Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.7, light);
+    let world: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Quad::new(
+            Point3::new(-5.0, 0.0, -5.0),
+            Vector3::new(0.0, 0.0, 10.0),
+            Vector3::new(10.0, 0.0, 0.0),
+            Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+        )),
+        Box::new(lamp.clone()),
+    ];
+    let lights: Vec<Box<dyn Hittable>> = vec![Box::new(lamp)];
+
+    let mut camera = Camera::new();
+    camera.image_width = 24;
+    camera.image_height = 16;
+    camera.samples_per_pixel = 128;
+    camera.max_depth = 4;
+    camera.look_from = Point3::new(0.0, 3.0, 6.0);
+    camera.look_at = Point3::new(0.0, 0.0, 0.0);
+    camera.background = Background::Solid(Color::default());
+    camera.spectral = spectral;
+    let image = camera.render(&world, &lights);
+    let total: f64 = image.pixels().iter().map(|pixel| pixel.get_g()).sum();
+    total / image.pixels().len() as f64
+}
+
+#[test]
+fn spectral_render_test() {
+    // Without dispersion, a gray scene looks the same traced in RGB or in wavelengths
+    let spectral = render_lit_floor(true);
+    let rgb = render_lit_floor(false);
+    assert!(
+        (spectral - rgb).abs() < 0.05 * rgb,
+        "{} vs {}",
+        spectral,
+        rgb
+    );
+}
+
+#[test]
+fn spectral_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "render": {"spectral": true},
+            "materials": {
+                "prism": {"type": "dielectric", "dispersion": {"type": "sellmeier",
+                    "b": [1.03961212, 0.231792344, 1.01046945],
+                    "c": [0.00600069867, 0.0200179144, 103.560653]}},
+                "flint": {"type": "dielectric", "refractive_index": 1.62,
+                    "dispersion": {"type": "cauchy", "a": 1.5814, "b": 0.01358}}
+            },
+            "objects": [
+                {"type": "sphere", "center": [0, 0, -1], "radius": 0.5, "material": "prism"},
+                {"type": "sphere", "center": [1, 0, -1], "radius": 0.5, "material": "flint"}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert!(scene.camera.spectral);
+    assert_eq!(scene.world.len(), 2);
+    let flint = Dispersion::Cauchy {
+        a: 1.5814,
+        b: 0.01358,
+    };
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let mut sampler = SamplerKind::Independent.create(1, 6);
+
+    // Each glass bends a wavelength by its own index, and RGB rays by the nominal one
+    for (x, dispersion, nominal) in [
+        (0.3, BK7_SELLMEIER, BK7_SELLMEIER.refractive_index(D_LINE)),
+        (1.3, flint, 1.62),
+    ] {
+        let inwards = Ray::new(Point3::new(x, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(inwards, everywhere).unwrap();
+        assert!(hit.material.is_dispersive());
+        for wavelength in [None, Some(450.0), Some(650.0)] {
+            let refractive_index = match wavelength {
+                Some(wavelength) => dispersion.refractive_index(wavelength),
+                None => nominal,
+            };
+            let mut refracted = 0;
+            for sample_index in 0..32 {
+                sampler.start_pixel_sample(0, 0, sample_index);
+                let scatter = hit
+                    .material
+                    .scatter(inwards.with_wavelength(wavelength), &hit, sampler.as_mut())
+                    .unwrap();
+                let direction = scatter.scattered_ray.get_direction().unit_vector();
+                if direction.dot_prod(hit.normal) < 0.0 {
+                    let sin_theta = direction.cross_prod(hit.normal).length();
+                    assert!((sin_theta - 0.6 / refractive_index).abs() < 1e-9);
+                    refracted += 1;
+                }
+            }
+            assert!(refracted > 0);
+        }
+    }
+    assert!(BK7_SELLMEIER.refractive_index(450.0) > BK7_SELLMEIER.refractive_index(650.0));
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"prism": {"type": "dielectric"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::MissingField));
+    assert_eq!(error.get_path(), "materials.prism.refractive_index");
+
+    // A Sellmeier pole inside the visible range
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"prism": {"type": "dielectric",
+            "dispersion": {"type": "sellmeier", "b": [1, 0, 0], "c": [0.2, 0, 0]}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.prism.dispersion");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"prism": {"type": "dielectric",
+            "dispersion": {"type": "abbe", "number": 64}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownType));
+    assert_eq!(error.get_path(), "materials.prism.dispersion.type");
+}
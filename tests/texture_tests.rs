@@ -0,0 +1,282 @@
+use std::{path::Path, sync::Arc};
+
+use lib::utilities::{
+    color::Color,
+    geometry::Hittable,
+    hit_record::HitRecord,
+    image::{Image, ImageFormat},
+    interval::Interval,
+    material::{Lambertian, Material},
+    point::Point3,
+    ray::Ray,
+    sampler::SamplerKind,
+    scene_error::SceneErrorKind,
+    scenes,
+    texture::{Checker, ImageTexture, NoiseStyle, NoiseTexture, Perlin, Texture, WrapMode},
+    vector3::Vector3,
+};
+
+mod common_config;
+
+fn assert_color(color: Color, expected: Color) {
+    let difference = (color.get_r() - expected.get_r()).abs()
+        + (color.get_g() - expected.get_g()).abs()
+        + (color.get_b() - expected.get_b()).abs();
+    assert!(difference < 1e-9);
+}
+
+#[test]
+fn checker_test() {
+    let (white, black) = (Color::new(1.0, 1.0, 1.0), Color::default());
+    let checker = Checker::from_colors(0.5, white, black);
+    assert_color(checker.value(0.0, 0.0, Point3::new(0.1, 0.1, 0.1)), white);
+    assert_color(checker.value(0.0, 0.0, Point3::new(0.6, 0.1, 0.1)), black);
+    assert_color(checker.value(0.0, 0.0, Point3::new(0.6, 0.6, 0.1)), white);
+    // Continues across the origin
+    assert_color(checker.value(0.0, 0.0, Point3::new(-0.1, 0.1, 0.1)), black);
+    assert_color(checker.value(0.0, 0.0, Point3::new(-0.1, -0.1, 0.1)), white);
+}
+
+/// Red, green on the top row; blue, white on the bottom row
+fn two_by_two() -> Image {
+    Image::from_pixels(
+        2,
+        2,
+        vec![
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+        ],
+    )
+}
+
+#[test]
+fn image_texture_filtering_test() {
+    let texture = ImageTexture::new(two_by_two(), WrapMode::Repeat);
+    let point = Point3::default();
+    // v runs upwards, so the top row is at high v
+    assert_color(texture.value(0.25, 0.75, point), Color::new(1.0, 0.0, 0.0));
+    assert_color(texture.value(0.75, 0.25, point), Color::new(1.0, 1.0, 1.0));
+    assert_color(texture.value(0.5, 0.75, point), Color::new(0.5, 0.5, 0.0));
+    assert_color(texture.value(0.5, 0.5, point), Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn image_texture_wrap_test() {
+    let point = Point3::default();
+    let (red, green) = (Color::new(1.0, 0.0, 0.0), Color::new(0.0, 1.0, 0.0));
+    let repeat = ImageTexture::new(two_by_two(), WrapMode::Repeat);
+    let clamp = ImageTexture::new(two_by_two(), WrapMode::Clamp);
+    let mirror = ImageTexture::new(two_by_two(), WrapMode::Mirror);
+
+    assert_color(repeat.value(-0.25, 0.75, point), green);
+    assert_color(clamp.value(-0.25, 0.75, point), red);
+    assert_color(mirror.value(-0.25, 0.75, point), red);
+
+    assert_color(repeat.value(-0.75, 0.75, point), red);
+    assert_color(clamp.value(-0.75, 0.75, point), red);
+    assert_color(mirror.value(-0.75, 0.75, point), green);
+    assert_color(repeat.value(1.25, 0.75, point), red);
+}
+
+#[test]
+fn image_load_test() {
+    let dir = common_config::TestDir::new("image_load_test");
+    let original = Image::from_pixels(
+        2,
+        1,
+        vec![Color::new(0.25, 0.5, 1.0), Color::new(0.0, 0.04, 0.8)],
+    );
+    original
+        .save(&dir.join("colors.png"), ImageFormat::Png)
+        .unwrap();
+
+    // Written gamma corrected, read back linear, up to 8-bit rounding
+    let loaded = Image::load(&dir.join("colors.png")).unwrap();
+    assert_eq!((loaded.get_width(), loaded.get_height()), (2, 1));
+    for (loaded, original) in loaded.pixels().iter().zip(original.pixels()) {
+        assert!((loaded.get_r() - original.get_r()).abs() < 0.01);
+        assert!((loaded.get_g() - original.get_g()).abs() < 0.01);
+        assert!((loaded.get_b() - original.get_b()).abs() < 0.01);
+    }
+
+    assert!(Image::load(&dir.join("missing.png")).is_err());
+    assert!(Image::load(&dir.join("colors.bmp")).is_err());
+}
+
+#[test]
+fn perlin_noise_test() {
+    let perlin = Perlin::new(3);
+    // Zero on the lattice, within [-1, 1] in between, and the same for the same seed
+    assert_eq!(perlin.noise(Point3::new(2.0, -3.0, 5.0)), 0.0);
+    let mut varies = false;
+    for index in 0..1000 {
+        let point = Point3::new(
+            index as f64 * 0.137,
+            index as f64 * -0.071,
+            (index % 17) as f64 * 0.59,
+        );
+        let noise = perlin.noise(point);
+        assert!((-1.0..=1.0).contains(&noise));
+        assert_eq!(noise, Perlin::new(3).noise(point));
+        varies |= noise.abs() > 0.1;
+    }
+    assert!(varies);
+
+    let marble = NoiseTexture::new(4.0, NoiseStyle::Marble, Color::new(1.0, 0.5, 0.0), 3);
+    for index in 0..100 {
+        let color = marble.value(0.0, 0.0, Point3::new(0.3, index as f64 * 0.1, 0.2));
+        assert!((0.0..=1.0).contains(&color.get_r()));
+        assert!((color.get_g() - (0.5 * color.get_r())).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn textured_material_test() {
+    let checker = Checker::from_colors(1.0, Color::new(0.8, 0.8, 0.8), Color::new(0.2, 0.2, 0.2));
+    let material = Lambertian::from_texture(Arc::new(checker));
+    let incoming = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+    let mut sampler = SamplerKind::Independent.create(1, 0);
+    sampler.start_pixel_sample(0, 0, 0);
+    for (x, expected) in [(0.5, 0.8), (1.5, 0.2)] {
+        let record = HitRecord::new(
+            Point3::new(x, 0.5, 0.5),
+            Vector3::new(0.0, 1.0, 0.0),
+            1.0,
+            true,
+            &material,
+            0.0,
+            0.0,
+        );
+        let scatter = material
+            .scatter(incoming, &record, sampler.as_mut())
+            .unwrap();
+        assert!((scatter.attenuation.get_r() - expected).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn textures_from_scene_file_test() {
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {
+                "floor": {"type": "lambertian", "albedo": {"type": "checker", "scale": 0.5,
+                    "even": [0.9, 0.9, 0.9], "odd": {"type": "noise", "style": "turbulence"}}},
+                "marble": {"type": "metal", "albedo": {"type": "noise", "scale": 2, "color": [0.9, 0.8, 0.7]}, "fuzz": 0.1},
+                "lamp": {"type": "diffuse_light", "emit": {"type": "solid", "color": [1, 0.5, 0.2]}, "intensity": 4}
+            },
+            "objects": [
+                {"type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0], "material": "floor"},
+                {"type": "sphere", "center": [0, 1, 0], "radius": 1, "material": "marble"},
+                {"type": "sphere", "center": [0, 4, 0], "radius": 0.5, "material": "lamp"}
+            ]
+        }"#,
+        Path::new(""),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.lights.len(), 1);
+    let everywhere = Interval::new(0.001, f64::INFINITY);
+    let mut sampler = SamplerKind::Independent.create(1, 2);
+
+    // The floor alternates plain squares with gray turbulence every half unit
+    let mut odd_squares = 0;
+    for x in [0.3, 0.7, 1.4, 2.6] {
+        let down = Ray::new(Point3::new(x + 3.0, 1.0, 0.2), Vector3::new(0.0, -1.0, 0.1));
+        let hit = scene.world.hit(down, everywhere).unwrap();
+        sampler.start_pixel_sample(0, 0, 0);
+        let albedo = hit
+            .material
+            .scatter(down, &hit, sampler.as_mut())
+            .unwrap()
+            .attenuation;
+        let cell = |coordinate: f64| (coordinate / 0.5).floor() as i64;
+        let point = hit.point;
+        match (cell(point.get_x()) + cell(point.get_y()) + cell(point.get_z())).rem_euclid(2) == 0 {
+            true => assert_color(albedo, Color::new(0.9, 0.9, 0.9)),
+            false => {
+                assert_eq!(albedo.get_r(), albedo.get_g());
+                assert_eq!(albedo.get_g(), albedo.get_b());
+                assert_ne!(albedo.get_r(), 0.9);
+                odd_squares += 1;
+            }
+        }
+    }
+    assert!(odd_squares > 0 && odd_squares < 4);
+
+    // The marble veins scale its tint, and the fuzz blurs the reflection a little
+    let towards = |y: f64| Ray::new(Point3::new(0.1, y, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut veins = Vec::new();
+    for y in [0.6, 1.2, 1.6] {
+        let hit = scene.world.hit(towards(y), everywhere).unwrap();
+        let mirror = towards(y).get_direction().reflection(&hit.normal);
+        for sample_index in 0..16 {
+            sampler.start_pixel_sample(0, 0, sample_index);
+            let Some(scatter) = hit.material.scatter(towards(y), &hit, sampler.as_mut()) else {
+                continue;
+            };
+            assert!(scatter.pdf.is_none());
+            let offset = scatter.scattered_ray.get_direction() - mirror;
+            assert!(offset.length() <= 0.1 + 1e-12);
+            let tint = scatter.attenuation;
+            assert!((tint.get_r() * 0.8 - tint.get_g() * 0.9).abs() < 1e-12);
+            assert!((tint.get_g() * 0.7 - tint.get_b() * 0.8).abs() < 1e-12);
+            veins.push(tint.get_r());
+        }
+    }
+    assert!(veins.iter().any(|&red| (red - veins[0]).abs() > 0.01));
+
+    // The lamp glows at four times its color and scatters nothing
+    let hit = scene.world.hit(towards(4.1), everywhere).unwrap();
+    assert_color(
+        hit.material.emitted(hit.u, hit.v, hit.point),
+        Color::new(4.0, 2.0, 0.8),
+    );
+    assert!(hit
+        .material
+        .scatter(towards(4.1), &hit, sampler.as_mut())
+        .is_none());
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"floor": {"type": "lambertian", "albedo": {"type": "image", "file": "missing.png"}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::Resource));
+    assert_eq!(error.get_path(), "materials.floor.albedo.file");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1,
+            "material": {"type": "lambertian", "albedo": {"type": "checker", "even": [1, 1, 1], "odd": [1.5, 0, 0]}}}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "objects[0].material.albedo.odd");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"floor": {"type": "lambertian", "albedo": {"type": "image", "file": "floor.png", "wrap": "tile"}}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "materials.floor.albedo.wrap");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "materials": {"floor": {"type": "lambertian", "albedo": "red"}}, "objects": []}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::WrongType));
+    assert_eq!(error.get_path(), "materials.floor.albedo");
+}
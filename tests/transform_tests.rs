@@ -0,0 +1,201 @@
+use std::{fs, path::Path, sync::Arc};
+
+use lib::utilities::{
+    aabb::Aabb,
+    geometry::{Hittable, Sphere},
+    instance::Instance,
+    interval::Interval,
+    material::Lambertian,
+    point::Point3,
+    ray::Ray,
+    scene_error::SceneErrorKind,
+    scenes,
+    transform::Transform,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+const EVERYWHERE: Interval = Interval::new(0.001, f64::INFINITY);
+
+fn assert_close(a: Vector3, b: Vector3) {
+    assert!((a - b).length() < 1e-9);
+}
+
+#[test]
+fn compose_and_inverse_test() {
+    let transform = Transform::scale(Vector3::new(2.0, 3.0, 0.5))
+        .then(&Transform::rotate(Vector3::new(1.0, 1.0, 0.0), 35.0))
+        .then(&Transform::translate(Vector3::new(1.0, -2.0, 4.0)));
+    let point = Point3::new(0.3, -1.2, 2.5);
+    let round_trip = transform
+        .inverse()
+        .apply_point(transform.apply_point(point));
+    assert_close(round_trip.as_vec(), point.as_vec());
+
+    // Steps apply in order: scale first, then translate
+    let scale_then_move = Transform::scale(Vector3::new(2.0, 2.0, 2.0))
+        .then(&Transform::translate(Vector3::new(1.0, 0.0, 0.0)));
+    let moved = scale_then_move.apply_point(Point3::new(1.0, 0.0, 0.0));
+    assert_close(moved.as_vec(), Vector3::new(3.0, 0.0, 0.0));
+    // Directions ignore the translation
+    let direction = scale_then_move.apply_vector(Vector3::new(0.0, 1.0, 0.0));
+    assert_close(direction, Vector3::new(0.0, 2.0, 0.0));
+}
+
+#[test]
+fn rotate_test() {
+    let rotated = Transform::rotate_y(90.0).apply_vector(Vector3::new(1.0, 0.0, 0.0));
+    assert_close(rotated, Vector3::new(0.0, 0.0, -1.0));
+    let rotated = Transform::rotate_z(90.0).apply_vector(Vector3::new(1.0, 0.0, 0.0));
+    assert_close(rotated, Vector3::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn normal_under_scale_test() {
+    // The plane x + y = 0 squashed along x keeps its normal perpendicular to it
+    let transform = Transform::scale(Vector3::new(0.25, 1.0, 1.0));
+    let tangent = transform.apply_vector(Vector3::new(1.0, -1.0, 0.0));
+    let normal = transform.apply_normal(Vector3::new(1.0, 1.0, 0.0));
+    assert!(tangent.dot_prod(normal).abs() < 1e-12);
+}
+
+#[test]
+fn look_at_test() {
+    let from = Point3::new(1.0, 2.0, 3.0);
+    let transform = Transform::look_at(
+        from,
+        Point3::new(1.0, 2.0, -5.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    assert_close(
+        transform.apply_point(Point3::default()).as_vec(),
+        from.as_vec(),
+    );
+    let forward = transform.apply_vector(Vector3::new(0.0, 0.0, 1.0));
+    assert_close(forward, Vector3::new(0.0, 0.0, -1.0));
+    let up = transform.apply_vector(Vector3::new(0.0, 1.0, 0.0));
+    assert_close(up, Vector3::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn bounding_box_test() {
+    let unit = Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    let bbox = Transform::rotate_z(45.0)
+        .then(&Transform::translate(Vector3::new(5.0, 0.0, 0.0)))
+        .apply_bbox(&unit);
+    let half_diagonal = 2.0_f64.sqrt();
+    assert!((bbox.x.min - (5.0 - half_diagonal)).abs() < 1e-9);
+    assert!((bbox.x.max - (5.0 + half_diagonal)).abs() < 1e-9);
+    assert!((bbox.y.max - half_diagonal).abs() < 1e-9);
+    assert!((bbox.z.max - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn instance_hit_test() {
+    let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point3::default(),
+        1.0,
+        Arc::new(Lambertian::default()),
+    ));
+    // A unit sphere stretched into an ellipsoid 4 wide, then moved to x = 10
+    let transform = Transform::scale(Vector3::new(2.0, 1.0, 1.0))
+        .then(&Transform::rotate_y(90.0))
+        .then(&Transform::translate(Vector3::new(10.0, 0.0, 0.0)));
+    let instance = Instance::new(sphere.clone(), transform);
+
+    // Rotated about y, the long axis now runs along z
+    let ray = Ray::new(Point3::new(10.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = instance.hit(ray, EVERYWHERE).unwrap();
+    assert!((hit.parameter - 3.0).abs() < 1e-9);
+    assert_close(hit.point.as_vec(), Vector3::new(10.0, 0.0, 2.0));
+    assert_close(hit.normal, Vector3::new(0.0, 0.0, 1.0));
+
+    // Off the pole the normal is the ellipsoid's, not the sphere's
+    let ray = Ray::new(Point3::new(10.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = instance.hit(ray, EVERYWHERE).unwrap();
+    let local = hit.point - Point3::new(10.0, 0.0, 0.0);
+    let expected = Vector3::new(local.get_x(), 0.0, local.get_z() / 4.0).unit_vector();
+    assert_close(hit.normal, expected);
+    // Tangents are carried along and stay in the surface
+    assert!(hit.dpdu.dot_prod(hit.normal).abs() < 1e-9);
+    assert!(hit.dpdv.dot_prod(hit.normal).abs() < 1e-9);
+
+    let beside = Ray::new(Point3::new(11.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(instance.hit(beside, EVERYWHERE).is_none());
+    let bbox = instance.bounding_box();
+    assert!((bbox.z.max - 2.0).abs() < 1e-9 && (bbox.x.min - 9.0).abs() < 1e-9);
+}
+
+const TRIANGLE_OBJ: &str = "v -1.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 2.0 0.0
+f 1 2 3
+";
+
+#[test]
+fn instances_from_scene_file_test() {
+    let dir = common_config::TestDir::new("instances_from_scene_file_test");
+    fs::write(dir.join("triangle.obj"), TRIANGLE_OBJ).unwrap();
+
+    let scene = scenes::scene_from_str(
+        r#"{
+            "version": 1,
+            "materials": {"grey": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}},
+            "objects": [
+                {"type": "instance", "object": {"type": "mesh", "file": "triangle.obj", "material": "grey"},
+                 "transform": [{"type": "translate", "offset": [-3, 0, 0]}]},
+                {"type": "instance", "object": {"type": "mesh", "file": "triangle.obj", "material": "grey"},
+                 "transform": [{"type": "scale", "factor": 2}, {"type": "rotate", "axis": [0, 1, 0], "degrees": 180}]},
+                {"type": "instance", "object": {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "grey"},
+                 "transform": [{"type": "look_at", "from": [0, 5, 0], "at": [0, 0, 0], "up": [0, 0, 1]}]}
+            ]
+        }"#,
+        dir.path(),
+        true,
+    )
+    .unwrap();
+    assert_eq!(scene.world.len(), 3);
+
+    let towards = |x: f64, y: f64| Ray::new(Point3::new(x, y, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(scene.world.hit(towards(-3.0, 1.0), EVERYWHERE).is_some());
+    assert!(scene.world.hit(towards(0.0, 3.5), EVERYWHERE).is_some());
+    assert!(scene.world.hit(towards(1.5, 3.5), EVERYWHERE).is_none());
+    let hit = scene.world.hit(towards(0.0, 5.0), EVERYWHERE).unwrap();
+    assert!((hit.parameter - 4.0).abs() < 1e-9);
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "instance",
+            "object": {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": {"type": "lambertian", "albedo": [1, 1, 1]}},
+            "transform": [{"type": "translate", "offset": [1, 0, 0]}, {"type": "scale", "factor": [1, 0, 1]}]}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::OutOfRange));
+    assert_eq!(error.get_path(), "objects[0].transform[1].factor");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "instance",
+            "object": {"type": "sphere", "center": [0, 0, 0], "radius": -1, "material": {"type": "lambertian", "albedo": [1, 1, 1]}},
+            "transform": []}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.get_path(), "objects[0].object.radius");
+
+    let error = scenes::scene_from_str(
+        r#"{"version": 1, "objects": [{"type": "instance",
+            "object": {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": {"type": "lambertian", "albedo": [1, 1, 1]}},
+            "transform": [{"type": "spin"}]}]}"#,
+        Path::new(""),
+        false,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error.get_kind(), SceneErrorKind::UnknownType));
+    assert_eq!(error.get_path(), "objects[0].transform[0].type");
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use lib::utilities::{
+    geometry::{Hittable, Triangle},
+    interval::Interval,
+    material::Lambertian,
+    point::Point3,
+    ray::Ray,
+    vector3::Vector3,
+};
+
+mod common_config;
+
+fn unit_quad() -> Vec<Box<dyn Hittable>> {
+    let material = Arc::new(Lambertian::default());
+    let (p0, p1, p2, p3) = (
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(1.0, 1.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+    );
+    vec![
+        Box::new(Triangle::new(p0, p1, p2, material.clone())),
+        Box::new(Triangle::new(p0, p2, p3, material)),
+    ]
+}
+
+#[test]
+fn triangle_hit_test() {
+    let triangle = Triangle::new(
+        Point3::new(-1.0, -1.0, 0.0),
+        Point3::new(1.0, -1.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::default()),
+    );
+    let ray = Ray::new(Point3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = triangle
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .expect("ray should hit the triangle");
+
+    assert_eq!(hit.parameter, 2.0);
+    assert!(hit.is_face_front);
+    assert_eq!(hit.normal.get_z(), 1.0);
+}
+
+#[test]
+fn triangle_miss_test() {
+    let triangle = Triangle::new(
+        Point3::new(-1.0, -1.0, 0.0),
+        Point3::new(1.0, -1.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::default()),
+    );
+    let ray = Ray::new(Point3::new(0.9, 0.9, 2.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(triangle
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .is_none());
+}
+
+#[test]
+fn triangle_bounding_box_test() {
+    // Two vertices share x and z; the box must still be tight on those axes
+    let triangle = Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 2.0, 0.0),
+        Point3::new(1.0, 1.0, 3.0),
+        Arc::new(Lambertian::default()),
+    );
+    let bounds = triangle.bounding_box();
+    for (axis, (min, max)) in [(0.0, 1.0), (0.0, 2.0), (0.0, 3.0)].into_iter().enumerate() {
+        assert_eq!(bounds.axis_interval(axis).min, min);
+        assert_eq!(bounds.axis_interval(axis).max, max);
+    }
+
+    // Only a truly flat axis is padded
+    let flat = Triangle::new(
+        Point3::new(0.0, 0.0, 1.0),
+        Point3::new(1.0, 0.0, 1.0),
+        Point3::new(0.0, 1.0, 1.0),
+        Arc::new(Lambertian::default()),
+    );
+    let z = flat.bounding_box().axis_interval(2);
+    assert!(z.min < 1.0 && z.max > 1.0 && z.size() < 0.01);
+    assert_eq!(flat.bounding_box().axis_interval(0).min, 0.0);
+}
+
+#[test]
+fn triangle_shared_edge_watertight_test() {
+    let quad = unit_quad();
+
+    // Rays exactly along the shared diagonal must hit at least one of the two triangles
+    let direction = Vector3::new(0.3, -0.7, -1.0);
+    for step in 1..100 {
+        let t = step as f64 / 100.0;
+        let ray = Ray::new(Point3::new(t, t, 0.0) - direction, direction);
+        assert!(quad.hit(ray, Interval::new(0.001, f64::INFINITY)).is_some());
+    }
+}
+
+#[test]
+fn triangle_interpolates_uvs_and_normals_test() {
+    let triangle = Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::default()),
+    )
+    .with_vertex_uvs([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)])
+    .with_vertex_normals([
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(1.0, 0.0, 1.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    ]);
+    let ray = Ray::new(Point3::new(0.25, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = triangle
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .expect("ray should hit the triangle");
+
+    assert!((hit.u - 0.25).abs() < 1e-12);
+    assert!((hit.v - 0.5).abs() < 1e-12);
+    assert!(hit.normal.get_x() > 0.0);
+}